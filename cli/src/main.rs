@@ -1,96 +1,511 @@
 use clap::{Parser, Subcommand};
-use std::error::Error;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use svl_core::{
-    client::{HttpStatsClient, TextInfo},
-    db::{val, DBConnection, DBParams},
-    stats::Stats,
+    client::{HttpStatsClient, RetryPolicy},
+    db::{val, DBConnection, DBParams, DataValue},
+    embeddings,
+    export::{self, ShardBy},
+    import::{self, ImportOptions, ImportProgress},
+    queries::{Query, QueryCommand},
+    stats::{Stats, WeightScale},
+    text::{self, LatinTokenizer, TextId, TokenMode},
 };
 
 mod repl;
+mod server;
+mod settings;
 
 #[derive(Parser)]
 #[command(author,version,about,long_about=None)]
 struct Cli {
     #[clap(subcommand)]
     command: CLICommand,
+
+    #[clap(
+        long,
+        global = true,
+        default_value = DBConnection::DEFAULT_PATH,
+        help = "Path of the database"
+    )]
+    db: PathBuf,
+
+    #[clap(
+        long,
+        global = true,
+        conflicts_with = "db",
+        help = "Use an empty in-memory DB instead of a database on disk (nothing is saved)"
+    )]
+    in_memory: bool,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Fail queries taking longer than this many seconds"
+    )]
+    query_timeout_secs: Option<u64>,
 }
 
 #[derive(Subcommand)]
 enum CLICommand {
-    #[clap(about = "Create the database + schema")]
-    CreateDB,
+    #[clap(about = "Create the database + schema (skips already existing relations)")]
+    CreateDB {
+        #[clap(long, help = "Drop existing relations and all their data first")]
+        force: bool,
+    },
 
     #[clap(about = "Import Latin library texts and calculate stats")]
-    ImportLibrary,
+    ImportLibrary(ImportArgs),
+
+    #[clap(about = "Export word counts as CSV")]
+    ExportWords(ExportWordsArgs),
+
+    #[clap(about = "Export relations as a portable JSON file")]
+    Export {
+        #[clap(help = "JSON file to write")]
+        path: PathBuf,
+
+        #[clap(
+            long = "relation",
+            default_values_t = ["Author".to_string(), "Text".to_string(), "Word".to_string()],
+            help = "Relation to export (repeatable)"
+        )]
+        relations: Vec<String>,
+    },
+
+    #[clap(about = "Import relations from a JSON file written by `export`")]
+    Import {
+        #[clap(help = "JSON file to read")]
+        path: PathBuf,
+    },
+
+    #[clap(
+        about = "Import text embeddings for /nearest from a JSON object of text ids to vectors"
+    )]
+    ImportEmbeddings {
+        #[clap(help = "JSON file to read, e.g. {\"1\": [0.1, 0.2], \"2\": [0.3, 0.1]}")]
+        path: PathBuf,
+    },
 
     #[clap(about = "Delete filtered words from DB")]
-    DeleteFilteredWords,
+    DeleteFilteredWords {
+        #[clap(
+            long,
+            help = "File with the words to delete, one per line (defaults to the library's page boilerplate words)"
+        )]
+        stopwords: Option<PathBuf>,
+    },
+
+    #[clap(about = "Run a single predefined query, e.g. `svl query top am 10`")]
+    Query {
+        #[clap(required = true, help = "Query command followed by its arguments")]
+        query: Vec<String>,
+
+        #[clap(long, help = "Seed for `sample`, to draw the same sample again")]
+        seed: Option<u64>,
+    },
 
     #[clap(about = "Run interactive REPL")]
-    Repl,
+    Repl {
+        #[clap(long, help = "Load rules from this file instead of ./rules.datalog")]
+        rules: Option<PathBuf>,
+    },
 
     #[clap(about = "Run interactive UI")]
     Ui,
+
+    #[clap(
+        about = "Serve the predefined queries as a JSON HTTP API, e.g. GET /words/top?prefix=am"
+    )]
+    Serve {
+        #[clap(long, default_value = "127.0.0.1:8080", help = "Address to listen on")]
+        addr: String,
+    },
+}
+
+impl CLICommand {
+    /// Whether the command only works on a DB with the schema created by `create-db`.
+    fn reads_stats(&self) -> bool {
+        matches!(
+            self,
+            CLICommand::ExportWords(_)
+                | CLICommand::Query { .. }
+                | CLICommand::Ui
+                | CLICommand::Serve { .. }
+        )
+    }
+}
+
+#[derive(clap::Args)]
+struct ImportArgs {
+    #[clap(
+        long,
+        default_value = HttpStatsClient::DEFAULT_BASE_URL,
+        help = "Import from a mirror of the library at this URL"
+    )]
+    base_url: String,
+
+    #[clap(
+        long,
+        default_value_t = 3,
+        help = "Retry failed requests (server errors, timeouts) this many times"
+    )]
+    retries: usize,
+
+    #[clap(
+        long,
+        default_value_t = 30,
+        help = "Give up on requests taking longer than this many seconds"
+    )]
+    timeout_secs: u64,
+
+    #[clap(
+        long,
+        default_value_t = HttpStatsClient::DEFAULT_CONCURRENCY,
+        help = "Maximum number of concurrent requests"
+    )]
+    concurrency: usize,
+
+    #[clap(long, help = "Skip texts already stored by an earlier (failed) import")]
+    resume: bool,
+
+    #[clap(long, help = "Cache downloaded texts in this directory")]
+    html_cache_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        requires = "html_cache_dir",
+        help = "Download texts again instead of using cached ones"
+    )]
+    refresh_html_cache: bool,
+
+    #[clap(
+        long,
+        help = "Commit stats in batches of this many rows instead of a single transaction"
+    )]
+    batch_size: Option<usize>,
+
+    #[clap(
+        long = "exclude-author",
+        help = "Skip texts of this author (repeatable)"
+    )]
+    exclude_authors: Vec<String>,
+
+    #[clap(
+        long = "exclude-url",
+        help = "Skip texts with URLs matching this regex (repeatable)"
+    )]
+    exclude_urls: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Keep numbers (e.g. dates) as words instead of dropping digits"
+    )]
+    keep_numbers: bool,
+
+    #[clap(
+        long,
+        help = "Count enclitics (-que, -ve, -ne) as separate words, e.g. arma + que for armaque"
+    )]
+    split_enclitics: bool,
+
+    #[clap(
+        long,
+        default_value_t = LatinTokenizer::DEFAULT_MIN_WORD_LEN,
+        help = "Drop words shorter than this many characters, e.g. 2 to skip single letters"
+    )]
+    min_word_len: usize,
+
+    #[clap(
+        long,
+        help = "Count the orthographic variants v and j as u and i, e.g. iuuenis for juvenis"
+    )]
+    fold_orthography: bool,
+
+    #[clap(
+        long,
+        help = "Also store the lemma of every word, e.g. for the /lemma query"
+    )]
+    lemmatize: bool,
+
+    #[clap(
+        long,
+        help = "Also store counts of consecutive word pairs, e.g. for the /collocations query"
+    )]
+    bigrams: bool,
+
+    #[clap(
+        long,
+        help = "File with words to skip instead of storing them, one per line"
+    )]
+    stopwords: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value_t = 24,
+        help = "Reuse cached author and text lists fetched within this many hours"
+    )]
+    index_ttl_hours: u64,
+
+    #[clap(
+        long,
+        help = "Ignore cached author and text lists and fetch them again"
+    )]
+    refresh_index: bool,
+
+    #[clap(long, help = "Export word cloud data as JSON to this file")]
+    word_cloud: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value_t = 100,
+        help = "Number of words in the word cloud"
+    )]
+    word_cloud_top: usize,
+
+    #[clap(long, help = "Use logarithmic instead of linear word cloud weights")]
+    word_cloud_log: bool,
+}
+
+impl ImportArgs {
+    fn index_ttl(&self) -> Duration {
+        if self.refresh_index {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(self.index_ttl_hours * 60 * 60)
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct ExportWordsArgs {
+    #[clap(
+        long,
+        required_unless_present = "output_dir",
+        conflicts_with = "output_dir",
+        help = "Write all rows into this CSV file"
+    )]
+    output: Option<PathBuf>,
+
+    #[clap(long, help = "Write one CSV file per shard into this directory")]
+    output_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value = "letter",
+        help = "Shard rows by first letter of the word or by author (letter, author)"
+    )]
+    shard_by: ShardBy,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    // logs go to stderr, e.g. RUST_LOG=svl::client=debug traces the HTTP requests of imports
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
     let cli = Cli::parse();
-    let db = DBConnection::new()?;
+    let db = if cli.in_memory {
+        let db = DBConnection::new_in_memory()?;
+        db.create_schema().await?;
+        db
+    } else {
+        DBConnection::with_path(&cli.db)?
+    };
+    let db = match cli.query_timeout_secs {
+        Some(secs) => db.with_timeout(Duration::from_secs(secs)),
+        None => db,
+    };
+
+    match db.path() {
+        Some(path) => eprintln!("Using database {}", path.display()),
+        None => eprintln!("Using in-memory database"),
+    }
+
+    if cli.command.reads_stats() {
+        db.verify_schema().await?;
+    }
 
     match cli.command {
-        CLICommand::CreateDB => create_schema(&db).await?,
-        CLICommand::ImportLibrary => fetch_and_store_stats(&db).await?,
-        CLICommand::DeleteFilteredWords => delete_filtered_words(&db).await?,
-        CLICommand::Repl => repl::run_repl(&db).await?,
-        CLICommand::Ui => svl_ui::run_ui(db)?,
+        CLICommand::CreateDB { force } => create_schema(&db, force).await?,
+        CLICommand::ImportLibrary(args) => {
+            let stats = fetch_and_store_stats(&db, &args).await?;
+            if let Some(path) = &args.word_cloud {
+                let scale = if args.word_cloud_log {
+                    WeightScale::Log
+                } else {
+                    WeightScale::Linear
+                };
+                export_word_cloud(&stats, path, args.word_cloud_top, scale)?;
+            }
+            db.compact().await?;
+        }
+        CLICommand::ExportWords(args) => export_words(&db, &args).await?,
+        CLICommand::Export { path, relations } => export_relations(&db, &path, &relations).await?,
+        CLICommand::Import { path } => {
+            import_relations(&db, &path).await?;
+            db.compact().await?;
+        }
+        CLICommand::ImportEmbeddings { path } => import_embeddings(&db, &path).await?,
+        CLICommand::DeleteFilteredWords { stopwords } => {
+            delete_filtered_words(&db, stopwords.as_deref()).await?
+        }
+        CLICommand::Query { query, seed } => run_query(&db, &query, seed).await?,
+        CLICommand::Repl { rules } => repl::run_repl(&db, rules).await?,
+        CLICommand::Ui => svl_ui::run_ui(db.clone())?,
+        CLICommand::Serve { addr } => server::run_server(&db, &addr).await?,
     }
 
+    db.close().await?;
     Ok(())
 }
 
-async fn create_schema(db: &DBConnection) -> Result<(), Box<dyn Error>> {
+async fn create_schema(db: &DBConnection, force: bool) -> Result<(), Box<dyn Error>> {
     println!("Creating DB with schema");
 
-    let tx = db.multi_tx(true);
+    let created = if force {
+        db.recreate_schema().await?
+    } else {
+        db.create_schema().await?
+    };
 
-    tx.run_script(
-        ":create Author { author_id: Int, name: String => url: String }",
-        Default::default(),
-    )?;
+    if created.is_empty() {
+        println!("All relations exist already, nothing to do.");
+    } else {
+        println!("Created relations: {}", created.join(", "));
+    }
 
-    tx.run_script(
-        ":create Word { word: String, text_id: Int => count: Int }",
-        Default::default(),
-    )?;
+    match db.path() {
+        Some(path) => println!("Success. DB saved to {}", path.display()),
+        None => println!("Success."),
+    }
 
-    tx.run_script(
-        ":create Text { text_id: Int, author_id: Int => url: String, text: String }",
-        Default::default(),
-    )?;
+    Ok(())
+}
 
-    tx.commit().await?;
+async fn run_query(
+    db: &DBConnection,
+    query: &[String],
+    seed: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let (cmd, args) = query.split_first().ok_or("Missing query command")?;
+    // parse strictly, so that typos fail right away instead of at evaluation
+    let cmd = cmd.trim_start_matches('/').parse::<QueryCommand>()?;
+    if matches!(
+        cmd,
+        QueryCommand::Quit | QueryCommand::Exit | QueryCommand::Clear
+    ) {
+        return Err(format!("{} is only available in the REPL", cmd).into());
+    }
+    let query = Query::new(cmd.to_string(), args.to_vec()).with_seed(seed);
+
+    let named_rows = query.eval(db).await?;
+    repl::result_table(&named_rows).printstd();
+
+    Ok(())
+}
 
-    println!("Success. DB saved to svl-stats.db");
+async fn export_words(db: &DBConnection, args: &ExportWordsArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(output_dir) = &args.output_dir {
+        let rows = export::word_rows(db).await?;
+        let paths = export::export_rows_sharded(&rows, output_dir, args.shard_by)?;
+        println!(
+            "Exported {} rows into {} files in {}",
+            rows.rows.len(),
+            paths.len(),
+            output_dir.display()
+        );
+    } else if let Some(output) = &args.output {
+        // a single file can be written page by page, without holding all rows in memory
+        let count = export::export_word_rows(db, output).await?;
+        println!("Exported {} rows to {}", count, output.display());
+    }
+
+    Ok(())
+}
+
+async fn export_relations(
+    db: &DBConnection,
+    path: &Path,
+    relations: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let relations: Vec<&str> = relations.iter().map(String::as_str).collect();
+    let exported = db.export_relations(&relations).await?;
+
+    std::fs::write(path, serde_json::to_string(&exported)?)?;
+    println!("Exported {} to {}", relations.join(", "), path.display());
+
+    Ok(())
+}
+
+async fn import_relations(db: &DBConnection, path: &Path) -> Result<(), Box<dyn Error>> {
+    let data: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    db.import_relations(&data).await?;
+    println!("Imported {}", path.display());
 
     Ok(())
 }
 
-async fn delete_filtered_words(db: &DBConnection) -> Result<(), Box<dyn Error>> {
+async fn import_embeddings(db: &DBConnection, path: &Path) -> Result<(), Box<dyn Error>> {
+    let data: BTreeMap<usize, Vec<f32>> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let embeddings: Vec<(TextId, Vec<f32>)> = data
+        .into_iter()
+        .map(|(text_id, embedding)| (text_id.into(), embedding))
+        .collect();
+    let Some((_, first)) = embeddings.first() else {
+        return Err(format!("No embeddings in {}", path.display()).into());
+    };
+
+    if embeddings::create_embedding_schema(db, first.len()).await? {
+        println!(
+            "Created {} relation and index",
+            embeddings::TEXT_EMBEDDING_RELATION
+        );
+    }
+    embeddings::store_text_embeddings(db, &embeddings).await?;
+    println!(
+        "Imported {} embeddings from {}",
+        embeddings.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Words from the library's page navigation and footer that end up in every text.
+const DEFAULT_FILTERED_WORDS: [&str; 4] = ["br", "classics", "latin", "library"];
+
+async fn delete_filtered_words(
+    db: &DBConnection,
+    stopwords: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let words: BTreeSet<String> = match stopwords {
+        Some(path) => text::load_stopwords(path)?,
+        None => DEFAULT_FILTERED_WORDS
+            .iter()
+            .map(|w| w.to_string())
+            .collect(),
+    };
+    println!("Deleting {} filtered words", words.len());
+
+    let filtered_words = DataValue::List(
+        words
+            .into_iter()
+            .map(|word| DataValue::List(vec![val(word)]))
+            .collect(),
+    );
+
     let tx = db.multi_tx(true);
 
     tx.run_script(
         "
-        filtered_word[word] <- [
-            ['br'],
-            ['classics'],
-            ['latin'],
-            ['library'],
-        ];
+        filtered_word[word] <- $filtered_words;
         del_word[word,text_id] := *Word{ word, text_id }, filtered_word[word];
         ?[word,text_id] := del_word[word,text_id]; :rm Word { word, text_id }
         ",
-        Default::default(),
+        DBParams::from_iter(vec![("filtered_words".into(), filtered_words)]),
     )?;
 
     tx.commit().await?;
@@ -98,78 +513,93 @@ async fn delete_filtered_words(db: &DBConnection) -> Result<(), Box<dyn Error>>
     Ok(())
 }
 
-async fn fetch_and_store_stats(db: &DBConnection) -> Result<(), Box<dyn Error>> {
-    let mut stats = Stats::new();
-    let client = HttpStatsClient::new()?;
-    let mut authors = client.get_authors().await?;
-    let mut text_futures = Vec::with_capacity(authors.len());
+fn export_word_cloud(
+    stats: &Stats,
+    path: &Path,
+    top_n: usize,
+    scale: WeightScale,
+) -> Result<(), Box<dyn Error>> {
+    let words: Vec<serde_json::Value> = stats
+        .word_cloud_data(top_n, scale)
+        .into_iter()
+        .map(|(word, weight)| serde_json::json!({ "word": word, "weight": weight }))
+        .collect();
+
+    std::fs::write(path, serde_json::to_string_pretty(&words)?)?;
+    println!("Word cloud data written to {}", path.display());
 
-    let tx = db.multi_tx(true);
+    Ok(())
+}
 
-    for (idx, author) in authors.iter().enumerate() {
-        text_futures.push(client.get_texts(author));
-
-        tx.run_script(
-            "
-            ?[author_id, name, url] <- [$props];
-            :put Author { author_id, name => url }
-            ",
-            DBParams::from_iter(vec![(
-                "props".into(),
-                val(vec![
-                    val(idx as i64),
-                    val(author.name.clone()),
-                    val(author.url.clone()),
-                ]),
-            )]),
-        )?;
+async fn fetch_and_store_stats(
+    db: &DBConnection,
+    args: &ImportArgs,
+) -> Result<Stats, Box<dyn Error>> {
+    let mut client = HttpStatsClient::with_base_url(args.base_url.as_str())?
+        .with_retry_policy(RetryPolicy {
+            max_retries: args.retries,
+            ..RetryPolicy::default()
+        })
+        .with_timeout(Duration::from_secs(args.timeout_secs))?
+        .with_concurrency(args.concurrency)?;
+    if let Some(dir) = &args.html_cache_dir {
+        client = client.with_cache_dir(dir.clone());
+        if args.refresh_html_cache {
+            client = client.refresh_cache();
+        }
     }
 
-    tx.commit().await?;
+    let options = ImportOptions {
+        batch_size: args.batch_size,
+        exclude_authors: args.exclude_authors.clone(),
+        exclude_urls: args.exclude_urls.clone(),
+        token_mode: if args.keep_numbers {
+            TokenMode::Alphanumeric
+        } else {
+            TokenMode::Alphabetic
+        },
+        split_enclitics: args.split_enclitics,
+        min_word_len: args.min_word_len,
+        fold_orthography: args.fold_orthography,
+        index_ttl: args.index_ttl(),
+        resume: args.resume,
+        lemmatize: args.lemmatize,
+        bigrams: args.bigrams,
+        stopwords: match &args.stopwords {
+            Some(path) => text::load_stopwords(path)?,
+            None => BTreeSet::new(),
+        },
+        concurrency: args.concurrency,
+    };
+
+    let stats = import::import_library(db, &client, &options, print_progress).await?;
 
-    // collect text futures and set on corresponding author
-    let mut author_texts: Vec<Vec<TextInfo>> = Vec::with_capacity(authors.len());
-    for text_future in text_futures {
-        let texts: Vec<TextInfo> = text_future.await?;
-        author_texts.push(texts);
-    }
-
-    assert_eq!(author_texts.len(), authors.len());
+    println!("Final stats: {}", stats);
+    Ok(stats)
+}
 
-    for (idx, texts) in author_texts.into_iter().enumerate() {
-        if let Some(author) = authors.get_mut(idx) {
-            author.texts = texts;
+fn print_progress(progress: ImportProgress) {
+    match progress {
+        ImportProgress::FetchingAuthors => println!("Fetching authors"),
+        ImportProgress::AuthorFetched { name, texts } => {
+            print!("{}", name);
+            if texts > 0 {
+                print!("\n  {} 📕", texts);
+            }
+            println!();
         }
-    }
-
-    for author in &authors {
-        print!("{}", author.name);
-        if !author.texts.is_empty() {
-            print!("\n  {} 📕", author.texts.len());
+        ImportProgress::FetchingText { url } => println!("Fetching {}", url),
+        ImportProgress::TextFetched { .. } => {}
+        ImportProgress::TextSkipped { url, reason } => println!("Skipping {} ({})", url, reason),
+        ImportProgress::Resuming { skipped } => {
+            println!(
+                "Resuming import, skipping {} already imported texts",
+                skipped
+            )
         }
-        println!();
-    }
-
-    println!();
-    println!();
-
-    let mut text_futures = Vec::with_capacity(authors.len());
-
-    for (author_id, author) in authors.iter().enumerate() {
-        for text_info in &author.texts {
-            println!("Fetching {}", text_info.url);
-            text_futures.push((author_id, client.fetch_text(&text_info.url)));
+        ImportProgress::Stored { texts, words } => {
+            println!("Stored {} texts with {} unique words", texts, words)
         }
+        ImportProgress::Warning(warning) => eprintln!("Warning: {}", warning),
     }
-
-    for (author_id, tf) in text_futures {
-        let mut text = tf.await?;
-        text.author_id = Some(author_id);
-        stats.add_text(text);
-    }
-
-    stats.store_in_db(db).await?;
-
-    println!("Final stats: {}", stats);
-    Ok(())
 }
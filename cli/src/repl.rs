@@ -1,22 +1,108 @@
-use prettytable::{Cell, Row, Table};
+use prettytable::{format::Alignment, Cell, Row, Table};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use rustyline::completion::{Completer, Pair};
 use rustyline::config::Configurer;
 use rustyline::error::ReadlineError;
 use rustyline::history::FileHistory;
 use rustyline::validate::MatchingBracketValidator;
 use rustyline::{
-    Cmd, CompletionType, Config, EditMode, Editor, EventHandler, KeyCode, KeyEvent, Modifiers,
+    Cmd, CompletionType, ConditionalEventHandler, Config, Context, EditMode, Editor, Event,
+    EventContext, EventHandler, KeyCode, KeyEvent, Modifiers, RepeatCount,
 };
 use rustyline_derive::{Completer, Helper, Highlighter, Hinter, Validator};
 
-use svl_core::db::{DBConnection, DBError};
-use svl_core::queries::{Query, QueryError};
+use svl_core::db::{format_data_value, DBConnection, DBError, DataValue, NamedRows};
+use svl_core::errors::SVLError;
+use svl_core::export;
+use svl_core::queries::{Query, QueryCommand, QueryError};
+use svl_core::{LoadRulesFrom, RulesScript};
+
+use crate::settings::{OutputFormat, Settings, SettingsError};
 use thiserror::Error;
 
 #[derive(Completer, Helper, Highlighter, Hinter, Validator)]
 struct InputValidator {
     #[rustyline(Validator)]
     brackets: MatchingBracketValidator,
+    #[rustyline(Completer)]
+    commands: CommandCompleter,
+}
+
+/// Commands handled by the REPL itself rather than by `Query::eval`.
+const REPL_COMMANDS: [&str; 4] = ["export", "load", "plan", "set"];
+
+/// Help for `REPL_COMMANDS`, shown by `/help` after the queries' own help.
+const REPL_HELP: [(&str, &str); 4] = [
+    (
+        "/export <file>",
+        "Export the result of the previous query to a CSV file",
+    ),
+    (
+        "/load <file>",
+        "Run a Datalog script from a file, with the loaded rules",
+    ),
+    ("/plan <query>", "Show the query plan for a Datalog query"),
+    (
+        "/set ?<key> ?<value>",
+        "Show or change REPL settings: output_format (table, csv, json), default_limit, case_sensitive",
+    ),
+];
+
+/// Completes `/`-commands, taking the query commands from `QueryCommand::all`.
+struct CommandCompleter;
+
+impl Completer for CommandCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let candidates = command_completions(&line[..pos])
+            .into_iter()
+            .map(|cmd| Pair {
+                display: cmd.clone(),
+                replacement: cmd,
+            })
+            .collect();
+        // completions replace everything after the leading `/`
+        Ok((1, candidates))
+    }
+}
+
+// known command names completing the typed `/`-prefix, only while still typing the command
+fn command_completions(typed: &str) -> Vec<String> {
+    let Some(prefix) = typed.strip_prefix('/') else {
+        return Vec::new();
+    };
+    if prefix.contains(char::is_whitespace) {
+        return Vec::new();
+    }
+
+    QueryCommand::all()
+        .iter()
+        .map(|cmd| cmd.to_string())
+        .chain(REPL_COMMANDS.iter().map(|cmd| cmd.to_string()))
+        .filter(|cmd| cmd.starts_with(prefix))
+        .collect()
+}
+
+/// Tab completes `/`-commands and indents Datalog scripts otherwise.
+struct IndentOrComplete;
+
+impl ConditionalEventHandler for IndentOrComplete {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        if ctx.line().starts_with('/') {
+            // fall back to the default binding, which is completion
+            None
+        } else {
+            Some(Cmd::Insert(0, "  ".to_string()))
+        }
+    }
 }
 
 fn validated_editor() -> Result<Editor<InputValidator, FileHistory>, ReadlineError> {
@@ -27,6 +113,7 @@ fn validated_editor() -> Result<Editor<InputValidator, FileHistory>, ReadlineErr
         .build();
     let h = InputValidator {
         brackets: MatchingBracketValidator::new(),
+        commands: CommandCompleter,
     };
     let mut editor = Editor::with_config(config)?;
     editor.set_helper(Some(h));
@@ -36,14 +123,17 @@ fn validated_editor() -> Result<Editor<InputValidator, FileHistory>, ReadlineErr
     );
     editor.bind_sequence(
         KeyEvent(KeyCode::Tab, Modifiers::NONE),
-        EventHandler::Simple(Cmd::Insert(0, "  ".to_string())),
+        EventHandler::Conditional(Box::new(IndentOrComplete)),
     );
     Ok(editor)
 }
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub async fn run_repl(db: &DBConnection) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run_repl(
+    db: &DBConnection,
+    rules_file: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("📖 Statistica Verbōrum Latīna REPL {VERSION} 📚");
     println!(
         "{}",
@@ -65,8 +155,19 @@ pub async fn run_repl(db: &DBConnection) -> Result<(), Box<dyn std::error::Error
     path_buf.push(".svl_history.txt");
     let history_file = path_buf.as_path();
 
-    let rules = svl_core::load_rules(svl_core::LoadRulesFrom::DefaultInCurrentDir)
-        .unwrap_or("".to_string());
+    let settings_file = Settings::default_path();
+    let mut settings = match settings_file.as_deref().map(Settings::load) {
+        Some(Ok(settings)) => settings,
+        Some(Err(e)) => {
+            eprintln!("Could not load settings, using defaults: {e}");
+            Settings::default()
+        }
+        None => Settings::default(),
+    };
+
+    let rules = load_rules(rules_file)?;
+    // kept around for `/export <file>`
+    let mut last_result: Option<NamedRows> = None;
 
     if rl.load_history(history_file).is_err() {
         println!("No previous history.");
@@ -78,10 +179,25 @@ pub async fn run_repl(db: &DBConnection) -> Result<(), Box<dyn std::error::Error
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str())?;
-                match parse_eval_print(db, &rules, counter, &line).await {
-                    Ok(_) => {
+                if let Some(args) = set_command_args(&line) {
+                    if let Err(error) =
+                        set_setting(counter, &mut settings, settings_file.as_deref(), args)
+                    {
+                        eprintln!("{counter:03} ❌ {error}\n");
+                    }
+                    continue;
+                }
+                match parse_eval_print(db, &rules, &settings, counter, &line, &last_result).await {
+                    Ok(Some(named_rows)) => {
+                        last_result = Some(named_rows);
+                        continue;
+                    }
+                    Ok(None) => {
                         continue;
                     }
+                    Err(REPLError::Query(QueryError::Quit)) => {
+                        break;
+                    }
                     Err(error) => {
                         eprintln!("REPL: {error}");
                         continue;
@@ -120,26 +236,144 @@ pub enum REPLError {
 
     #[error("QueryError: {0}")]
     Query(#[from] QueryError),
+
+    #[error("ExportError: {0}")]
+    Export(#[from] SVLError),
+
+    #[error("Nothing to export yet, run a query first")]
+    NothingToExport,
+
+    #[error("SettingsError: {0}")]
+    Settings(#[from] SettingsError),
 }
 
+/// Loads the rules from the given file or `rules.datalog` in the current directory.
+/// A missing default rules file is fine, a missing explicitly given one is an error.
+fn load_rules(rules_file: Option<PathBuf>) -> Result<String, SVLError> {
+    let explicit = rules_file.is_some();
+    let path = match rules_file {
+        Some(path) => path,
+        None => LoadRulesFrom::DefaultInCurrentDir.path()?,
+    };
+
+    match svl_core::load_rules(LoadRulesFrom::File(path.clone())) {
+        Ok(rules) => {
+            println!("Loaded rules from {}", path.display());
+            Ok(rules)
+        }
+        Err(SVLError::RulesFileNotFound(path)) if !explicit => {
+            println!(
+                "No rules file found at {}, starting without rules.",
+                path.display()
+            );
+            Ok(String::new())
+        }
+        Err(SVLError::RulesFileNotFound(path)) => {
+            eprintln!("Rules file not found: {}", path.display());
+            Err(SVLError::RulesFileNotFound(path))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// the arguments of a `/set` command, if the line is one
+fn set_command_args(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line == "/set" {
+        return Some("");
+    }
+    line.strip_prefix("/set ").map(str::trim)
+}
+
+/// Shows all settings for `/set` without arguments, otherwise changes and saves one.
+fn set_setting(
+    counter: usize,
+    settings: &mut Settings,
+    settings_file: Option<&Path>,
+    args: &str,
+) -> Result<(), REPLError> {
+    if args.is_empty() {
+        print!("{settings}");
+        return Ok(());
+    }
+
+    let (key, value) =
+        args.split_once(char::is_whitespace)
+            .ok_or_else(|| SettingsError::InvalidValue {
+                key: args.to_string(),
+                value: String::new(),
+                expected: "a value",
+            })?;
+    settings.set(key, value.trim())?;
+
+    if let Some(path) = settings_file {
+        settings.save(path)?;
+    }
+    println!("{counter:03} ✅ {key} = {}", value.trim());
+    Ok(())
+}
+
+/// Evaluates a line of input and prints its result, which is returned so it can be exported later.
 async fn parse_eval_print(
     db: &DBConnection,
     rules: &str,
+    settings: &Settings,
     counter: usize,
     code: &str,
-) -> Result<(), REPLError> {
-    let params = Default::default();
+    last_result: &Option<NamedRows>,
+) -> Result<Option<NamedRows>, REPLError> {
+    if let Some(path) = code.strip_prefix("/export ") {
+        let named_rows = last_result.as_ref().ok_or(REPLError::NothingToExport)?;
+        export_result(counter, named_rows, Path::new(path.trim()))?;
+        return Ok(None);
+    }
+
+    if let Some(path) = code.strip_prefix("/load ") {
+        let path = Path::new(path.trim());
+        let code = match std::fs::read_to_string(path) {
+            Ok(code) => code,
+            Err(e) => {
+                eprintln!("{counter:03} ❌ Could not read {}: {e}\n", path.display());
+                return Ok(None);
+            }
+        };
+        return run_script(db, rules, settings, counter, &code).await;
+    }
+
+    if let Some(script) = code.strip_prefix("/plan ") {
+        let script = RulesScript::new(rules, script);
+        let start = Instant::now();
+        return match db.explain(&script.script, Default::default()).await {
+            Ok(named_rows) => print_result_table(counter, named_rows, settings, start.elapsed()),
+            Err(e) => print_error(counter, script.adjust_db_error(e)),
+        };
+    }
 
     if code.starts_with('/') {
         let code = code.trim_start_matches('/');
-        let query = Query::parse(code)?;
+        let query = Query::parse(code)?.with_case_sensitive(settings.case_sensitive);
+        if query.cmd == QueryCommand::Clear {
+            print!("\x1B[2J\x1B[1;1H");
+            return Ok(None);
+        }
+        let start = Instant::now();
         match query.eval(db).await {
-            Ok(named_rows) => {
-                return print_result_table(counter, named_rows);
+            Ok(mut named_rows) => {
+                if query.cmd == QueryCommand::Help {
+                    named_rows.rows.extend(
+                        REPL_HELP.iter().map(|(usage, description)| {
+                            vec![(*usage).into(), (*description).into()]
+                        }),
+                    );
+                }
+                return print_result_table(counter, named_rows, settings, start.elapsed());
             }
             Err(QueryError::UnknownQuery(query)) => {
                 println!("{counter:03} ❌ Unknown query: {query}");
-                return Ok(());
+                return Ok(None);
+            }
+            Err(QueryError::Quit) => {
+                return Err(QueryError::Quit.into());
             }
             Err(e) => {
                 return print_query_error(counter, e);
@@ -147,15 +381,80 @@ async fn parse_eval_print(
         }
     }
 
-    let code = format!("{}\n{}", rules, code);
-    match db.run_mutable(&code, params).await {
-        Ok(named_rows) => print_result_table(counter, named_rows),
-        Err(e) => print_error(counter, e),
+    run_script(db, rules, settings, counter, code).await
+}
+
+/// Runs a Datalog script with the loaded rules prepended and prints its result.
+async fn run_script(
+    db: &DBConnection,
+    rules: &str,
+    settings: &Settings,
+    counter: usize,
+    code: &str,
+) -> Result<Option<NamedRows>, REPLError> {
+    let script = RulesScript::new(rules, code);
+    for name in script.shadowed_rules() {
+        eprintln!("{counter:03} ⚠️  Rule {name} shadows a rule of the same name in rules.datalog");
+    }
+
+    let start = Instant::now();
+    match db.run_mutable(&script.script, Default::default()).await {
+        Ok(named_rows) => print_result_table(counter, named_rows, settings, start.elapsed()),
+        Err(e) => print_error(counter, script.adjust_db_error(e)),
+    }
+}
+
+/// Prints up to `default_limit` rows in the configured output format.
+/// All rows are returned, so exporting them isn't limited by the setting.
+fn print_result_table(
+    counter: usize,
+    named_rows: NamedRows,
+    settings: &Settings,
+    elapsed: Duration,
+) -> Result<Option<NamedRows>, REPLError> {
+    println!("{counter:03} ✅ ({elapsed:.1?})");
+
+    let shown = settings
+        .default_limit
+        .unwrap_or(named_rows.rows.len())
+        .min(named_rows.rows.len());
+    let rows = &named_rows.rows[..shown];
+
+    match settings.output_format {
+        OutputFormat::Table => {
+            let shown_rows = NamedRows::new(named_rows.headers.clone(), rows.to_vec());
+            result_table(&shown_rows).print_tty(true)?;
+        }
+        OutputFormat::Csv => {
+            export::write_csv(&mut std::io::stdout().lock(), &named_rows.headers, rows)?;
+        }
+        OutputFormat::Json => {
+            let shown_rows = NamedRows::new(named_rows.headers.clone(), rows.to_vec());
+            println!("{:#}", shown_rows.into_json());
+        }
+    }
+
+    if shown < named_rows.rows.len() {
+        println!(
+            "... {} more rows (change with /set default_limit <limit>)",
+            named_rows.rows.len() - shown
+        );
     }
+
+    Ok(Some(named_rows))
 }
 
-fn print_result_table(counter: usize, named_rows: cozo::NamedRows) -> Result<(), REPLError> {
-    println!("{counter:03} ✅");
+fn export_result(counter: usize, named_rows: &NamedRows, path: &Path) -> Result<(), REPLError> {
+    export::export_rows(named_rows, path)?;
+    println!(
+        "{counter:03} ✅ Exported {} rows to {}",
+        named_rows.rows.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+pub(crate) fn result_table(named_rows: &NamedRows) -> Table {
     let mut table = Table::new();
     let mut column_names = Vec::with_capacity(named_rows.headers.len() + 1);
 
@@ -167,29 +466,198 @@ fn print_result_table(counter: usize, named_rows: cozo::NamedRows) -> Result<(),
 
     table.set_titles(Row::new(column_names));
 
+    let numeric_columns = numeric_columns(named_rows);
+
     for (idx, row) in named_rows.rows.iter().enumerate() {
         let mut cells = Vec::with_capacity(row.len() + 1);
-        cells.push(Cell::new(format!("{}", idx).as_str()));
+        cells.push(Cell::new_align(
+            format!("{}", idx).as_str(),
+            Alignment::RIGHT,
+        ));
 
-        for cell in row.iter() {
-            cells.push(Cell::new(cell.clone().to_string().as_str()));
+        for (col, cell) in row.iter().enumerate() {
+            let align = if numeric_columns.get(col).copied().unwrap_or(false) {
+                Alignment::RIGHT
+            } else {
+                Alignment::LEFT
+            };
+            cells.push(Cell::new_align(&format_data_value(cell), align));
         }
 
         table.add_row(Row::new(cells));
     }
 
-    // Print the table to stdout
-    table.print_tty(true)?;
+    table
+}
 
-    Ok(())
+// columns in which every cell is a number get right-aligned
+fn numeric_columns(named_rows: &NamedRows) -> Vec<bool> {
+    (0..named_rows.headers.len())
+        .map(|col| {
+            !named_rows.rows.is_empty()
+                && named_rows
+                    .rows
+                    .iter()
+                    .all(|row| matches!(row.get(col), Some(DataValue::Num(_))))
+        })
+        .collect()
 }
 
-fn print_error(counter: usize, e: DBError) -> Result<(), REPLError> {
+fn print_error(counter: usize, e: DBError) -> Result<Option<NamedRows>, REPLError> {
     eprintln!("{counter:03} ❌ {e}\n");
     Err(REPLError::Db(e))
 }
 
-fn print_query_error(counter: usize, e: QueryError) -> Result<(), REPLError> {
+fn print_query_error(counter: usize, e: QueryError) -> Result<Option<NamedRows>, REPLError> {
     eprintln!("{counter:03} ❌ {e}\n");
     Err(REPLError::Query(e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svl_core::db::val;
+
+    #[test]
+    fn repl_commands_have_help() {
+        for command in REPL_COMMANDS {
+            assert!(
+                REPL_HELP
+                    .iter()
+                    .any(|(usage, _)| usage.split_whitespace().next()
+                        == Some(format!("/{}", command).as_str())),
+                "{}",
+                command
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn export_writes_last_result_as_csv() {
+        let db = DBConnection::new_in_memory().unwrap();
+        let path = std::env::temp_dir().join(format!("svl-repl-export-{}.csv", std::process::id()));
+        let export = format!("/export {}", path.display());
+
+        assert!(matches!(
+            parse_eval_print(&db, "", &Settings::default(), 1, &export, &None).await,
+            Err(REPLError::NothingToExport)
+        ));
+
+        let last_result = parse_eval_print(
+            &db,
+            "",
+            &Settings::default(),
+            2,
+            r#"?[word, note] <- [['amo', 'I love'], ['te', 'you, "thee"']]"#,
+            &None,
+        )
+        .await
+        .unwrap();
+        assert!(
+            parse_eval_print(&db, "", &Settings::default(), 3, &export, &last_result)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(csv, "word,note\namo,I love\nte,\"you, \"\"thee\"\"\"\n");
+    }
+
+    #[tokio::test]
+    async fn load_runs_script_from_file_with_rules() {
+        let db = DBConnection::new_in_memory().unwrap();
+        let path =
+            std::env::temp_dir().join(format!("svl-repl-load-{}.datalog", std::process::id()));
+        std::fs::write(&path, "?[x] := double[1, x]").unwrap();
+        let load = format!("/load {}", path.display());
+
+        let result = parse_eval_print(
+            &db,
+            "double[x, y] := x in [1, 2], y = x * 2",
+            &Settings::default(),
+            1,
+            &load,
+            &None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.rows, vec![vec![DataValue::from(2i64)]]);
+
+        // a missing file is reported right away, not again as an error by the REPL loop
+        assert!(matches!(
+            parse_eval_print(&db, "", &Settings::default(), 2, &load, &None).await,
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn set_command_args_are_only_taken_from_set() {
+        assert_eq!(set_command_args(" /set "), Some(""));
+        assert_eq!(
+            set_command_args("/set output_format csv"),
+            Some("output_format csv")
+        );
+        assert_eq!(set_command_args("/settings"), None);
+        assert_eq!(set_command_args("/top am"), None);
+    }
+
+    #[test]
+    fn set_setting_changes_and_saves_settings() {
+        let path =
+            std::env::temp_dir().join(format!("svl-repl-settings-{}.toml", std::process::id()));
+        let mut settings = Settings::default();
+
+        set_setting(1, &mut settings, Some(&path), "default_limit 10").unwrap();
+        assert_eq!(settings.default_limit, Some(10));
+        assert_eq!(Settings::load(&path).unwrap(), settings);
+
+        assert!(matches!(
+            set_setting(2, &mut settings, Some(&path), "colour red"),
+            Err(REPLError::Settings(SettingsError::UnknownKey(_)))
+        ));
+        assert!(matches!(
+            set_setting(3, &mut settings, Some(&path), "output_format"),
+            Err(REPLError::Settings(SettingsError::InvalidValue { .. }))
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn completes_slash_commands() {
+        assert_eq!(command_completions("/cou").len(), 4);
+        assert!(command_completions("/cou")
+            .iter()
+            .all(|cmd| cmd.starts_with("count")));
+        assert_eq!(command_completions("/exp"), vec!["export".to_string()]);
+        assert_eq!(
+            command_completions("/author"),
+            vec!["authors", "author", "author-stats"]
+        );
+        assert!(command_completions("/top am").is_empty());
+        assert!(command_completions("?[x] <- [[1]]").is_empty());
+    }
+
+    #[test]
+    fn numeric_columns_are_right_aligned() {
+        let named_rows = NamedRows::new(
+            vec!["word".into(), "count".into()],
+            vec![vec![val("amo"), val(1i64)], vec![val("te"), val(100i64)]],
+        );
+
+        let rendered = result_table(&named_rows).to_string();
+
+        let row = |idx: &str, count: &str| {
+            rendered
+                .lines()
+                .any(|line| line.starts_with(idx) && line.ends_with(count))
+        };
+
+        assert!(row("|     0 |", "|     1 |"));
+        assert!(row("|     1 |", "|   100 |"));
+    }
+}
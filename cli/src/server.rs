@@ -0,0 +1,324 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use serde_json::json;
+use svl_core::{
+    db::{DBConnection, DBError},
+    queries::{Query, QueryCommand, QueryError},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+};
+
+/// Requests with longer headers are rejected, there's no need for bodies with GET only.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Clients that don't send their whole request within this time get a 408.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections beyond this many at once are answered with a 503 right away.
+const MAX_CONNECTIONS: usize = 64;
+
+/// REST endpoints for predefined queries, with the names of their query string
+/// parameters in the order of the query's arguments. Any query is also available
+/// as `/query/<command>?args=<arg>&args=<arg>`.
+const ROUTES: [(&str, QueryCommand, &[&str]); 12] = [
+    (
+        "/words/top",
+        QueryCommand::Top,
+        &["prefix", "limit", "offset"],
+    ),
+    (
+        "/words/ends",
+        QueryCommand::Ends,
+        &["suffix", "limit", "offset"],
+    ),
+    (
+        "/words/contains",
+        QueryCommand::Contains,
+        &["substring", "limit", "offset"],
+    ),
+    (
+        "/words/similar",
+        QueryCommand::Similar,
+        &["word", "max_distance"],
+    ),
+    (
+        "/words/concordance",
+        QueryCommand::Concordance,
+        &["word", "window", "limit"],
+    ),
+    ("/word", QueryCommand::Word, &["word", "limit"]),
+    ("/text", QueryCommand::Text, &["text_id", "limit"]),
+    ("/texts/nearest", QueryCommand::Nearest, &["text_id", "k"]),
+    ("/authors", QueryCommand::Authors, &["limit"]),
+    ("/author", QueryCommand::Author, &["name", "limit"]),
+    ("/summary", QueryCommand::Summary, &[]),
+    ("/schema", QueryCommand::Schema, &[]),
+];
+
+/// Serves the predefined queries as JSON over HTTP until stopped with Ctrl+C.
+pub async fn run_server(db: &DBConnection, addr: &str) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("Serving queries on http://{}", listener.local_addr()?);
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            stopped = tokio::signal::ctrl_c() => {
+                println!("Stopping server");
+                return stopped;
+            }
+        };
+        let db = db.clone();
+        let permit = connections.clone().try_acquire_owned().ok();
+        tokio::spawn(async move {
+            let result = match permit {
+                Some(_permit) => handle_connection(&db, stream).await,
+                None => {
+                    let (status, body) = error_response(503, "too many connections");
+                    write_response(stream, status, body).await
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to handle request from {}: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(db: &DBConnection, mut stream: TcpStream) -> std::io::Result<()> {
+    let (status, body) = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Err(_) => error_response(408, "request not received in time"),
+        Ok(request) => {
+            let request = request?;
+            if request.len() > MAX_REQUEST_SIZE {
+                error_response(413, "request too large")
+            } else {
+                respond(db, &String::from_utf8_lossy(&request)).await
+            }
+        }
+    };
+
+    write_response(stream, status, body).await
+}
+
+// reads up to the end of the headers, or until the request got too large
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+        if request.len() > MAX_REQUEST_SIZE {
+            break;
+        }
+    }
+    Ok(request)
+}
+
+async fn write_response(
+    mut stream: TcpStream,
+    status: u16,
+    body: serde_json::Value,
+) -> std::io::Result<()> {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        status_text(status),
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn respond(db: &DBConnection, request: &str) -> (u16, serde_json::Value) {
+    let query = match parse_request(request) {
+        Ok(query) => query,
+        Err((status, message)) => return error_response(status, &message),
+    };
+
+    match query.eval(db).await {
+        Ok(rows) => (200, rows.into_json()),
+        Err(QueryError::DBError(DBError::Cozo(message))) => error_response(500, &message),
+        Err(QueryError::DBError(e @ (DBError::Locked { .. } | DBError::WriteStall(_)))) => {
+            error_response(503, &e.to_string())
+        }
+        Err(QueryError::DBError(e @ DBError::Timeout(_))) => error_response(504, &e.to_string()),
+        Err(e) => error_response(400, &e.to_string()),
+    }
+}
+
+// the query to run for a request, or the status and message to fail with
+fn parse_request(request: &str) -> Result<Query, (u16, String)> {
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err((400, "invalid request line".into()));
+    };
+    if method != "GET" {
+        return Err((405, format!("method {} not allowed, only GET", method)));
+    }
+
+    let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+    let params = parse_query_string(query_string);
+
+    if let Some(cmd) = path.strip_prefix("/query/") {
+        let cmd = percent_decode(cmd);
+        let cmd = cmd
+            .parse::<QueryCommand>()
+            .map_err(|e| (404, e.to_string()))?;
+        if matches!(cmd, QueryCommand::Quit | QueryCommand::Exit) {
+            return Err((404, format!("{} is only available in the REPL", cmd)));
+        }
+        let args = params.get("args").cloned().unwrap_or_default();
+        return Ok(Query::new(cmd.to_string(), args));
+    }
+
+    let Some((_, cmd, names)) = ROUTES.iter().find(|(route, _, _)| *route == path) else {
+        return Err((404, format!("unknown path {}", path)));
+    };
+    Ok(Query::new(cmd.to_string(), route_args(names, &params)?))
+}
+
+// the query arguments from named parameters, up to the first missing one
+fn route_args(
+    names: &[&str],
+    params: &BTreeMap<String, Vec<String>>,
+) -> Result<Vec<String>, (u16, String)> {
+    let mut args = Vec::new();
+    let mut missing = None;
+
+    for name in names {
+        match (params.get(*name).and_then(|v| v.first()), missing) {
+            (Some(value), None) => args.push(value.clone()),
+            (Some(_), Some(missing)) => {
+                return Err((400, format!("parameter {} requires {}", name, missing)))
+            }
+            (None, _) => missing = missing.or(Some(*name)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn parse_query_string(query_string: &str) -> BTreeMap<String, Vec<String>> {
+    let mut params: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for pair in query_string.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        params
+            .entry(percent_decode(key))
+            .or_default()
+            .push(percent_decode(value));
+    }
+    params
+}
+
+// decodes `%XX` escapes (of UTF-8 bytes) and `+` as space, keeping invalid escapes as they are
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = s
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                if let Some(byte) = hex {
+                    decoded.push(byte);
+                    i += 2;
+                } else {
+                    decoded.push(b'%');
+                }
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn error_response(status: u16, message: &str) -> (u16, serde_json::Value) {
+    (status, json!({ "error": message }))
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(target: &str) -> Result<Query, (u16, String)> {
+        parse_request(&format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            target
+        ))
+    }
+
+    #[test]
+    fn routes_map_parameters_to_query_args() {
+        assert_eq!(
+            get("/words/top?limit=20&prefix=am").unwrap(),
+            Query::new("top".into(), vec!["am".into(), "20".into()])
+        );
+        assert_eq!(
+            get("/word?word=am%C4%ABcus").unwrap(),
+            Query::new("word".into(), vec!["amīcus".into()])
+        );
+        assert_eq!(
+            get("/query/author?args=Marcus+Tullius+Cicero").unwrap(),
+            Query::new("author".into(), vec!["Marcus Tullius Cicero".into()])
+        );
+        assert_eq!(
+            get("/summary").unwrap(),
+            Query::new("summary".into(), vec![])
+        );
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        assert_eq!(get("/nothing").unwrap_err().0, 404);
+        assert_eq!(get("/query/nothing").unwrap_err().0, 404);
+        assert_eq!(get("/words/top?prefix=am&offset=10").unwrap_err().0, 400);
+        assert_eq!(
+            parse_request("POST /summary HTTP/1.1\r\n\r\n")
+                .unwrap_err()
+                .0,
+            405
+        );
+    }
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode("am%C4%ABcus"), "amīcus");
+        assert_eq!(percent_decode("a+b%20c"), "a b c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde_derive::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Unknown setting: {0} (known settings: {known})", known = Settings::KEYS.join(", "))]
+    UnknownKey(String),
+
+    #[error("Invalid value for {key}: {value} (expected {expected})")]
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+
+    #[error("Invalid settings file: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Could not write settings: {0}")]
+    Serialize(#[from] toml::ser::Error),
+
+    #[error("IOError: {0}")]
+    IO(#[from] std::io::Error),
+}
+
+/// How the REPL prints query results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Table => write!(f, "table"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// REPL preferences, kept across sessions in a small TOML file.
+/// Missing settings get their defaults, unknown ones (e.g. of newer versions) are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub output_format: OutputFormat,
+    /// Maximum number of result rows printed, all rows if `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_limit: Option<usize>,
+    /// Whether predefined queries match words case-sensitively.
+    pub case_sensitive: bool,
+}
+
+impl Settings {
+    pub const KEYS: [&'static str; 3] = ["output_format", "default_limit", "case_sensitive"];
+
+    /// The default settings file in the home directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".svl_config.toml"))
+    }
+
+    /// Loads settings from a file, falling back to the defaults if it doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, SettingsError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => contents.parse(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SettingsError> {
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Changes a setting by name, e.g. from `/set <key> <value>`.
+    /// A `default_limit` of `none` or `0` prints all rows.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), SettingsError> {
+        let invalid = |expected| SettingsError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+            expected,
+        };
+
+        match key {
+            "output_format" => {
+                self.output_format = value.parse().map_err(|_| invalid("table, csv or json"))?;
+            }
+            "default_limit" => {
+                self.default_limit = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid("a number or none"))?),
+                }
+                .filter(|&limit| limit > 0);
+            }
+            "case_sensitive" => {
+                self.case_sensitive = match value {
+                    "true" | "on" => true,
+                    "false" | "off" => false,
+                    _ => return Err(invalid("true or false")),
+                };
+            }
+            _ => return Err(SettingsError::UnknownKey(key.to_string())),
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Settings {
+    type Err = SettingsError;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let settings: Settings = toml::from_str(contents)?;
+        // a limit of 0 prints all rows, same as with `/set`
+        Ok(Settings {
+            default_limit: settings.default_limit.filter(|&limit| limit > 0),
+            ..settings
+        })
+    }
+}
+
+impl Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let contents = toml::to_string(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_roundtrip_through_config_file_format() {
+        let mut settings = Settings::default();
+        settings.set("output_format", "csv").unwrap();
+        settings.set("default_limit", "25").unwrap();
+        settings.set("case_sensitive", "on").unwrap();
+        assert!(settings.case_sensitive);
+
+        assert_eq!(settings.to_string().parse::<Settings>().unwrap(), settings);
+        // unknown settings are ignored, a limit of 0 prints all rows
+        assert_eq!(
+            "# comment\n\noutput_format = \"json\"\ndefault_limit = 0\ncolour = \"red\"\n"
+                .parse::<Settings>()
+                .unwrap(),
+            Settings {
+                output_format: OutputFormat::Json,
+                default_limit: None,
+                case_sensitive: false,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_errors() {
+        let mut settings = Settings::default();
+
+        assert!(matches!(
+            settings.set("colour", "red"),
+            Err(SettingsError::UnknownKey(key)) if key == "colour"
+        ));
+        assert!(matches!(
+            settings.set("output_format", "xml"),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            settings.set("default_limit", "many"),
+            Err(SettingsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            "output_format".parse::<Settings>(),
+            Err(SettingsError::Parse(_))
+        ));
+        assert!(matches!(
+            "output_format = \"xml\"".parse::<Settings>(),
+            Err(SettingsError::Parse(_))
+        ));
+
+        settings.set("default_limit", "0").unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+}
@@ -0,0 +1,10 @@
+//! Helpers shared by the benchmarks, which can't use the crate's `#[cfg(test)]` helpers.
+
+use svl_core::db::DBConnection;
+
+/// An in-memory DB with the schema created.
+pub async fn empty_db() -> DBConnection {
+    let db = DBConnection::new_in_memory().unwrap();
+    db.create_schema().await.unwrap();
+    db
+}
@@ -0,0 +1,65 @@
+//! Compares running read-only queries one after another with running them
+//! concurrently on clones of one `DBConnection`. Run with `cargo bench -p svl-core`.
+
+mod common;
+
+use common::empty_db;
+use std::time::{Duration, Instant};
+use svl_core::db::{val, DBConnection, DBParams};
+
+const WORDS: usize = 20_000;
+const QUERIES: usize = 32;
+const QUERY: &str = "?[len, count(word)] := *Word{word}, len = length(word) :order len";
+
+#[tokio::main]
+async fn main() {
+    let db = empty_db().await;
+    let words: Vec<_> = (0..WORDS)
+        .map(|i| {
+            val(vec![
+                val(format!("word{}", i)),
+                val(i as i64 % 100),
+                val(1i64),
+            ])
+        })
+        .collect();
+    db.run_mutable(
+        "?[word, text_id, count] <- $words :put Word {word, text_id => count}",
+        DBParams::from([("words".to_string(), val(words))]),
+    )
+    .await
+    .unwrap();
+
+    let sequential = sequential_reads(&db).await;
+    let concurrent = concurrent_reads(&db).await;
+
+    println!("{} reads over {} words:", QUERIES, WORDS);
+    println!("  sequential: {:?}", sequential);
+    println!("  concurrent: {:?}", concurrent);
+    println!(
+        "  speedup:    {:.2}x",
+        sequential.as_secs_f64() / concurrent.as_secs_f64()
+    );
+}
+
+async fn sequential_reads(db: &DBConnection) -> Duration {
+    let start = Instant::now();
+    for _ in 0..QUERIES {
+        db.run_immutable(QUERY, DBParams::new()).await.unwrap();
+    }
+    start.elapsed()
+}
+
+async fn concurrent_reads(db: &DBConnection) -> Duration {
+    let start = Instant::now();
+    let reads: Vec<_> = (0..QUERIES)
+        .map(|_| {
+            let db = db.clone();
+            tokio::spawn(async move { db.run_immutable(QUERY, DBParams::new()).await })
+        })
+        .collect();
+    for read in reads {
+        read.await.unwrap().unwrap();
+    }
+    start.elapsed()
+}
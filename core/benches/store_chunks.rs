@@ -0,0 +1,94 @@
+//! Compares storing word counts with one script per row, as `Stats::store_in_db`
+//! used to, with storing them in chunks of rows per script, and with
+//! `Stats::store_in_db` itself. Run with `cargo bench -p svl-core --bench store_chunks`.
+
+mod common;
+
+use common::empty_db;
+use std::time::{Duration, Instant};
+use svl_core::{
+    db::{val, DBParams, DataValue},
+    stats::Stats,
+    text::Text,
+};
+
+const TEXTS: usize = 20;
+const WORDS_PER_TEXT: usize = 1_000;
+const ROWS_PER_SCRIPT: usize = 1000;
+const PUT_WORDS: &str = "?[word, text_id, count] <- $rows :put Word {word, text_id => count}";
+
+#[tokio::main]
+async fn main() {
+    let rows: Vec<DataValue> = (0..TEXTS)
+        .flat_map(|t| {
+            (0..WORDS_PER_TEXT).map(move |w| {
+                val(vec![
+                    val(format!("verbum{}", w)),
+                    val(t as i64 + 1),
+                    val(1i64),
+                ])
+            })
+        })
+        .collect();
+
+    let per_row = store_per_row(&rows).await;
+    let chunked = store_chunked(&rows).await;
+    let stats = store_stats().await;
+
+    println!("{} word counts:", rows.len());
+    println!("  one script per row: {:?}", per_row);
+    println!("  {} rows per script: {:?}", ROWS_PER_SCRIPT, chunked);
+    println!("  Stats::store_in_db: {:?}", stats);
+    println!(
+        "  speedup:            {:.2}x",
+        per_row.as_secs_f64() / chunked.as_secs_f64()
+    );
+}
+
+async fn store_per_row(rows: &[DataValue]) -> Duration {
+    let db = empty_db().await;
+    let start = Instant::now();
+    let tx = db.multi_tx(true);
+    for row in rows {
+        tx.run_script(
+            PUT_WORDS,
+            DBParams::from([("rows".to_string(), val(vec![row.clone()]))]),
+        )
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+    start.elapsed()
+}
+
+async fn store_chunked(rows: &[DataValue]) -> Duration {
+    let db = empty_db().await;
+    let start = Instant::now();
+    let tx = db.multi_tx(true);
+    for chunk in rows.chunks(ROWS_PER_SCRIPT) {
+        tx.run_script(
+            PUT_WORDS,
+            DBParams::from([("rows".to_string(), val(chunk.to_vec()))]),
+        )
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+    start.elapsed()
+}
+
+async fn store_stats() -> Duration {
+    let mut stats = Stats::new();
+    for t in 0..TEXTS {
+        let words: Vec<String> = (0..WORDS_PER_TEXT)
+            .map(|w| format!("verbum{}", w))
+            .collect();
+        // storing texts requires an author
+        let mut text = Text::new(format!("text{}", t), words.join(" "));
+        text.author_id = Some(0);
+        stats.add_text(text);
+    }
+
+    let db = empty_db().await;
+    let start = Instant::now();
+    stats.store_in_db(&db).await.unwrap();
+    start.elapsed()
+}
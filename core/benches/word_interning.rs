@@ -0,0 +1,83 @@
+//! Compares the heap memory of `Stats`, which interns words, with the layout it
+//! had before: a map from each word to its stats, which kept another copy of the word.
+//! Run with `cargo bench -p svl-core --bench word_interning`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use svl_core::{
+    stats::{Stats, WordStats},
+    text::{Text, TextId, Word},
+};
+
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const TEXTS: usize = 50;
+const WORDS_PER_TEXT: usize = 5_000;
+
+// heap bytes still allocated by the value `build` returns
+fn heap_size<T>(build: impl FnOnce() -> T) -> (usize, T) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let value = build();
+    (ALLOCATED.load(Ordering::Relaxed) - before, value)
+}
+
+fn main() {
+    // mostly distinct words, like the long tail of a real corpus
+    let texts: Vec<Text> = (0..TEXTS)
+        .map(|t| {
+            let words: Vec<String> = (0..WORDS_PER_TEXT)
+                .map(|w| format!("verbum{}", (t * WORDS_PER_TEXT + w) % 100_000))
+                .collect();
+            Text::new(format!("text{}", t), words.join(" "))
+        })
+        .collect();
+    let words: Vec<(TextId, Word)> = texts
+        .iter()
+        .enumerate()
+        .flat_map(|(t, text)| text.words().map(move |word| (TextId::from(t + 1), word)))
+        .collect();
+
+    let (interned, stats) = heap_size(|| {
+        let mut stats = Stats::new();
+        for (text_id, word) in &words {
+            stats.add_word(*text_id, word.clone());
+        }
+        stats
+    });
+
+    let (keyed, map) = heap_size(|| {
+        let mut map: HashMap<Word, (Word, WordStats)> = HashMap::new();
+        for (text_id, word) in &words {
+            map.entry(word.clone())
+                .or_insert_with(|| (word.clone(), WordStats::new(*text_id)))
+                .1
+                .count_text(*text_id);
+        }
+        map
+    });
+
+    println!("{} distinct words:", stats.unique_word_count());
+    println!("  map with words as keys: {} KiB", keyed / 1024);
+    println!("  interned words:         {} KiB", interned / 1024);
+    drop(map);
+}
@@ -1,32 +1,302 @@
-use std::sync::Arc;
+use std::{
+    future::Future,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+use serde::de::DeserializeOwned;
 use serde_derive::{Deserialize, Serialize};
 
-use crate::text::Text;
+use crate::{
+    db::{val, DBConnection, DBParams, INDEX_CACHE_SCHEMA},
+    errors::SVLError,
+    text::{Author, Text},
+};
+
+const LOG_TARGET: &str = "svl::client";
+
+/// Source of the library's index pages: the author list and each author's text list.
+#[allow(async_fn_in_trait)]
+pub trait LibraryIndex {
+    /// URL of the page listing all authors.
+    fn index_url(&self) -> &str;
+    async fn get_authors(&self) -> crate::Result<Vec<AuthorInfo>>;
+    async fn get_texts(&self, author_info: &AuthorInfo) -> crate::Result<Vec<TextInfo>>;
+}
+
+/// How often and how long to back off when requests fail transiently,
+/// i.e. with server errors, timeouts or connection errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before the given retry (starting at 0), doubling with every retry.
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let factor = 2u32.saturating_pow(retry.try_into().unwrap_or(u32::MAX));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Runs `op`, retrying it as long as it fails with a retryable error.
+    pub async fn run<T, F, Fut>(&self, what: &str, mut op: F) -> crate::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = crate::Result<T>>,
+    {
+        let mut retry = 0;
+
+        loop {
+            match op().await {
+                Err(e) if e.is_retryable() && retry < self.max_retries => {
+                    let backoff = self.backoff(retry);
+                    retry += 1;
+                    log::warn!(
+                        target: LOG_TARGET,
+                        "Fetching {} failed: {}, retrying in {:?} ({}/{})",
+                        what,
+                        e,
+                        backoff,
+                        retry,
+                        self.max_retries
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct HttpStatsClient {
     client: reqwest::Client,
     semaphore: Arc<tokio::sync::Semaphore>,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    timeout: Duration,
+    html_cache: Option<HtmlCache>,
+    concurrency: usize,
+}
+
+/// On-disk cache of fetched text pages, one file per URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HtmlCache {
+    dir: PathBuf,
+    // ignore cached pages, but still write fetched pages back
+    refresh: bool,
 }
 
 impl HttpStatsClient {
-    const BASE_URL: &'static str = "https://thelatinlibrary.com/";
-    const MAX_CONCURRENT_REQUESTS: usize = 25;
+    pub const DEFAULT_BASE_URL: &'static str = "https://thelatinlibrary.com/";
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+    pub const DEFAULT_CONCURRENCY: usize = 25;
+
+    // selectors are tried in order, so that minor changes to the site's markup
+    // can still be parsed by one of the more lenient fallbacks
+    const AUTHOR_SELECTORS: &'static [&'static str] = &[
+        "form[name=myform] select[name=dest] option",
+        "select[name=dest] option",
+        "form select option",
+    ];
+    const TEXT_SELECTORS: &'static [&'static str] =
+        &["div.work table tr td a", "div.work a", "table tr td a"];
 
     pub fn new() -> crate::Result<Self> {
-        let client = reqwest::Client::builder().https_only(true).build()?;
-        // allow max of MAX_CONCURRENT_REQUESTS concurrent requests using this http client pool
-        let semaphore = Arc::new(tokio::sync::Semaphore::new(Self::MAX_CONCURRENT_REQUESTS));
+        Self::with_base_url(Self::DEFAULT_BASE_URL)
+    }
+
+    /// Creates a client for a mirror of the library hosted at `base`.
+    /// Plain http is only allowed if the mirror itself isn't served via https.
+    pub fn with_base_url(base: impl Into<String>) -> crate::Result<Self> {
+        let mut base_url = base.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+
+        let timeout = Self::DEFAULT_TIMEOUT;
+        let client = Self::build_client(&base_url, timeout)?;
+        let concurrency = Self::DEFAULT_CONCURRENCY;
+
+        Ok(Self {
+            client,
+            semaphore: Self::semaphore(concurrency),
+            base_url,
+            retry_policy: RetryPolicy::default(),
+            timeout,
+            html_cache: None,
+            concurrency,
+        })
+    }
+
+    /// Allows at most `concurrency` requests at the same time (at least 1).
+    pub fn with_concurrency(mut self, concurrency: usize) -> crate::Result<Self> {
+        if concurrency == 0 {
+            return Err(SVLError::InvalidConcurrency(concurrency));
+        }
+        self.semaphore = Self::semaphore(concurrency);
+        self.concurrency = concurrency;
+        Ok(self)
+    }
+
+    // allow max of `concurrency` concurrent requests using this http client pool
+    fn semaphore(concurrency: usize) -> Arc<tokio::sync::Semaphore> {
+        Arc::new(tokio::sync::Semaphore::new(concurrency))
+    }
+
+    /// Fails requests that take longer than `timeout` with `SVLError::Timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> crate::Result<Self> {
+        self.client = Self::build_client(&self.base_url, timeout)?;
+        self.timeout = timeout;
+        Ok(self)
+    }
+
+    /// Caches fetched texts in `dir`, so that they are only downloaded once.
+    pub fn with_cache_dir(mut self, dir: PathBuf) -> Self {
+        self.html_cache = Some(HtmlCache {
+            dir,
+            refresh: false,
+        });
+        self
+    }
 
-        Ok(Self { client, semaphore })
+    /// Downloads texts again even if they are cached, replacing the cached pages.
+    pub fn refresh_cache(mut self) -> Self {
+        if let Some(cache) = self.html_cache.as_mut() {
+            cache.refresh = true;
+        }
+        self
+    }
+
+    fn build_client(base_url: &str, timeout: Duration) -> crate::Result<reqwest::Client> {
+        let client = reqwest::Client::builder()
+            .https_only(base_url.starts_with("https://"))
+            .timeout(timeout)
+            .build()?;
+        Ok(client)
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
     }
 
     pub async fn fetch_text(&self, text_url: &str) -> crate::Result<Text> {
+        let html_text = self.get_cached_html(text_url, RetryPolicy::none()).await?;
+        Ok(Self::parse_text(text_url, &html_text))
+    }
+
+    /// Like `fetch_text`, but retries transient failures according to the client's `RetryPolicy`.
+    pub async fn fetch_text_with_retry(&self, text_url: &str) -> crate::Result<Text> {
+        let html_text = self.get_cached_html(text_url, self.retry_policy).await?;
+        Ok(Self::parse_text(text_url, &html_text))
+    }
+
+    async fn get_cached_html(&self, url: &str, retry_policy: RetryPolicy) -> crate::Result<String> {
+        if let Some(html_text) = self.read_cached_html(url).await? {
+            log::debug!(target: LOG_TARGET, "Using cached page for {}", url);
+            return Ok(html_text);
+        }
+
+        let html_text = retry_policy.run(url, || self.get_html(url)).await?;
+
+        if let Some(path) = self.cache_path(url) {
+            log::debug!(target: LOG_TARGET, "Caching page for {} in {:?}", url, path);
+            // the page was fetched fine, a failure to cache it only costs a fetch next time
+            if let Err(e) = write_cached_html(&path, &html_text).await {
+                log::warn!(target: LOG_TARGET, "Could not cache page for {} in {:?}: {}", url, path, e);
+            }
+        }
+
+        Ok(html_text)
+    }
+
+    async fn read_cached_html(&self, url: &str) -> crate::Result<Option<String>> {
+        let Some(path) = self.cache_path(url) else {
+            return Ok(None);
+        };
+        if self.html_cache.as_ref().is_some_and(|cache| cache.refresh) {
+            return Ok(None);
+        }
+
+        match tokio::fs::read_to_string(path).await {
+            Ok(html_text) => Ok(Some(html_text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn cache_path(&self, url: &str) -> Option<PathBuf> {
+        let cache = self.html_cache.as_ref()?;
+        Some(cache.dir.join(format!("{:016x}.html", fnv1a_hash(url))))
+    }
+
+    async fn get_html(&self, url: &str) -> crate::Result<String> {
         let _permit = self.semaphore.acquire().await?;
-        let text = self.client.get(text_url).send().await?.text().await?;
+        let timed_out = |e: reqwest::Error| {
+            if e.is_timeout() {
+                SVLError::Timeout {
+                    url: url.into(),
+                    timeout: self.timeout,
+                }
+            } else {
+                SVLError::Reqwest(e)
+            }
+        };
+
+        log::debug!(target: LOG_TARGET, "GET {}", url);
+        let response = self.client.get(url).send().await.map_err(timed_out)?;
+
+        let status = response.status();
+        log::debug!(target: LOG_TARGET, "GET {} -> {}", url, status);
+        if !status.is_success() {
+            return Err(SVLError::HttpStatus {
+                url: url.into(),
+                status: status.as_u16(),
+            });
+        }
+
+        response.text().await.map_err(timed_out)
+    }
+
+    fn parse_text(text_url: &str, html_text: &str) -> Text {
         let body_selector = scraper::Selector::parse("body").unwrap();
-        let html = scraper::Html::parse_document(&text);
+        let html = scraper::Html::parse_document(html_text);
         let body = html.select(&body_selector).next().unwrap().inner_html();
 
         // only leave text nodes
@@ -35,65 +305,225 @@ impl HttpStatsClient {
             .text()
             .collect::<String>();
 
-        let text = Text::new(text_url.into(), text);
-        Ok(text)
+        Text::new(text_url.into(), text)
     }
 
     pub async fn get_authors(&self) -> crate::Result<Vec<AuthorInfo>> {
-        let _permit = self.semaphore.acquire().await?;
-        let html_text = self.client.get(Self::BASE_URL).send().await?.text().await?;
-
-        let html = scraper::Html::parse_document(&html_text);
-        let mut authors = Vec::new();
+        let html_text = self
+            .retry_policy
+            .run(&self.base_url, || self.get_html(&self.base_url))
+            .await?;
+        self.parse_authors(&html_text)
+    }
 
-        let selector =
-            scraper::Selector::parse("form[name=myform] select[name=dest] option").unwrap();
+    pub fn parse_authors(&self, html_text: &str) -> crate::Result<Vec<AuthorInfo>> {
+        let html = scraper::Html::parse_document(html_text);
+        let options = select_with_fallbacks(&html, Self::AUTHOR_SELECTORS, "authors")?;
 
-        for author in html.select(&selector) {
-            // <option value="$URL">$NAME</option>
-            let author_info = AuthorInfo {
-                name: author.inner_html().trim().into(),
-                url: Self::path_to_url(author.value().attr("value").unwrap()),
-                texts: Vec::new(),
-            };
-            authors.push(author_info);
-        }
+        let authors = options
+            .into_iter()
+            .filter_map(|author| {
+                // <option value="$URL">$NAME</option>
+                let path = author.value().attr("value")?;
+                Some(AuthorInfo {
+                    name: author.inner_html().trim().into(),
+                    url: self.path_to_url(path),
+                    texts: Vec::new(),
+                })
+            })
+            .collect();
 
         Ok(authors)
     }
 
-    pub fn path_to_url(path: &str) -> String {
-        format!("{}{}", Self::BASE_URL, path.trim())
+    pub fn path_to_url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path.trim())
     }
 
     pub async fn get_texts(&self, author_info: &AuthorInfo) -> crate::Result<Vec<TextInfo>> {
-        let permit = self.semaphore.acquire().await?;
         let html_text = self
-            .client
-            .get(author_info.url.clone())
-            .send()
-            .await?
-            .text()
+            .retry_policy
+            .run(&author_info.url, || self.get_html(&author_info.url))
             .await?;
-        drop(permit);
+        self.parse_texts(&html_text)
+    }
 
-        let html = scraper::Html::parse_document(&html_text);
-        let mut text_infos = Vec::new();
+    pub fn parse_texts(&self, html_text: &str) -> crate::Result<Vec<TextInfo>> {
+        let html = scraper::Html::parse_document(html_text);
+        let links = select_with_fallbacks(&html, Self::TEXT_SELECTORS, "texts")?;
 
-        let selector = scraper::Selector::parse("div.work table tr td a").unwrap();
+        let text_infos = links
+            .into_iter()
+            .filter_map(|txt| {
+                let path = txt.value().attr("href")?;
+                Some(TextInfo {
+                    name: txt.inner_html().trim().into(),
+                    url: self.path_to_url(path),
+                })
+            })
+            .collect();
 
-        for txt in html.select(&selector) {
-            let text_info = TextInfo {
-                name: txt.inner_html().trim().into(),
-                url: Self::path_to_url(txt.value().attr("href").unwrap().trim()),
-            };
-            text_infos.push(text_info);
+        Ok(text_infos)
+    }
+}
+
+impl LibraryIndex for HttpStatsClient {
+    fn index_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn get_authors(&self) -> crate::Result<Vec<AuthorInfo>> {
+        HttpStatsClient::get_authors(self).await
+    }
+
+    async fn get_texts(&self, author_info: &AuthorInfo) -> crate::Result<Vec<TextInfo>> {
+        HttpStatsClient::get_texts(self, author_info).await
+    }
+}
+
+/// Caches the parsed index pages in the `IndexCache` relation, so that repeated
+/// imports only need to fetch the text bodies while the cache is fresh.
+pub struct IndexCache<'a> {
+    db: &'a DBConnection,
+    ttl: Duration,
+}
+
+impl<'a> IndexCache<'a> {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Opens the cache, creating its relation for databases created before it existed.
+    pub async fn open(db: &'a DBConnection, ttl: Duration) -> crate::Result<Self> {
+        if !db.relation_names().await?.iter().any(|n| n == "IndexCache") {
+            db.run_mutable(INDEX_CACHE_SCHEMA, DBParams::new()).await?;
         }
+        Ok(Self { db, ttl })
+    }
 
-        Ok(text_infos)
+    pub async fn authors<I: LibraryIndex>(&self, index: &I) -> crate::Result<Vec<AuthorInfo>> {
+        let page_url = index.index_url();
+        if let Some(authors) = self.lookup(page_url).await? {
+            return Ok(authors);
+        }
+
+        let authors = index.get_authors().await?;
+        self.store(page_url, &authors).await?;
+        Ok(authors)
+    }
+
+    pub async fn texts<I: LibraryIndex>(
+        &self,
+        index: &I,
+        author_info: &AuthorInfo,
+    ) -> crate::Result<Vec<TextInfo>> {
+        if let Some(texts) = self.lookup(&author_info.url).await? {
+            return Ok(texts);
+        }
+
+        let texts = index.get_texts(author_info).await?;
+        self.store(&author_info.url, &texts).await?;
+        Ok(texts)
+    }
+
+    async fn lookup<T: DeserializeOwned>(&self, page_url: &str) -> crate::Result<Option<T>> {
+        let rows = self
+            .db
+            .run_immutable(
+                "?[data, fetched_at] := *IndexCache{ page_url, data, fetched_at }, page_url = $page_url",
+                DBParams::from_iter(vec![("page_url".into(), val(page_url))]),
+            )
+            .await?;
+
+        let Some(row) = rows.rows.first() else {
+            return Ok(None);
+        };
+
+        let fetched_at = row.get(1).and_then(|v| v.get_float()).unwrap_or_default();
+        if unix_now() - fetched_at >= self.ttl.as_secs_f64() {
+            log::debug!(target: LOG_TARGET, "Index cache for {} is stale", page_url);
+            return Ok(None);
+        }
+
+        let data = row
+            .first()
+            .and_then(|v| v.get_str())
+            .ok_or(SVLError::InvalidState)?;
+
+        log::info!(target: LOG_TARGET, "Using cached index for {}", page_url);
+        Ok(Some(serde_json::from_str(data)?))
+    }
+
+    async fn store<T: serde::Serialize>(&self, page_url: &str, value: &T) -> crate::Result<()> {
+        self.db
+            .run_mutable(
+                "
+                ?[page_url, data, fetched_at] <- [[$page_url, $data, $fetched_at]];
+                :put IndexCache { page_url => data, fetched_at }
+                ",
+                DBParams::from_iter(vec![
+                    ("page_url".into(), val(page_url)),
+                    ("data".into(), val(serde_json::to_string(value)?)),
+                    ("fetched_at".into(), val(unix_now())),
+                ]),
+            )
+            .await?;
+
+        Ok(())
     }
 }
 
+// writes to a temporary file first and renames it into place,
+// so an interrupted write never leaves a truncated page in the cache
+async fn write_cached_html(path: &Path, html_text: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let tmp_path = path.with_extension(format!("html.{}.tmp", std::process::id()));
+    tokio::fs::write(&tmp_path, html_text).await?;
+    if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+// stable across Rust versions and platforms (unlike `DefaultHasher`), so cache file names stay valid
+fn fnv1a_hash(s: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    s.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Returns the elements matched by the first selector that matches anything.
+fn select_with_fallbacks<'a>(
+    html: &'a scraper::Html,
+    selectors: &[&str],
+    what: &str,
+) -> crate::Result<Vec<scraper::ElementRef<'a>>> {
+    for selector in selectors {
+        let parsed = scraper::Selector::parse(selector).expect("Selector should be valid");
+        let elements: Vec<_> = html.select(&parsed).collect();
+
+        if !elements.is_empty() {
+            log::info!(target: LOG_TARGET, "Parsing {} using selector: {}", what, selector);
+            return Ok(elements);
+        }
+
+        log::debug!(target: LOG_TARGET, "Selector for {} matched nothing: {}", what, selector);
+    }
+
+    Err(SVLError::NoSelectorMatched(what.into()))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AuthorInfo {
     pub name: String,
@@ -106,3 +536,365 @@ pub struct TextInfo {
     pub name: String,
     pub url: String,
 }
+
+impl From<(usize, AuthorInfo)> for Author {
+    /// Authors are assigned ids by their position in the library index, so the
+    /// enumeration index of an `AuthorInfo` becomes its `author_id`.
+    fn from((author_id, info): (usize, AuthorInfo)) -> Self {
+        Self {
+            author_id,
+            name: info.name,
+            url: info.url,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{val, ToDataValue};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingIndex {
+        author_requests: AtomicUsize,
+        text_requests: AtomicUsize,
+    }
+
+    impl CountingIndex {
+        fn requests(&self) -> (usize, usize) {
+            (
+                self.author_requests.load(Ordering::SeqCst),
+                self.text_requests.load(Ordering::SeqCst),
+            )
+        }
+    }
+
+    impl LibraryIndex for CountingIndex {
+        fn index_url(&self) -> &str {
+            HttpStatsClient::DEFAULT_BASE_URL
+        }
+
+        async fn get_authors(&self) -> crate::Result<Vec<AuthorInfo>> {
+            self.author_requests.fetch_add(1, Ordering::SeqCst);
+            let mut authors = vec![author_info("Caesar"), author_info("Cicero")];
+            for author in authors.iter_mut() {
+                author.texts.clear();
+            }
+            Ok(authors)
+        }
+
+        async fn get_texts(&self, _author_info: &AuthorInfo) -> crate::Result<Vec<TextInfo>> {
+            self.text_requests.fetch_add(1, Ordering::SeqCst);
+            Ok(author_info("Caesar").texts)
+        }
+    }
+
+    async fn fetch_index(cache: &IndexCache<'_>, index: &CountingIndex) -> Vec<AuthorInfo> {
+        let mut authors = cache.authors(index).await.unwrap();
+        for author in authors.iter_mut() {
+            author.texts = cache.texts(index, author).await.unwrap();
+        }
+        authors
+    }
+
+    fn client() -> HttpStatsClient {
+        HttpStatsClient::new().unwrap()
+    }
+
+    fn author_info(name: &str) -> AuthorInfo {
+        AuthorInfo {
+            name: name.into(),
+            url: client().path_to_url(&format!("{}.html", name.to_lowercase())),
+            texts: vec![TextInfo {
+                name: "Liber I".into(),
+                url: "https://thelatinlibrary.com/caesar/gall1.shtml".into(),
+            }],
+        }
+    }
+
+    #[test]
+    fn parse_authors_with_fallback_selectors() {
+        let expected = vec![AuthorInfo {
+            name: "Caesar".into(),
+            url: "https://thelatinlibrary.com/caesar.html".into(),
+            texts: Vec::new(),
+        }];
+
+        let current = r#"<form name="myform"><select name="dest">
+            <option value="caesar.html">Caesar</option>
+        </select></form>"#;
+        assert_eq!(client().parse_authors(current).unwrap(), expected);
+
+        let renamed_form = r#"<form name="authors"><select name="dest">
+            <option value="caesar.html"> Caesar </option>
+        </select></form>"#;
+        assert_eq!(client().parse_authors(renamed_form).unwrap(), expected);
+
+        let renamed_select = r#"<form><select name="author">
+            <option value="caesar.html">Caesar</option>
+        </select></form>"#;
+        assert_eq!(client().parse_authors(renamed_select).unwrap(), expected);
+
+        assert!(matches!(
+            client().parse_authors("<p>Nihil hic</p>"),
+            Err(SVLError::NoSelectorMatched(_))
+        ));
+    }
+
+    #[test]
+    fn parse_texts_with_fallback_selectors() {
+        let expected = vec![TextInfo {
+            name: "Liber I".into(),
+            url: "https://thelatinlibrary.com/caesar/gall1.shtml".into(),
+        }];
+
+        let current = r#"<div class="work"><table><tr>
+            <td><a href="caesar/gall1.shtml">Liber I</a></td>
+        </tr></table></div>"#;
+        assert_eq!(client().parse_texts(current).unwrap(), expected);
+
+        let without_table = r#"<div class="work"><a href="caesar/gall1.shtml">Liber I</a></div>"#;
+        assert_eq!(client().parse_texts(without_table).unwrap(), expected);
+
+        let without_div = r#"<table><tr>
+            <td><a href="caesar/gall1.shtml">Liber I</a></td>
+        </tr></table>"#;
+        assert_eq!(client().parse_texts(without_div).unwrap(), expected);
+
+        assert!(matches!(
+            client().parse_texts("<p>Nihil hic</p>"),
+            Err(SVLError::NoSelectorMatched(_))
+        ));
+    }
+
+    #[test]
+    fn client_with_base_url() {
+        assert_eq!(client().base_url(), "https://thelatinlibrary.com/");
+
+        let mirror = HttpStatsClient::with_base_url("http://localhost:8080/latin").unwrap();
+        assert_eq!(mirror.base_url(), "http://localhost:8080/latin/");
+        assert_eq!(mirror.index_url(), "http://localhost:8080/latin/");
+        assert_eq!(
+            mirror.path_to_url(" caesar.html "),
+            "http://localhost:8080/latin/caesar.html"
+        );
+
+        let html = r#"<form name="myform"><select name="dest">
+            <option value="caesar.html">Caesar</option>
+        </select></form>"#;
+        assert_eq!(
+            mirror.parse_authors(html).unwrap()[0].url,
+            "http://localhost:8080/latin/caesar.html"
+        );
+    }
+
+    #[test]
+    fn client_timeout() {
+        assert_eq!(client().timeout(), HttpStatsClient::DEFAULT_TIMEOUT);
+
+        let client = client().with_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(client.timeout(), Duration::from_secs(5));
+
+        let timeout = SVLError::Timeout {
+            url: "https://thelatinlibrary.com/".into(),
+            timeout: Duration::from_secs(5),
+        };
+        assert!(timeout.is_retryable());
+        assert_eq!(
+            timeout.to_string(),
+            "Request to https://thelatinlibrary.com/ timed out after 5s"
+        );
+    }
+
+    #[test]
+    fn client_concurrency() {
+        assert_eq!(client().concurrency(), HttpStatsClient::DEFAULT_CONCURRENCY);
+
+        let client = client().with_concurrency(4).unwrap();
+        assert_eq!(client.concurrency(), 4);
+        assert_eq!(client.semaphore.available_permits(), 4);
+
+        assert!(matches!(
+            HttpStatsClient::new().unwrap().with_concurrency(0),
+            Err(SVLError::InvalidConcurrency(0))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_text_uses_html_cache() {
+        let dir = std::env::temp_dir().join(format!("svl-html-cache-{}", std::process::id()));
+        // nothing listens on the discard port, so only cached pages can be fetched
+        let url = "http://127.0.0.1:9/caesar/gall1.shtml";
+        let client = HttpStatsClient::with_base_url("http://127.0.0.1:9/")
+            .unwrap()
+            .with_retry_policy(RetryPolicy::none())
+            .with_cache_dir(dir.clone());
+
+        let path = client.cache_path(url).unwrap();
+        assert_eq!(path, client.cache_path(url).unwrap());
+        assert_ne!(path, client.cache_path("http://127.0.0.1:9/x").unwrap());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "<html><body>Gallia est omnis divisa</body></html>").unwrap();
+
+        let text = client.fetch_text(url).await.unwrap();
+        assert_eq!(text.text, "Gallia est omnis divisa");
+
+        let refreshing = client.refresh_cache();
+        assert!(refreshing.fetch_text(url).await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cached_pages_are_written_whole() {
+        let dir = std::env::temp_dir().join(format!("svl-html-write-{}", std::process::id()));
+        let path = dir.join("page.html");
+
+        write_cached_html(&path, "<html></html>").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "<html></html>");
+        // only the page itself is left, no temporary file
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        // a page can't be written below a file
+        assert!(write_cached_html(&path.join("x.html"), "").await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fnv1a_hash_is_stable() {
+        assert_eq!(fnv1a_hash(""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_hash("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(1), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(3), Duration::from_secs(5));
+        assert_eq!(policy.backoff(100), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn retry_policy_retries_server_errors_only() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        };
+        let status = |status: u16| SVLError::HttpStatus {
+            url: "https://thelatinlibrary.com/caesar.html".into(),
+            status,
+        };
+
+        let mut attempts = 0;
+        let result = policy
+            .run("caesar", || {
+                attempts += 1;
+                let result = if attempts < 3 {
+                    Err(status(503))
+                } else {
+                    Ok(attempts)
+                };
+                async move { result }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let mut attempts = 0;
+        let result: crate::Result<()> = policy
+            .run("caesar", || {
+                attempts += 1;
+                let result = Err(status(404));
+                async move { result }
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(SVLError::HttpStatus { status: 404, .. })
+        ));
+        assert_eq!(attempts, 1);
+
+        let mut attempts = 0;
+        let result: crate::Result<()> = RetryPolicy::none()
+            .run("caesar", || {
+                attempts += 1;
+                let result = Err(status(503));
+                async move { result }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn author_from_enumerated_author_info() {
+        let author = Author::from((3, author_info("Caesar")));
+
+        assert_eq!(
+            author,
+            Author {
+                author_id: 3,
+                name: "Caesar".into(),
+                url: "https://thelatinlibrary.com/caesar.html".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn import_row_uses_enumerated_id() {
+        let authors = vec![author_info("Caesar"), author_info("Cicero")];
+        let rows: Vec<_> = authors
+            .into_iter()
+            .enumerate()
+            .map(Author::from)
+            .map(|a| a.to_data_value())
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                val(vec![
+                    val(0usize),
+                    val("Caesar"),
+                    val("https://thelatinlibrary.com/caesar.html")
+                ]),
+                val(vec![
+                    val(1usize),
+                    val("Cicero"),
+                    val("https://thelatinlibrary.com/cicero.html")
+                ]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn warm_index_cache_skips_index_requests() {
+        let db = DBConnection::new_in_memory().unwrap();
+        let index = CountingIndex::default();
+
+        let cache = IndexCache::open(&db, IndexCache::DEFAULT_TTL)
+            .await
+            .unwrap();
+        let cold = fetch_index(&cache, &index).await;
+        assert_eq!(index.requests(), (1, 2));
+
+        let warm = fetch_index(&cache, &index).await;
+        assert_eq!(warm, cold);
+        assert_eq!(index.requests(), (1, 2));
+
+        // opening again must not fail on the already existing relation
+        let expired = IndexCache::open(&db, Duration::ZERO).await.unwrap();
+        let refetched = fetch_index(&expired, &index).await;
+        assert_eq!(refetched, cold);
+        assert_eq!(index.requests(), (2, 4));
+    }
+}
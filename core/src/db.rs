@@ -1,13 +1,44 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::sync::{Arc, Mutex};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::task;
 
 pub use cozo::{DataValue, JsonData, MultiTransaction, NamedRows, Num, Validity, Vector};
 use cozo::{DbInstance, ScriptMutability};
 
+const LOG_TARGET: &str = "svl::db";
+
 pub type DBResult = Result<NamedRows, DBError>;
 pub type DBParams = BTreeMap<String, DataValue>;
 
+pub const AUTHOR_SCHEMA: &str = ":create Author { author_id: Int, name: String => url: String }";
+pub const WORD_SCHEMA: &str = ":create Word { word: String, text_id: Int => count: Int }";
+pub const TEXT_SCHEMA: &str =
+    ":create Text { text_id: Int, author_id: Int => url: String, text: String }";
+/// Lemma of each stored word, if the import lemmatized words.
+pub const LEMMA_SCHEMA: &str = ":create Lemma { word: String => lemma: String }";
+/// Counts of pairs of consecutive words per text, if the import tracked them.
+pub const BIGRAM_SCHEMA: &str =
+    ":create BiGram { first: String, second: String, text_id: Int => count: Int }";
+/// Parsed contents of library index pages (as JSON), keyed by page URL.
+pub const INDEX_CACHE_SCHEMA: &str =
+    ":create IndexCache { page_url: String => data: String, fetched_at: Float }";
+
+/// Relations every DB has had from the start, unlike those added to the schema later.
+pub const REQUIRED_SCHEMA: [&str; 3] = [AUTHOR_SCHEMA, WORD_SCHEMA, TEXT_SCHEMA];
+
+/// `:create` scripts for all relations making up the svl schema.
+pub const SCHEMA: [&str; 6] = [
+    AUTHOR_SCHEMA,
+    WORD_SCHEMA,
+    TEXT_SCHEMA,
+    LEMMA_SCHEMA,
+    BIGRAM_SCHEMA,
+    INDEX_CACHE_SCHEMA,
+];
+
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
 pub enum DBError {
     #[error("DB error: {0}")]
@@ -16,13 +47,127 @@ pub enum DBError {
     #[error("Tokio task error: {0}")]
     JoinError(String),
 
+    #[error("Database {} is locked by another process: {message}", .path.display())]
+    Locked { path: PathBuf, message: String },
+
+    #[error("Database {} is corrupt: {message}", .path.display())]
+    Corrupt { path: PathBuf, message: String },
+
+    #[error("Cannot open database {}: {message}", .path.display())]
+    CannotOpen { path: PathBuf, message: String },
+
+    #[error("Missing schema, relations {} don't exist (run `svl create-db` first)", .missing.join(", "))]
+    MissingSchema { missing: Vec<String> },
+
+    #[error("Unknown relation: {name} (known relations: {})", .known.join(", "))]
+    UnknownRelation { name: String, known: Vec<String> },
+
+    #[error("Missing column {0} in row")]
+    MissingColumn(usize),
+
+    #[error("Invalid type in column {column}, expected {expected}")]
+    InvalidType {
+        column: usize,
+        expected: ExpectedType,
+    },
+
+    #[error("Invalid export data: {0}")]
+    InvalidExport(String),
+
+    #[error("Query timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("DB write stalled: {0}")]
+    WriteStall(String),
+
+    #[error("Cannot explain script: {0}")]
+    CannotExplain(String),
+
     #[error("Other error: {0}")]
     Other(String),
 }
 
+/// Type a column value was expected to have when decoding a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedType {
+    Boolean,
+    Float,
+    Integer,
+    String,
+    Usize,
+}
+
+impl std::fmt::Display for ExpectedType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpectedType::Boolean => write!(f, "Boolean"),
+            ExpectedType::Float => write!(f, "Float"),
+            ExpectedType::Integer => write!(f, "Integer"),
+            ExpectedType::String => write!(f, "String"),
+            ExpectedType::Usize => write!(f, "Usize"),
+        }
+    }
+}
+
+impl DBError {
+    // Cozo prefixes the messages of rocksdb statuses with this
+    const ROCKSDB_ERROR: &'static str = "RocksDB error: ";
+
+    // how rocksdb's `Status::ToString` starts for writes stalled by heavy compaction:
+    // `kBusy`, `kTimedOut`, `kTryAgain` and `kIncomplete` from `no_slowdown` writes
+    const WRITE_STALL_STATUSES: &'static [&'static str] = &[
+        "Resource busy: ",
+        "Operation timed out: ",
+        "Operation failed. Try again.: ",
+        "Incomplete: Write stall",
+    ];
+
+    // statuses without a message are shown with their debug representation instead
+    const WRITE_STALL_CODES: &'static [&'static str] =
+        &["code: kBusy,", "code: kTimedOut,", "code: kTryAgain,"];
+
+    /// Wraps an error message from Cozo, telling rocksdb write stalls apart from other errors.
+    pub fn from_cozo_message(message: String) -> Self {
+        let is_stall = message
+            .split_once(Self::ROCKSDB_ERROR)
+            .is_some_and(|(_, status)| {
+                Self::WRITE_STALL_STATUSES
+                    .iter()
+                    .any(|prefix| status.starts_with(prefix))
+                    || Self::WRITE_STALL_CODES
+                        .iter()
+                        .any(|code| status.contains(code))
+            });
+
+        if is_stall {
+            Self::WriteStall(message)
+        } else {
+            Self::Cozo(message)
+        }
+    }
+
+    /// Tells a locked or corrupt store apart from other failures to open the one at `path`.
+    pub fn from_open_message(path: &Path, message: String) -> Self {
+        let path = path.to_path_buf();
+        let lowercase = message.to_lowercase();
+        if lowercase.contains("corrupt") || lowercase.contains("checksum") {
+            Self::Corrupt { path, message }
+        } else if lowercase.contains("lock file") || message.contains("/LOCK") {
+            Self::Locked { path, message }
+        } else {
+            Self::CannotOpen { path, message }
+        }
+    }
+
+    /// Whether the failed operation may succeed if retried later.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::WriteStall(_))
+    }
+}
+
 impl From<cozo::Error> for DBError {
     fn from(e: cozo::Error) -> Self {
-        Self::Cozo(e.to_string())
+        Self::from_cozo_message(e.to_string())
     }
 }
 
@@ -32,65 +177,581 @@ impl From<tokio::task::JoinError> for DBError {
     }
 }
 
+/// A stored relation and its columns, see `DBConnection::relations`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationInfo {
+    pub name: String,
+    pub columns: Vec<ColumnInfo>,
+}
+
+impl RelationInfo {
+    pub fn keys(&self) -> impl Iterator<Item = &ColumnInfo> {
+        self.columns.iter().filter(|c| c.is_key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &ColumnInfo> {
+        self.columns.iter().filter(|c| !c.is_key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub is_key: bool,
+    /// Cozo's name of the column type, e.g. `String` or `Int`.
+    pub column_type: String,
+}
+
+/// Shared connection to a store. `DbInstance` is `Send + Sync` and handles
+/// concurrent queries itself, so clones don't serialize queries on each other.
 #[derive(Clone)]
 pub struct DBConnection {
-    db: Arc<Mutex<DbInstance>>,
+    db: Arc<DbInstance>,
+    path: Option<PathBuf>,
+    timeout: Option<Duration>,
 }
 
 impl DBConnection {
-    pub fn new() -> Result<Self, String> {
-        let db = DbInstance::new_with_str("rocksdb", "svl-stats.db", Default::default())?;
+    pub const DEFAULT_PATH: &'static str = "svl-stats.db";
+    // trivial query run after opening a store, failing if it's unusable
+    const HEALTH_CHECK: &'static str = "?[x] <- [[1]]";
+
+    pub fn new() -> Result<Self, DBError> {
+        Self::with_path(Self::DEFAULT_PATH)
+    }
+
+    /// Opens (or creates) the rocksdb store at `path` and checks it can run queries.
+    /// Fails with `DBError::Locked` if another process has it open and with
+    /// `DBError::Corrupt` if it can't be read.
+    pub fn with_path(path: impl AsRef<Path>) -> Result<Self, DBError> {
+        let path = path.as_ref();
+        let db = DbInstance::new_with_str("rocksdb", &path.to_string_lossy(), Default::default())
+            .map_err(|message| DBError::from_open_message(path, message))?;
+        db.run_script(
+            Self::HEALTH_CHECK,
+            Default::default(),
+            ScriptMutability::Immutable,
+        )
+        .map_err(|e| match DBError::from_open_message(path, e.to_string()) {
+            DBError::CannotOpen { path, message } => DBError::Corrupt { path, message },
+            e => e,
+        })?;
         Ok(Self {
-            db: Arc::new(Mutex::new(db)),
+            db: Arc::new(db),
+            path: Some(path.to_path_buf()),
+            timeout: None,
         })
     }
 
-    pub async fn run_immutable(&self, script: &str, params: DBParams) -> DBResult {
-        let db = Arc::clone(&self.db);
-        let script = script.to_string();
-        let params = params.clone();
-        task::spawn_blocking(move || {
-            let db = db.lock().unwrap();
-            db.run_script(&script, params, ScriptMutability::Immutable)
+    /// Creates a database that only lives in memory, e.g. for tests or
+    /// ephemeral sessions that shouldn't touch the rocksdb store on disk.
+    pub fn new_in_memory() -> Result<Self, DBError> {
+        let db = DbInstance::new_with_str("mem", "", Default::default()).map_err(DBError::Other)?;
+        Ok(Self {
+            db: Arc::new(db),
+            path: None,
+            timeout: None,
         })
-        .await?
-        .map_err(|e| DBError::Cozo(e.to_string()))
+    }
+
+    /// Timeout of scripts set with `with_timeout`, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Path of the store on disk, `None` for in-memory databases.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Fails scripts run through this connection (and its clones) with
+    /// `DBError::Timeout` once they take longer than `timeout`.
+    ///
+    /// Cozo can't interrupt a running script, so it keeps running in the background
+    /// until done: a timed out mutable script may still commit its changes.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub async fn run_immutable(&self, script: &str, params: DBParams) -> DBResult {
+        self.run_script(script, params, ScriptMutability::Immutable)
+            .await
+    }
+
+    /// Runs a full rocksdb compaction (`::compact`), which rewrites the whole store.
+    ///
+    /// Slow for large stores, so it's only worth it after big imports.
+    pub async fn compact(&self) -> Result<(), DBError> {
+        self.run_mutable("::compact", DBParams::new()).await?;
+        Ok(())
+    }
+
+    /// Closes the store, waiting for rocksdb's background work to finish.
+    ///
+    /// Clones of this connection share the same store, which only closes once the
+    /// last clone is dropped. If other clones are still around, this does nothing
+    /// and the store stays open (and usable) through them. Writes are persisted
+    /// through rocksdb's write-ahead log either way.
+    pub async fn close(self) -> Result<(), DBError> {
+        if let Ok(db) = Arc::try_unwrap(self.db) {
+            // closing rocksdb waits for background work, so keep it off the runtime threads
+            task::spawn_blocking(move || drop(db)).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs a read-only script in pages of `page_size` rows, see `PagedRows`.
+    pub fn run_immutable_paged(
+        &self,
+        script: &str,
+        params: DBParams,
+        page_size: usize,
+    ) -> PagedRows {
+        PagedRows {
+            db: self.clone(),
+            script: script.to_string(),
+            params,
+            page_size: page_size.max(1),
+            offset: 0,
+            done: false,
+        }
     }
 
     pub async fn run_mutable(&self, script: &str, params: DBParams) -> DBResult {
+        self.run_script(script, params, ScriptMutability::Mutable)
+            .await
+    }
+
+    async fn run_script(
+        &self,
+        script: &str,
+        params: DBParams,
+        mutability: ScriptMutability,
+    ) -> DBResult {
         let db = Arc::clone(&self.db);
         let script = script.to_string();
-        let params = params.clone();
-        task::spawn_blocking(move || {
-            let db = db.lock().unwrap();
-            db.run_script(&script, params, ScriptMutability::Mutable)
-        })
-        .await?
-        .map_err(|e| DBError::Cozo(e.to_string()))
+        let run = task::spawn_blocking(move || db.run_script(&script, params, mutability));
+        let result = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run).await.map_err(|_| {
+                log::warn!(target: LOG_TARGET, "Query timed out after {:?}", timeout);
+                DBError::Timeout(timeout)
+            })??,
+            None => run.await?,
+        };
+        result.map_err(|e| DBError::from_cozo_message(e.to_string()))
     }
 
-    pub fn multi_tx(&self, write: bool) -> AsyncMultiTransaction {
+    /// Returns Cozo's query plan for the given query script instead of running it.
+    pub async fn explain(&self, script: &str, params: DBParams) -> DBResult {
+        let script = script.trim().trim_end_matches(';');
+        if script.is_empty() {
+            return Err(DBError::CannotExplain("empty script".into()));
+        }
+        if script.starts_with("::") {
+            return Err(DBError::CannotExplain(
+                "system commands have no query plan".into(),
+            ));
+        }
+
+        self.run_immutable(&format!("::explain {{ {} }}", script), params)
+            .await
+            .map_err(|e| match e {
+                DBError::Cozo(msg) => DBError::CannotExplain(msg),
+                e => e,
+            })
+    }
+
+    /// Fails with `DBError::MissingSchema` unless the relations every query needs
+    /// (`REQUIRED_SCHEMA`) exist. The others are created by imports when missing.
+    pub async fn verify_schema(&self) -> Result<(), DBError> {
+        let existing = self.relation_names().await?;
+        let missing: Vec<String> = REQUIRED_SCHEMA
+            .into_iter()
+            .map(schema_relation)
+            .filter(|name| !existing.iter().any(|n| n == name))
+            .map(String::from)
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(DBError::MissingSchema { missing })
+        }
+    }
+
+    /// Creates all relations of the schema that don't exist yet, so it can be run
+    /// again on an existing DB. Returns the names of the newly created relations.
+    pub async fn create_schema(&self) -> Result<Vec<String>, DBError> {
+        let existing = self.relation_names().await?;
+        let missing: Vec<&str> = SCHEMA
+            .into_iter()
+            .filter(|script| !existing.iter().any(|n| n == schema_relation(script)))
+            .collect();
+
+        let tx = self.multi_tx(true);
+        tx.run_scripts(missing.iter().map(|script| (*script, DBParams::new())))?;
+        tx.commit().await?;
+
+        Ok(missing
+            .into_iter()
+            .map(|script| schema_relation(script).to_string())
+            .collect())
+    }
+
+    /// Drops all relations of the schema (including their data) and creates them again.
+    pub async fn recreate_schema(&self) -> Result<Vec<String>, DBError> {
+        let existing = self.relation_names().await?;
+        let to_remove: Vec<&str> = SCHEMA
+            .into_iter()
+            .map(schema_relation)
+            .filter(|name| existing.iter().any(|n| n == name))
+            .collect();
+
+        if !to_remove.is_empty() {
+            self.run_mutable(
+                &format!("::remove {}", to_remove.join(", ")),
+                DBParams::new(),
+            )
+            .await?;
+        }
+
+        self.create_schema().await
+    }
+
+    /// Exports all rows of the given relations as JSON, in the form
+    /// `{ "<relation>": { "headers": [...], "rows": [[...], ...] } }`.
+    pub async fn export_relations(&self, relations: &[&str]) -> Result<serde_json::Value, DBError> {
+        let db = Arc::clone(&self.db);
+        let relations: Vec<String> = relations.iter().map(|r| r.to_string()).collect();
+        let exported = task::spawn_blocking(move || db.export_relations(relations.iter()))
+            .await?
+            .map_err(|e| DBError::from_cozo_message(e.to_string()))?;
+
+        Ok(serde_json::Value::Object(
+            exported
+                .into_iter()
+                .map(|(name, rows)| (name, rows.into_json()))
+                .collect(),
+        ))
+    }
+
+    /// Imports rows exported by `export_relations` into the (already existing) relations.
+    pub async fn import_relations(&self, data: &serde_json::Value) -> Result<(), DBError> {
+        let relations = data
+            .as_object()
+            .ok_or_else(|| DBError::InvalidExport("expected an object of relations".into()))?
+            .iter()
+            .map(|(name, rows)| Ok((name.clone(), named_rows_from_json(name, rows)?)))
+            .collect::<Result<BTreeMap<_, _>, DBError>>()?;
+
         let db = Arc::clone(&self.db);
-        let db = db.lock().unwrap();
-        let tx = db.multi_transaction(write);
-        AsyncMultiTransaction(tx)
+        task::spawn_blocking(move || db.import_relations(relations))
+            .await?
+            .map_err(|e| DBError::from_cozo_message(e.to_string()))
+    }
+
+    /// Returns the names of all stored relations in the database.
+    pub async fn relation_names(&self) -> Result<Vec<String>, DBError> {
+        let relations = self.run_immutable("::relations", DBParams::new()).await?;
+        let name = column_position(&relations, "name")?;
+
+        Ok(relations
+            .rows
+            .iter()
+            .filter_map(|row| row.get(name).and_then(|n| n.get_str()))
+            .map(|n| n.to_string())
+            .collect())
+    }
+
+    /// All stored relations with their columns, keys first, e.g. to show the schema.
+    pub async fn relations(&self) -> Result<Vec<RelationInfo>, DBError> {
+        let mut relations = Vec::new();
+
+        for name in self.relation_names().await? {
+            let rows = self
+                .run_immutable(&format!("::columns {}", name), DBParams::new())
+                .await?;
+            let column = column_position(&rows, "column")?;
+            let is_key = column_position(&rows, "is_key")?;
+            let column_type = column_position(&rows, "type")?;
+
+            let columns = rows
+                .rows
+                .iter()
+                .map(|row| {
+                    Ok(ColumnInfo {
+                        name: get_str(row, column)?,
+                        is_key: get_bool(row, is_key)?,
+                        column_type: get_str(row, column_type)?,
+                    })
+                })
+                .collect::<Result<_, DBError>>()?;
+
+            relations.push(RelationInfo { name, columns });
+        }
+
+        Ok(relations)
+    }
+
+    /// Counts the rows stored in the given relation.
+    /// Fails with `DBError::UnknownRelation` if no such relation exists.
+    pub async fn count(&self, relation: &str) -> Result<usize, DBError> {
+        let relations = self.run_immutable("::relations", DBParams::new()).await?;
+        let name = column_position(&relations, "name")?;
+        let arity = column_position(&relations, "arity")?;
+
+        let arity = relations
+            .rows
+            .iter()
+            .find(|row| row.get(name).and_then(|n| n.get_str()) == Some(relation))
+            .and_then(|row| row.get(arity).and_then(|a| a.get_int()));
+
+        let Some(arity) = arity else {
+            let known = relations
+                .rows
+                .iter()
+                .filter_map(|row| row.get(name).and_then(|n| n.get_str()))
+                .map(|n| n.to_string())
+                .collect();
+            return Err(DBError::UnknownRelation {
+                name: relation.to_string(),
+                known,
+            });
+        };
+
+        // bind every column so each stored row is counted exactly once
+        let columns = (0..arity)
+            .map(|idx| format!("c{}", idx))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let script = format!("?[count(c0)] := *{}[{}]", relation, columns);
+
+        let rows = self.run_immutable(&script, DBParams::new()).await?;
+        let count = rows
+            .rows
+            .first()
+            .and_then(|row| row.first())
+            .and_then(|c| c.get_int())
+            .unwrap_or(0);
+
+        Ok(count as usize)
+    }
+
+    pub fn multi_tx(&self, write: bool) -> AsyncMultiTransaction {
+        let tx = self.db.multi_transaction(write);
+        AsyncMultiTransaction(Some(tx))
+    }
+}
+
+/// Name of the relation created by one of the `SCHEMA` scripts.
+fn schema_relation(script: &str) -> &str {
+    script
+        .trim_start_matches(":create")
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+}
+
+fn named_rows_from_json(relation: &str, json: &serde_json::Value) -> Result<NamedRows, DBError> {
+    let invalid = |what: &str| DBError::InvalidExport(format!("{} of {}", what, relation));
+
+    let headers = json
+        .get("headers")
+        .and_then(|h| h.as_array())
+        .ok_or_else(|| invalid("missing headers"))?
+        .iter()
+        .map(|h| {
+            h.as_str()
+                .map(String::from)
+                .ok_or_else(|| invalid("invalid header"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let rows = json
+        .get("rows")
+        .and_then(|r| r.as_array())
+        .ok_or_else(|| invalid("missing rows"))?
+        .iter()
+        .map(|row| {
+            row.as_array()
+                .map(|values| values.iter().cloned().map(DataValue::from).collect())
+                .ok_or_else(|| invalid("invalid row"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(NamedRows::new(headers, rows))
+}
+
+fn column_position(rows: &NamedRows, column: &str) -> Result<usize, DBError> {
+    rows.headers
+        .iter()
+        .position(|h| h == column)
+        .ok_or_else(|| DBError::Other(format!("Missing column: {}", column)))
+}
+
+/// How often and how long to back off when writes fail transiently,
+/// e.g. while rocksdb stalls writes during heavy compaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StallRetry {
+    pub max_retries: usize,
+    pub initial_backoff: Duration,
+}
+
+impl Default for StallRetry {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+impl StallRetry {
+    /// Runs `op`, retrying it with exponential backoff as long as it fails with a transient error.
+    pub async fn run<T, F, Fut>(&self, mut op: F) -> Result<T, DBError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, DBError>>,
+    {
+        let mut backoff = self.initial_backoff;
+        let mut retries = 0;
+
+        loop {
+            match op().await {
+                Err(e) if e.is_transient() && retries < self.max_retries => {
+                    retries += 1;
+                    log::warn!(
+                        target: LOG_TARGET,
+                        "{}, retrying in {:?} ({}/{})",
+                        e,
+                        backoff,
+                        retries,
+                        self.max_retries
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
     }
 }
 
-pub struct AsyncMultiTransaction(MultiTransaction);
+/// A transaction spanning multiple scripts.
+///
+/// Changes made within the transaction only become visible once `commit`
+/// succeeds. Calling `rollback`, or dropping the transaction without
+/// committing it, discards all of its changes.
+pub struct AsyncMultiTransaction(Option<MultiTransaction>);
 
 impl AsyncMultiTransaction {
-    pub async fn commit(self) -> Result<(), DBError> {
-        let tx = self.0;
+    /// Commits all changes made within the transaction.
+    ///
+    /// Cozo's `MultiTransaction::commit` drops the result of the actual commit
+    /// and only reports failures to reach the transaction, so a commit that fails
+    /// in rocksdb (e.g. in a write conflict) still returns `Ok(())` here.
+    pub async fn commit(mut self) -> Result<(), DBError> {
+        let tx = self.take();
         task::spawn_blocking(move || tx.commit())
             .await?
-            .map_err(|e| DBError::Cozo(e.to_string()))
+            .map_err(|e| DBError::from_cozo_message(e.to_string()))
+    }
+
+    pub async fn rollback(mut self) -> Result<(), DBError> {
+        let tx = self.take();
+        task::spawn_blocking(move || tx.abort())
+            .await?
+            .map_err(|e| DBError::from_cozo_message(e.to_string()))
     }
 
     pub fn run_script(&self, script: &str, params: DBParams) -> DBResult {
-        let AsyncMultiTransaction(tx) = self;
+        let tx = self
+            .0
+            .as_ref()
+            .expect("Transaction should not be finished yet");
         tx.run_script(script, params)
-            .map_err(|e| DBError::Cozo(e.to_string()))
+            .map_err(|e| DBError::from_cozo_message(e.to_string()))
+    }
+
+    /// Runs the scripts one after another, stopping at the first one that fails.
+    /// Returns the results of all scripts, in order.
+    pub fn run_scripts<'a>(
+        &self,
+        scripts: impl IntoIterator<Item = (&'a str, DBParams)>,
+    ) -> Result<Vec<NamedRows>, DBError> {
+        scripts
+            .into_iter()
+            .map(|(script, params)| self.run_script(script, params))
+            .collect()
+    }
+
+    fn take(&mut self) -> MultiTransaction {
+        self.0
+            .take()
+            .expect("Transaction should not be finished yet")
+    }
+}
+
+impl Drop for AsyncMultiTransaction {
+    fn drop(&mut self) {
+        if let Some(tx) = self.0.take() {
+            if let Err(e) = tx.abort() {
+                log::warn!(
+                    target: LOG_TARGET,
+                    "Failed to roll back uncommitted transaction: {}",
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// The results of a read-only script, fetched page by page with `:limit` and `:offset`
+/// so that large results never need to be held in memory at once.
+/// The script must not have a `:limit` or `:offset` of its own.
+///
+/// Every page runs the whole script again and skips the rows of the previous pages,
+/// so fetching all pages takes quadratic time in the number of pages. Each page also
+/// reads its own snapshot of the database: rows written while paging can be skipped
+/// or returned twice.
+pub struct PagedRows {
+    db: DBConnection,
+    script: String,
+    params: DBParams,
+    page_size: usize,
+    offset: usize,
+    done: bool,
+}
+
+impl PagedRows {
+    /// The next page of rows, or `None` after the last one. The first page is
+    /// returned even without any rows, so the headers are always known.
+    pub async fn next_page(&mut self) -> Option<DBResult> {
+        if self.done {
+            return None;
+        }
+
+        let script = format!(
+            "{}\n:limit {}\n:offset {}",
+            self.script, self.page_size, self.offset
+        );
+        let result = self.db.run_immutable(&script, self.params.clone()).await;
+
+        match &result {
+            Ok(rows) => {
+                self.done = rows.rows.len() < self.page_size;
+                if rows.rows.is_empty() && self.offset > 0 {
+                    return None;
+                }
+                self.offset += rows.rows.len();
+            }
+            Err(_) => self.done = true,
+        }
+
+        Some(result)
     }
 }
 
@@ -187,10 +848,595 @@ impl ToDataValue for () {
 
 impl ToDataValue for usize {
     fn to_data_value(&self) -> DataValue {
-        DataValue::Num(Num::Int(*self as i64))
+        // saturate instead of wrapping around to negative numbers
+        DataValue::Num(Num::Int(i64::try_from(*self).unwrap_or(i64::MAX)))
+    }
+}
+
+impl<T: ToDataValue> ToDataValue for Option<T> {
+    fn to_data_value(&self) -> DataValue {
+        match self {
+            Some(v) => v.to_data_value(),
+            None => DataValue::Null,
+        }
     }
 }
 
 pub fn val<V: ToDataValue>(v: V) -> DataValue {
     v.to_data_value()
 }
+
+/// Converts a value into JSON, the counterpart to `ToDataValue`, using Cozo's own
+/// conversion: bytes are base64 encoded, `NaN` becomes `null`, infinite floats
+/// become `"INFINITY"` or `"NEGATIVE_INFINITY"` and vectors become arrays.
+pub fn data_value_to_json(v: &DataValue) -> serde_json::Value {
+    serde_json::Value::from(v.clone())
+}
+
+/// Number of decimal places floats are formatted with by `format_data_value`.
+pub const DEFAULT_FLOAT_PRECISION: usize = 4;
+
+/// Formats a value for humans, e.g. in result tables: strings without quotes,
+/// floats with `DEFAULT_FLOAT_PRECISION` decimal places (whole numbers without any,
+/// e.g. the results of `sum`), lists and sets as
+/// `[a, b, c]` and null as an empty string.
+pub fn format_data_value(v: &DataValue) -> String {
+    format_data_value_with_precision(v, DEFAULT_FLOAT_PRECISION)
+}
+
+/// Like `format_data_value`, with floats formatted with `precision` decimal places.
+pub fn format_data_value_with_precision(v: &DataValue, precision: usize) -> String {
+    let format_all = |values: &mut dyn Iterator<Item = &DataValue>| {
+        let values: Vec<String> = values
+            .map(|v| format_data_value_with_precision(v, precision))
+            .collect();
+        format!("[{}]", values.join(", "))
+    };
+
+    match v {
+        DataValue::Null => String::new(),
+        DataValue::Bool(b) => b.to_string(),
+        DataValue::Num(Num::Int(i)) => i.to_string(),
+        DataValue::Num(Num::Float(f)) if f.fract() == 0.0 && f.abs() < 1e15 => {
+            format!("{:.0}", f)
+        }
+        DataValue::Num(Num::Float(f)) => format!("{:.*}", precision, f),
+        DataValue::Str(s) => s.to_string(),
+        DataValue::List(values) => format_all(&mut values.iter()),
+        DataValue::Set(values) => format_all(&mut values.iter()),
+        DataValue::Json(JsonData(json)) => json.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Converts all rows into maps keyed by their column headers, following the
+/// `next` chain so that all results are included.
+pub fn rows_as_maps(rows: &NamedRows) -> Vec<BTreeMap<String, DataValue>> {
+    let mut maps = Vec::with_capacity(rows.rows.len());
+    let mut current = Some(rows);
+
+    while let Some(named_rows) = current {
+        maps.extend(named_rows.rows.iter().map(|row| {
+            named_rows
+                .headers
+                .iter()
+                .cloned()
+                .zip(row.iter().cloned())
+                .collect::<BTreeMap<_, _>>()
+        }));
+        current = named_rows.next.as_deref();
+    }
+
+    maps
+}
+
+fn column(row: &[DataValue], idx: usize) -> Result<&DataValue, DBError> {
+    row.get(idx).ok_or(DBError::MissingColumn(idx))
+}
+
+fn decode<T>(
+    row: &[DataValue],
+    idx: usize,
+    expected: ExpectedType,
+    get: impl FnOnce(&DataValue) -> Option<T>,
+) -> Result<T, DBError> {
+    get(column(row, idx)?).ok_or(DBError::InvalidType {
+        column: idx,
+        expected,
+    })
+}
+
+/// Decodes the string in column `idx` of a result row.
+pub fn get_str(row: &[DataValue], idx: usize) -> Result<String, DBError> {
+    decode(row, idx, ExpectedType::String, |v| {
+        v.get_str().map(|s| s.to_string())
+    })
+}
+
+pub fn get_int(row: &[DataValue], idx: usize) -> Result<i64, DBError> {
+    decode(row, idx, ExpectedType::Integer, DataValue::get_int)
+}
+
+/// Decodes a non-negative integer, e.g. an id or a count.
+pub fn get_usize(row: &[DataValue], idx: usize) -> Result<usize, DBError> {
+    decode(row, idx, ExpectedType::Usize, |v| {
+        v.get_int().and_then(|i| usize::try_from(i).ok())
+    })
+}
+
+pub fn get_float(row: &[DataValue], idx: usize) -> Result<f64, DBError> {
+    decode(row, idx, ExpectedType::Float, DataValue::get_float)
+}
+
+pub fn get_bool(row: &[DataValue], idx: usize) -> Result<bool, DBError> {
+    decode(row, idx, ExpectedType::Boolean, |v| match v {
+        DataValue::Bool(b) => Some(*b),
+        _ => None,
+    })
+}
+
+/// An in-memory DB with the schema created, for tests.
+#[cfg(test)]
+pub(crate) async fn empty_db() -> DBConnection {
+    let db = DBConnection::new_in_memory().unwrap();
+    db.create_schema().await.unwrap();
+    db
+}
+
+/// An in-memory DB with the schema created and `script` run on it to store test data.
+#[cfg(test)]
+pub(crate) async fn seeded_db(script: &str) -> DBConnection {
+    let db = empty_db().await;
+    db.run_mutable(script, DBParams::new()).await.unwrap();
+    db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn explain_returns_query_plan() {
+        let db = empty_db().await;
+
+        let plan = db
+            .explain(
+                "?[word] := *Word{word}, starts_with(word, 'am')",
+                DBParams::new(),
+            )
+            .await
+            .unwrap();
+        assert!(!plan.rows.is_empty());
+
+        assert!(matches!(
+            db.explain("::relations", DBParams::new()).await,
+            Err(DBError::CannotExplain(_))
+        ));
+        assert!(matches!(
+            db.explain("?[x] := *Missing{x}", DBParams::new()).await,
+            Err(DBError::CannotExplain(_))
+        ));
+    }
+
+    #[test]
+    fn classify_write_stalls() {
+        for message in [
+            "RocksDB error: Resource busy: stalled",
+            "RocksDB error: Operation failed. Try again.: ",
+            "RocksDB error: Incomplete: Write stall",
+            "RocksDB error: RocksDbStatus { code: kTimedOut, subcode: kNone, severity: kNoError, message: \"\" }",
+        ] {
+            assert_eq!(
+                DBError::from_cozo_message(message.into()),
+                DBError::WriteStall(message.into())
+            );
+        }
+        // mentioning a stall isn't enough, it has to be rocksdb's status
+        for message in [
+            "Resource busy: stalled",
+            "Cannot find relation 'timed out', try again",
+            "RocksDB error: IO error: No such file: resource busy: ",
+        ] {
+            assert_eq!(
+                DBError::from_cozo_message(message.into()),
+                DBError::Cozo(message.into())
+            );
+        }
+        assert_eq!(
+            DBError::from_cozo_message("Cannot find requested stored relation 'X'".into()),
+            DBError::Cozo("Cannot find requested stored relation 'X'".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn stall_retry_retries_transient_errors() {
+        let retry = StallRetry {
+            max_retries: 3,
+            initial_backoff: Duration::ZERO,
+        };
+        let stall = || DBError::WriteStall("Resource busy".into());
+
+        // stalls twice, then succeeds
+        let mut attempts = 0;
+        let result = retry
+            .run(|| {
+                attempts += 1;
+                let result = if attempts <= 2 {
+                    Err(stall())
+                } else {
+                    Ok(attempts)
+                };
+                async move { result }
+            })
+            .await;
+        assert_eq!(result, Ok(3));
+
+        // gives up after max_retries
+        let mut attempts = 0;
+        let result: Result<(), _> = retry
+            .run(|| {
+                attempts += 1;
+                let result = Err(stall());
+                async move { result }
+            })
+            .await;
+        assert_eq!(result, Err(stall()));
+        assert_eq!(attempts, 4);
+
+        // other errors are not retried
+        let mut attempts = 0;
+        let result: Result<(), _> = retry
+            .run(|| {
+                attempts += 1;
+                async { Err(DBError::Other("broken".into())) }
+            })
+            .await;
+        assert_eq!(result, Err(DBError::Other("broken".into())));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn decode_row_values() {
+        let row = vec![val("amo"), val(3i64), val(0.5), val(true), val(-1i64)];
+
+        assert_eq!(get_str(&row, 0), Ok("amo".to_string()));
+        assert_eq!(get_int(&row, 1), Ok(3));
+        assert_eq!(get_usize(&row, 1), Ok(3));
+        assert_eq!(get_float(&row, 2), Ok(0.5));
+        assert_eq!(get_bool(&row, 3), Ok(true));
+
+        assert_eq!(
+            get_int(&row, 0),
+            Err(DBError::InvalidType {
+                column: 0,
+                expected: ExpectedType::Integer
+            })
+        );
+        assert_eq!(
+            get_usize(&row, 4),
+            Err(DBError::InvalidType {
+                column: 4,
+                expected: ExpectedType::Usize
+            })
+        );
+        assert_eq!(get_str(&row, 5), Err(DBError::MissingColumn(5)));
+        assert_eq!(
+            get_str(&row, 1).unwrap_err().to_string(),
+            "Invalid type in column 1, expected String"
+        );
+    }
+
+    #[test]
+    fn rows_as_maps_follows_next_chain() {
+        let mut rows = NamedRows::new(
+            vec!["word".into(), "count".into()],
+            vec![vec![val("amo"), val(3i64)]],
+        );
+        rows.next = Some(Box::new(NamedRows::new(
+            vec!["word".into()],
+            vec![vec![val("amas")], vec![val("amat")]],
+        )));
+
+        let maps = rows_as_maps(&rows);
+        assert_eq!(maps.len(), 3);
+        assert_eq!(
+            maps[0],
+            BTreeMap::from([
+                ("word".to_string(), val("amo")),
+                ("count".to_string(), val(3i64)),
+            ])
+        );
+        assert_eq!(maps[2], BTreeMap::from([("word".to_string(), val("amat"))]));
+    }
+
+    #[test]
+    fn classify_open_errors() {
+        let path = Path::new("svl-stats.db");
+        assert!(matches!(
+            DBError::from_open_message(
+                path,
+                "IO error: While lock file: svl-stats.db/LOCK: Resource temporarily unavailable"
+                    .into()
+            ),
+            DBError::Locked { .. }
+        ));
+        assert!(matches!(
+            DBError::from_open_message(path, "Corruption: bad block contents".into()),
+            DBError::Corrupt { .. }
+        ));
+        assert!(matches!(
+            DBError::from_open_message(path, "IO error: Permission denied".into()),
+            DBError::CannotOpen { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn verify_schema_reports_missing_relations() {
+        let db = DBConnection::new_in_memory().unwrap();
+        db.run_mutable(AUTHOR_SCHEMA, DBParams::new())
+            .await
+            .unwrap();
+        match db.verify_schema().await {
+            Err(DBError::MissingSchema { missing }) => assert_eq!(missing, vec!["Word", "Text"]),
+            other => panic!("expected missing schema, got {:?}", other),
+        }
+
+        // relations added to the schema later aren't required
+        db.run_mutable(WORD_SCHEMA, DBParams::new()).await.unwrap();
+        db.run_mutable(TEXT_SCHEMA, DBParams::new()).await.unwrap();
+        assert_eq!(db.verify_schema().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn create_schema_is_idempotent() {
+        let db = DBConnection::new_in_memory().unwrap();
+        assert_eq!(
+            db.create_schema().await.unwrap(),
+            vec!["Author", "Word", "Text", "Lemma", "BiGram", "IndexCache"]
+        );
+        db.run_mutable(
+            "?[word, text_id, count] <- [['amo', 1, 3]]; :put Word { word, text_id => count }",
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        db.run_mutable("::remove IndexCache", DBParams::new())
+            .await
+            .unwrap();
+        assert_eq!(db.create_schema().await.unwrap(), vec!["IndexCache"]);
+        assert!(db.create_schema().await.unwrap().is_empty());
+        assert_eq!(db.count("Word").await, Ok(1));
+
+        assert_eq!(db.recreate_schema().await.unwrap().len(), SCHEMA.len());
+        assert_eq!(db.count("Word").await, Ok(0));
+    }
+
+    #[test]
+    fn optional_data_values() {
+        assert_eq!(val(Some(3usize)), val(3usize));
+        assert_eq!(val(None::<usize>), DataValue::Null);
+        assert_eq!(val(Some("amo")), val("amo"));
+    }
+
+    #[test]
+    fn format_data_values_for_humans() {
+        assert_eq!(format_data_value(&DataValue::Null), "");
+        assert_eq!(format_data_value(&val("amo")), "amo");
+        assert_eq!(format_data_value(&val(42i64)), "42");
+        assert_eq!(format_data_value(&val(2.0 / 3.0)), "0.6667");
+        assert_eq!(format_data_value_with_precision(&val(2.0 / 3.0), 1), "0.7");
+        assert_eq!(format_data_value(&val(42.0)), "42");
+        assert_eq!(format_data_value(&val(-3.0)), "-3");
+        assert_eq!(
+            format_data_value(&val(vec![val("amo"), val(0.5), DataValue::Null])),
+            "[amo, 0.5000, ]"
+        );
+        assert_eq!(
+            format_data_value(&val(BTreeSet::from([val(2i64), val(1i64)]))),
+            "[1, 2]"
+        );
+    }
+
+    #[test]
+    fn convert_data_values_to_json() {
+        use serde_json::json;
+
+        assert_eq!(data_value_to_json(&DataValue::Null), json!(null));
+        assert_eq!(data_value_to_json(&val(true)), json!(true));
+        assert_eq!(data_value_to_json(&val(42i64)), json!(42));
+        assert_eq!(data_value_to_json(&val(1.5)), json!(1.5));
+        assert_eq!(data_value_to_json(&val(f64::NAN)), json!(null));
+        assert_eq!(data_value_to_json(&val("amo")), json!("amo"));
+        assert_eq!(
+            data_value_to_json(&val(vec![val("amo"), val(1i64), DataValue::Null])),
+            json!(["amo", 1, null])
+        );
+        assert_eq!(
+            data_value_to_json(&val(BTreeSet::from([val(2i64), val(1i64)]))),
+            json!([1, 2])
+        );
+        assert_eq!(
+            data_value_to_json(&val(JsonData(json!({"word": "amo"})))),
+            json!({"word": "amo"})
+        );
+
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (&b"h"[..], "aA=="),
+            (&b"he"[..], "aGU="),
+            (&b"hello"[..], "aGVsbG8="),
+            (&b"hello!"[..], "aGVsbG8h"),
+        ] {
+            assert_eq!(
+                data_value_to_json(&val(bytes.to_vec())),
+                json!(encoded),
+                "{:?}",
+                bytes
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn export_and_import_relations_roundtrip() {
+        let db = empty_db().await;
+        db.run_mutable(
+            r#"
+            ?[word, text_id, count] <- [['amo', 1, 3], ['amīcus', 2, 1]];
+            :put Word { word, text_id => count }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+        db.run_mutable(
+            "?[author_id, name, url] <- [[0, 'Caesar', 'caesar.html']]; :put Author { author_id, name => url }",
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let exported = db.export_relations(&["Author", "Word"]).await.unwrap();
+        // the export survives being written to and read from a file
+        let exported: serde_json::Value = serde_json::from_str(&exported.to_string()).unwrap();
+
+        let copy = empty_db().await;
+        copy.import_relations(&exported).await.unwrap();
+
+        for query in [
+            "?[word, text_id, count] := *Word{word, text_id, count}",
+            "?[author_id, name, url] := *Author{author_id, name, url}",
+        ] {
+            assert_eq!(
+                copy.run_immutable(query, DBParams::new())
+                    .await
+                    .unwrap()
+                    .rows,
+                db.run_immutable(query, DBParams::new()).await.unwrap().rows
+            );
+        }
+
+        assert!(matches!(
+            copy.import_relations(&serde_json::json!({ "Word": { "rows": [] } }))
+                .await,
+            Err(DBError::InvalidExport(_))
+        ));
+    }
+
+    const PUT_LEMMA: &str = "?[lemma] <- [['amicus']]; :put Lemma { lemma }";
+
+    #[tokio::test]
+    async fn uncommitted_transactions_are_rolled_back() {
+        let db = DBConnection::new_in_memory().unwrap();
+        db.run_mutable(":create Lemma { lemma: String }", DBParams::new())
+            .await
+            .unwrap();
+
+        {
+            let tx = db.multi_tx(true);
+            tx.run_script(PUT_LEMMA, DBParams::new()).unwrap();
+        }
+        assert_eq!(db.count("Lemma").await, Ok(0));
+
+        let tx = db.multi_tx(true);
+        tx.run_script(PUT_LEMMA, DBParams::new()).unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(db.count("Lemma").await, Ok(0));
+
+        let tx = db.multi_tx(true);
+        tx.run_script(PUT_LEMMA, DBParams::new()).unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(db.count("Lemma").await, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn list_relations_with_their_columns() {
+        let db = DBConnection::new_in_memory().unwrap();
+        db.run_mutable(WORD_SCHEMA, DBParams::new()).await.unwrap();
+
+        let relations = db.relations().await.unwrap();
+        assert_eq!(relations.len(), 1);
+        let word = &relations[0];
+        assert_eq!(word.name, "Word");
+
+        let names = |columns: Vec<&ColumnInfo>| -> Vec<String> {
+            columns.iter().map(|c| c.name.clone()).collect()
+        };
+        assert_eq!(names(word.keys().collect()), vec!["word", "text_id"]);
+        assert_eq!(names(word.values().collect()), vec!["count"]);
+        assert_eq!(word.columns[0].column_type, "String");
+    }
+
+    #[tokio::test]
+    async fn run_immutable_in_pages() {
+        let db = DBConnection::new_in_memory().unwrap();
+        let script = "?[x] := x in [1, 2, 3, 4, 5]";
+
+        let mut pages = db.run_immutable_paged(script, DBParams::new(), 2);
+        let mut sizes = Vec::new();
+        let mut values = Vec::new();
+        while let Some(page) = pages.next_page().await {
+            let page = page.unwrap();
+            sizes.push(page.rows.len());
+            values.extend(page.rows.into_iter().map(|row| row[0].clone()));
+        }
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert_eq!(values, (1..=5i64).map(val).collect::<Vec<_>>());
+
+        let mut pages = db.run_immutable_paged("?[x] := x in []", DBParams::new(), 2);
+        let first = pages.next_page().await.unwrap().unwrap();
+        assert_eq!(first.headers, vec!["x".to_string()]);
+        assert!(pages.next_page().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn close_keeps_the_store_open_for_other_clones() {
+        let db = empty_db().await;
+        let clone = db.clone();
+
+        db.close().await.unwrap();
+        assert_eq!(clone.count("Author").await.unwrap(), 0);
+        clone.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn slow_scripts_time_out() {
+        let db = DBConnection::new_in_memory()
+            .unwrap()
+            .with_timeout(Duration::from_nanos(1));
+        assert!(matches!(
+            db.run_immutable("?[count(x)] := x in int_range(10000000)", DBParams::new())
+                .await,
+            Err(DBError::Timeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn run_scripts_stops_at_the_first_error() {
+        let db = DBConnection::new_in_memory().unwrap();
+        db.run_mutable(":create Lemma { lemma: String }", DBParams::new())
+            .await
+            .unwrap();
+
+        let tx = db.multi_tx(true);
+        let results = tx
+            .run_scripts([
+                (PUT_LEMMA, DBParams::new()),
+                ("?[count(lemma)] := *Lemma{lemma}", DBParams::new()),
+            ])
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].rows, vec![vec![val(1i64)]]);
+        tx.commit().await.unwrap();
+
+        let tx = db.multi_tx(true);
+        assert!(tx
+            .run_scripts([
+                ("?[x] := *Missing{x}", DBParams::new()),
+                (PUT_LEMMA, DBParams::new()),
+            ])
+            .is_err());
+        tx.rollback().await.unwrap();
+        assert_eq!(db.count("Lemma").await, Ok(1));
+    }
+}
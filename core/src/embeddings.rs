@@ -0,0 +1,126 @@
+//! Embedding vectors of texts, supplied externally (e.g. by a sentence embedding model),
+//! and semantic search over them with a cozo HNSW index.
+
+use crate::{
+    db::{val, DBConnection, DBError, DBParams, DataValue},
+    text::TextId,
+};
+
+pub const TEXT_EMBEDDING_RELATION: &str = "TextEmbedding";
+/// HNSW index over the embeddings, kept up to date by cozo as embeddings are stored.
+pub const TEXT_EMBEDDING_INDEX: &str = "TextEmbedding:semantic";
+
+/// `:create` script for the embeddings relation, the vectors have `dim` dimensions.
+pub fn text_embedding_schema(dim: usize) -> String {
+    format!(
+        ":create {} {{ text_id: Int => embedding: <F32; {}> }}",
+        TEXT_EMBEDDING_RELATION, dim
+    )
+}
+
+/// Creates the embeddings relation and its index, unless the relation exists already.
+/// Returns whether it was created.
+pub async fn create_embedding_schema(db: &DBConnection, dim: usize) -> Result<bool, DBError> {
+    let existing = db.relation_names().await?;
+    if existing.iter().any(|name| name == TEXT_EMBEDDING_RELATION) {
+        return Ok(false);
+    }
+
+    db.run_mutable(&text_embedding_schema(dim), DBParams::new())
+        .await?;
+    build_embedding_index(db, dim).await?;
+
+    Ok(true)
+}
+
+/// Creates the HNSW index used by `/nearest`, comparing embeddings by cosine distance.
+pub async fn build_embedding_index(db: &DBConnection, dim: usize) -> Result<(), DBError> {
+    let script = format!(
+        "::hnsw create {} {{
+            dim: {},
+            m: 32,
+            dtype: F32,
+            fields: [embedding],
+            distance: Cosine,
+            ef_construction: 50,
+        }}",
+        TEXT_EMBEDDING_INDEX, dim
+    );
+    db.run_mutable(&script, DBParams::new()).await?;
+    Ok(())
+}
+
+/// Stores (or replaces) the embedding of each text. All embeddings need the
+/// number of dimensions the relation was created with.
+pub async fn store_text_embeddings(
+    db: &DBConnection,
+    embeddings: &[(TextId, Vec<f32>)],
+) -> Result<(), DBError> {
+    let Some((_, first)) = embeddings.first() else {
+        return Ok(());
+    };
+    if let Some((text_id, embedding)) = embeddings.iter().find(|(_, e)| e.len() != first.len()) {
+        return Err(DBError::Other(format!(
+            "embedding of text {} has {} dimensions, expected {}",
+            text_id,
+            embedding.len(),
+            first.len()
+        )));
+    }
+
+    let rows = DataValue::List(
+        embeddings
+            .iter()
+            .map(|(text_id, embedding)| {
+                let values = embedding.iter().map(|&v| val(v as f64));
+                DataValue::List(vec![text_id.into(), DataValue::List(values.collect())])
+            })
+            .collect(),
+    );
+
+    let script = format!(
+        "embeddings[text_id, values] <- $embeddings
+        ?[text_id, embedding] := embeddings[text_id, values], embedding = vec(values)
+        :put {} {{ text_id => embedding }}",
+        TEXT_EMBEDDING_RELATION
+    );
+    db.run_mutable(
+        &script,
+        DBParams::from_iter(vec![("embeddings".into(), rows)]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn store_embeddings_of_texts() {
+        let db = DBConnection::new_in_memory().unwrap();
+        assert_eq!(create_embedding_schema(&db, 2).await, Ok(true));
+        assert_eq!(create_embedding_schema(&db, 2).await, Ok(false));
+
+        store_text_embeddings(
+            &db,
+            &[
+                (1usize.into(), vec![1.0, 0.0]),
+                (2usize.into(), vec![0.0, 1.0]),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(db.count(TEXT_EMBEDDING_RELATION).await, Ok(2));
+
+        assert!(matches!(
+            store_text_embeddings(
+                &db,
+                &[(1usize.into(), vec![1.0, 0.0]), (2usize.into(), vec![1.0])]
+            )
+            .await,
+            Err(DBError::Other(_))
+        ));
+    }
+}
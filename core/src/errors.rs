@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use thiserror::Error;
 use tokio::task::JoinError;
@@ -11,12 +11,30 @@ pub enum SVLError {
     #[error("reqwest error: {0}")]
     Reqwest(#[from] reqwest::Error),
 
+    #[error("HTTP status {status} for {url}")]
+    HttpStatus { url: String, status: u16 },
+
+    #[error("Request to {url} timed out after {}s", .timeout.as_secs())]
+    Timeout { url: String, timeout: Duration },
+
     #[error("task join error: {0}")]
     TaskJoin(#[from] JoinError),
 
     #[error("failed to acquire semaphore permit: {0}")]
     SemaphoreAcquire(#[from] tokio::sync::AcquireError),
 
+    #[error("DB error: {0}")]
+    DB(#[from] crate::db::DBError),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Store(#[from] crate::stats::StoreError),
+
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+
     #[error("Unknown IO error: {0}")]
     IOError(#[from] std::io::Error),
 
@@ -26,9 +44,27 @@ pub enum SVLError {
     #[error("Load rules failed: {0:?}")]
     LoadRulesFailed(std::io::Error),
 
+    #[error("No selector matched any {0} in the page markup")]
+    NoSelectorMatched(String),
+
+    #[error("Invalid concurrency: {0} (at least 1 concurrent request is required)")]
+    InvalidConcurrency(usize),
+
     #[error("Invalid state")]
     InvalidState,
 
     #[error("Unknown error: {0:?}")]
     Unknown(Option<String>),
 }
+
+impl SVLError {
+    /// Whether a failed request may succeed if retried: server errors, timeouts and connection errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SVLError::HttpStatus { status, .. } => *status >= 500,
+            SVLError::Timeout { .. } => true,
+            SVLError::Reqwest(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+}
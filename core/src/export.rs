@@ -0,0 +1,280 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    db::{DBConnection, DBError, DBParams, DataValue, NamedRows},
+    errors::SVLError,
+};
+
+/// How exported rows are split up into multiple files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardBy {
+    Letter,
+    Author,
+}
+
+impl FromStr for ShardBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "letter" => Ok(ShardBy::Letter),
+            "author" => Ok(ShardBy::Author),
+            _ => Err(format!(
+                "Unknown shard kind: {} (expected letter or author)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ShardBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShardBy::Letter => write!(f, "letter"),
+            ShardBy::Author => write!(f, "author"),
+        }
+    }
+}
+
+// all word counts along with the author of the text they appear in
+const WORD_ROWS_QUERY: &str = r#"
+    ?[word, text_id, author_id, count] :=
+        *Word{word, text_id, count},
+        *Text{text_id, author_id}
+    :sort word, text_id
+"#;
+
+pub async fn word_rows(db: &DBConnection) -> Result<NamedRows, DBError> {
+    db.run_immutable(WORD_ROWS_QUERY, DBParams::new()).await
+}
+
+/// Rows fetched per query by `export_word_rows`.
+pub const EXPORT_PAGE_SIZE: usize = 50_000;
+
+/// Writes all word counts into a single CSV file, fetching them page by page
+/// instead of loading them all at once. Returns the number of exported rows.
+pub async fn export_word_rows(db: &DBConnection, output: &Path) -> Result<usize, SVLError> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut pages = db.run_immutable_paged(WORD_ROWS_QUERY, DBParams::new(), EXPORT_PAGE_SIZE);
+    let mut count = 0;
+
+    while let Some(page) = pages.next_page().await {
+        let page = page?;
+        if count == 0 {
+            write_csv_header(&mut writer, &page.headers)?;
+        }
+        write_csv_rows(&mut writer, &page.rows)?;
+        count += page.rows.len();
+    }
+
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Writes all rows into a single CSV file.
+pub fn export_rows(rows: &NamedRows, output: &Path) -> Result<(), SVLError> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    write_csv(&mut writer, &rows.headers, &rows.rows)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the rows into one CSV file per shard within `output_dir`.
+/// Every file starts with the same header. Returns the paths of all written files.
+pub fn export_rows_sharded(
+    rows: &NamedRows,
+    output_dir: &Path,
+    shard_by: ShardBy,
+) -> Result<Vec<PathBuf>, SVLError> {
+    let column = match shard_by {
+        ShardBy::Letter => "word",
+        ShardBy::Author => "author_id",
+    };
+    let position = rows
+        .headers
+        .iter()
+        .position(|h| h == column)
+        .ok_or(SVLError::Unknown(Some(format!(
+            "Missing column: {}",
+            column
+        ))))?;
+
+    let mut shards: BTreeMap<String, Vec<Vec<DataValue>>> = BTreeMap::new();
+    for row in &rows.rows {
+        let key = row
+            .get(position)
+            .map(|value| shard_key(value, shard_by))
+            .unwrap_or_else(|| "_".into());
+        shards.entry(key).or_default().push(row.clone());
+    }
+
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut paths = Vec::with_capacity(shards.len());
+    for (key, shard_rows) in shards {
+        let path = output_dir.join(format!("words-{}-{}.csv", shard_by, key));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        write_csv(&mut writer, &rows.headers, &shard_rows)?;
+        writer.flush()?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+fn shard_key(value: &DataValue, shard_by: ShardBy) -> String {
+    match shard_by {
+        ShardBy::Letter => value
+            .get_str()
+            .and_then(|word| word.chars().next())
+            .filter(|c| c.is_alphanumeric())
+            .map(|c| c.to_lowercase().to_string())
+            .unwrap_or_else(|| "_".into()),
+        ShardBy::Author => value
+            .get_int()
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| "_".into()),
+    }
+}
+
+/// Writes a header line and all rows as CSV, quoting fields where needed.
+pub fn write_csv<W: Write>(
+    writer: &mut W,
+    headers: &[String],
+    rows: &[Vec<DataValue>],
+) -> std::io::Result<()> {
+    write_csv_header(writer, headers)?;
+    write_csv_rows(writer, rows)
+}
+
+fn write_csv_header<W: Write>(writer: &mut W, headers: &[String]) -> std::io::Result<()> {
+    let header: Vec<String> = headers.iter().map(|h| csv_field(h)).collect();
+    writeln!(writer, "{}", header.join(","))
+}
+
+fn write_csv_rows<W: Write>(writer: &mut W, rows: &[Vec<DataValue>]) -> std::io::Result<()> {
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|v| csv_field(&csv_value(v))).collect();
+        writeln!(writer, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+fn csv_value(value: &DataValue) -> String {
+    match value {
+        DataValue::Str(s) => s.to_string(),
+        DataValue::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::seeded_db;
+    use std::collections::BTreeSet;
+
+    async fn texts_and_words_db() -> DBConnection {
+        let db = seeded_db(
+            r#"
+            ?[text_id, author_id, url, text] <- [[1, 0, 'a', 'amo te'], [2, 1, 'b', 'ave te']];
+            :put Text { text_id, author_id => url, text }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[word, text_id, count] <- [['amo', 1, 1], ['te', 1, 1], ['ave', 2, 1], ['te', 2, 1]];
+            :put Word { word, text_id => count }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    fn read_shards(paths: &[PathBuf]) -> (BTreeSet<String>, Vec<String>) {
+        let mut headers = BTreeSet::new();
+        let mut rows = Vec::new();
+        for path in paths {
+            let content = std::fs::read_to_string(path).unwrap();
+            let mut lines = content.lines();
+            headers.insert(lines.next().unwrap().to_string());
+            rows.extend(lines.map(|l| l.to_string()));
+        }
+        (headers, rows)
+    }
+
+    #[tokio::test]
+    async fn test_export_rows_sharded() {
+        let db = texts_and_words_db().await;
+        let rows = word_rows(&db).await.unwrap();
+        let dir = std::env::temp_dir().join(format!("svl-export-test-{}", std::process::id()));
+
+        for (shard_by, expected_files) in [(ShardBy::Letter, 2), (ShardBy::Author, 2)] {
+            let output_dir = dir.join(shard_by.to_string());
+            let paths = export_rows_sharded(&rows, &output_dir, shard_by).unwrap();
+            assert_eq!(paths.len(), expected_files);
+
+            let (headers, mut exported) = read_shards(&paths);
+            assert_eq!(
+                headers,
+                BTreeSet::from(["word,text_id,author_id,count".to_string()])
+            );
+
+            // every row is exported exactly once across all shards
+            exported.sort();
+            assert_eq!(
+                exported,
+                vec!["amo,1,0,1", "ave,2,1,1", "te,1,0,1", "te,2,1,1"]
+            );
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_word_rows() {
+        let db = texts_and_words_db().await;
+        let path =
+            std::env::temp_dir().join(format!("svl-export-words-{}.csv", std::process::id()));
+
+        assert_eq!(export_word_rows(&db, &path).await.unwrap(), 4);
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            content.lines().collect::<Vec<_>>(),
+            vec![
+                "word,text_id,author_id,count",
+                "amo,1,0,1",
+                "ave,2,1,1",
+                "te,1,0,1",
+                "te,2,1,1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("amo"), "amo");
+        assert_eq!(csv_field("amo, amas"), "\"amo, amas\"");
+        assert_eq!(csv_field("dixit \"ave\""), "\"dixit \"\"ave\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+}
@@ -0,0 +1,498 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    time::Duration,
+};
+
+use futures::{stream, StreamExt};
+
+use crate::{
+    client::{HttpStatsClient, IndexCache},
+    db::{val, DBConnection, DBParams},
+    errors::SVLError,
+    stats::{Stats, TextFilter},
+    text::{Author, EndingLemmatizer, LatinTokenizer, TokenMode},
+};
+
+const LOG_TARGET: &str = "svl::import";
+
+/// Progress events emitted while importing the library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportProgress {
+    FetchingAuthors,
+    AuthorFetched {
+        name: String,
+        texts: usize,
+    },
+    FetchingText {
+        url: String,
+    },
+    TextFetched {
+        url: String,
+    },
+    TextSkipped {
+        url: String,
+        reason: String,
+    },
+    /// Resuming an earlier import, skipping this many already imported texts.
+    Resuming {
+        skipped: usize,
+    },
+    Stored {
+        texts: usize,
+        words: usize,
+    },
+    Warning(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Commit stats in batches of this many rows instead of a single transaction.
+    pub batch_size: Option<usize>,
+    /// Names of authors whose texts are skipped (case-insensitive).
+    pub exclude_authors: Vec<String>,
+    /// Regex patterns for text URLs that are skipped.
+    pub exclude_urls: Vec<String>,
+    pub token_mode: TokenMode,
+    /// Count enclitics (`-que`, `-ve`, `-ne`) as words of their own.
+    pub split_enclitics: bool,
+    /// Drop words shorter than this many characters.
+    pub min_word_len: usize,
+    /// Count `v` and `j` as `u` and `i`, see `Word::normalize_latin`.
+    pub fold_orthography: bool,
+    /// How long cached author and text lists are reused.
+    pub index_ttl: Duration,
+    /// Skip texts that are already stored in the DB, e.g. after an import failed partway.
+    pub resume: bool,
+    /// Also store the lemma of every word, see `EndingLemmatizer`.
+    pub lemmatize: bool,
+    /// Also store counts of pairs of consecutive words.
+    pub bigrams: bool,
+    /// Lowercase words that are skipped instead of stored, see `text::load_stopwords`.
+    pub stopwords: BTreeSet<String>,
+    /// Fetch up to this many texts at the same time (at least 1).
+    pub concurrency: usize,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: None,
+            exclude_authors: Vec::new(),
+            exclude_urls: Vec::new(),
+            token_mode: TokenMode::default(),
+            split_enclitics: false,
+            min_word_len: LatinTokenizer::DEFAULT_MIN_WORD_LEN,
+            fold_orthography: false,
+            index_ttl: IndexCache::DEFAULT_TTL,
+            resume: false,
+            lemmatize: false,
+            bigrams: false,
+            stopwords: BTreeSet::new(),
+            concurrency: HttpStatsClient::DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Builds the filter for the excluded authors and URLs, warning about
+    /// excluded author names that don't match any of the given authors.
+    pub fn text_filter<P>(&self, authors: &[Author], progress: &P) -> crate::Result<TextFilter>
+    where
+        P: Fn(ImportProgress),
+    {
+        let mut filter = TextFilter::new();
+
+        for name in &self.exclude_authors {
+            let matching: Vec<&Author> = authors
+                .iter()
+                .filter(|a| a.name.eq_ignore_ascii_case(name))
+                .collect();
+            if matching.is_empty() {
+                progress(ImportProgress::Warning(format!(
+                    "no author named {} to exclude",
+                    name
+                )));
+            }
+            for author in matching {
+                filter.exclude_author(author.author_id);
+            }
+        }
+
+        for pattern in &self.exclude_urls {
+            filter.exclude_url_pattern(pattern)?;
+        }
+
+        Ok(filter)
+    }
+}
+
+/// Fetches all authors and their texts from the library, stores them in the
+/// DB and returns the calculated stats, reporting progress to `progress`.
+pub async fn import_library<P>(
+    db: &DBConnection,
+    client: &HttpStatsClient,
+    options: &ImportOptions,
+    progress: P,
+) -> crate::Result<Stats>
+where
+    P: Fn(ImportProgress),
+{
+    progress(ImportProgress::FetchingAuthors);
+
+    let cache = IndexCache::open(db, options.index_ttl).await?;
+    let mut authors = cache.authors(client).await?;
+
+    // author ids are assigned by position in the library index
+    let author_records: Vec<Author> = authors
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(Author::from)
+        .collect();
+
+    let filter = options.text_filter(&author_records, &progress)?;
+    let mut stats = Stats::with_filter(filter.clone());
+    stats.set_tokenizer(LatinTokenizer {
+        mode: options.token_mode,
+        split_enclitics: options.split_enclitics,
+        min_word_len: options.min_word_len,
+        fold_orthography: options.fold_orthography,
+    });
+    if options.lemmatize || options.bigrams {
+        // DBs created before lemmas and bigrams were stored lack their relations
+        db.create_schema().await?;
+    }
+    if options.lemmatize {
+        stats.set_lemmatizer(EndingLemmatizer);
+    }
+    stats.set_track_bigrams(options.bigrams);
+    stats.set_stopwords(options.stopwords.clone());
+
+    let (imported_urls, max_text_id) = if options.resume {
+        imported_texts(db).await?
+    } else {
+        (HashSet::new(), 0)
+    };
+    // new texts must not overwrite the ones of an earlier import
+    stats.set_text_id_offset(max_text_id);
+
+    let tx = db.multi_tx(true);
+
+    for author in &author_records {
+        tx.run_script(
+            "
+            ?[author_id, name, url] <- [$props];
+            :put Author { author_id, name => url }
+            ",
+            DBParams::from_iter(vec![("props".into(), val(author))]),
+        )?;
+    }
+
+    tx.commit().await?;
+
+    for author_info in authors.iter_mut() {
+        let texts = match cache.texts(client, author_info).await {
+            Ok(texts) => texts,
+            Err(SVLError::NoSelectorMatched(_)) => {
+                progress(ImportProgress::Warning(format!(
+                    "no texts found for {}",
+                    author_info.name
+                )));
+                Vec::new()
+            }
+            Err(err) => return Err(err),
+        };
+        author_info.texts = texts;
+
+        progress(ImportProgress::AuthorFetched {
+            name: author_info.name.clone(),
+            texts: author_info.texts.len(),
+        });
+    }
+
+    let mut text_futures = Vec::with_capacity(authors.len());
+    let mut already_imported = 0;
+
+    for (author_info, author) in authors.iter().zip(&author_records) {
+        for text_info in &author_info.texts {
+            if imported_urls.contains(&text_info.url) {
+                already_imported += 1;
+                continue;
+            }
+            if filter.excludes(Some(author.author_id), &text_info.url) {
+                progress(ImportProgress::TextSkipped {
+                    url: text_info.url.clone(),
+                    reason: "excluded".into(),
+                });
+                continue;
+            }
+            progress(ImportProgress::FetchingText {
+                url: text_info.url.clone(),
+            });
+            text_futures.push((
+                author.author_id,
+                &text_info.url,
+                client.fetch_text_with_retry(&text_info.url),
+            ));
+        }
+    }
+
+    if options.resume {
+        log::info!(target: LOG_TARGET, "Skipping {} already imported texts", already_imported);
+        progress(ImportProgress::Resuming {
+            skipped: already_imported,
+        });
+    }
+
+    // fetched concurrently, but added in order so that text ids don't depend on timing
+    let mut fetched = stream::iter(
+        text_futures
+            .into_iter()
+            .map(|(author_id, url, tf)| async move { (author_id, url, tf.await) }),
+    )
+    .buffered(options.concurrency.max(1));
+
+    while let Some((author_id, url, text)) = fetched.next().await {
+        let mut text = match text {
+            Ok(text) => text,
+            Err(err @ SVLError::HttpStatus { .. }) if !err.is_retryable() => {
+                progress(ImportProgress::TextSkipped {
+                    url: url.clone(),
+                    reason: err.to_string(),
+                });
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        progress(ImportProgress::TextFetched { url: url.clone() });
+        text.author_id = Some(author_id);
+        stats.add_text(text);
+    }
+
+    let summary = stats.store_in_db_batched(db, options.batch_size).await?;
+    log::info!(target: LOG_TARGET, "Stored {} rows: {:?}", summary.rows_written(), summary);
+    progress(ImportProgress::Stored {
+        texts: stats.text_count(),
+        words: stats.unique_word_count(),
+    });
+
+    Ok(stats)
+}
+
+/// URLs of all texts stored in the DB and the highest text id in use.
+async fn imported_texts(db: &DBConnection) -> crate::Result<(HashSet<String>, usize)> {
+    let rows = db
+        .run_immutable("?[text_id, url] := *Text{text_id, url}", DBParams::new())
+        .await?;
+
+    let mut urls = HashSet::new();
+    let mut max_text_id = 0;
+    for row in &rows.rows {
+        if let Some(url) = row.get(1).and_then(|url| url.get_str()) {
+            urls.insert(url.to_string());
+        }
+        if let Some(text_id) = row.first().and_then(|id| id.get_int()) {
+            max_text_id = max_text_id.max(text_id.max(0) as usize);
+        }
+    }
+
+    Ok((urls, max_text_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::RetryPolicy;
+    use crate::db::empty_db;
+    use std::cell::RefCell;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    // serves a small library with two authors: Caesar with two texts and a missing
+    // one, and Cicero without any texts. Returns the base URL of the library.
+    async fn serve_library() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0; 4096];
+                let read = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+                let page = match path {
+                    "/" => Some(
+                        r#"<form name="myform"><select name="dest">
+                        <option value="caesar.html">Caesar</option>
+                        <option value="cicero.html">Cicero</option>
+                        </select></form>"#,
+                    ),
+                    "/caesar.html" => Some(
+                        r#"<div class="work">
+                        <a href="caesar/gall1.shtml">gall1</a>
+                        <a href="caesar/gall2.shtml">gall2</a>
+                        <a href="caesar/missing.shtml">missing</a>
+                        </div>"#,
+                    ),
+                    "/cicero.html" => Some("<p>Nothing here yet</p>"),
+                    "/caesar/gall1.shtml" | "/caesar/gall2.shtml" => {
+                        Some("<html><body>Gallia est omnis divisa</body></html>")
+                    }
+                    _ => None,
+                };
+                let response = match page {
+                    Some(page) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        page.len(),
+                        page
+                    ),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        base_url
+    }
+
+    fn library_client(base_url: &str) -> HttpStatsClient {
+        HttpStatsClient::with_base_url(base_url)
+            .unwrap()
+            .with_retry_policy(RetryPolicy::none())
+    }
+
+    #[tokio::test]
+    async fn import_library_stores_texts_and_reports_progress() {
+        let db = empty_db().await;
+        let options = ImportOptions {
+            exclude_urls: vec!["gall2".into()],
+            ..ImportOptions::default()
+        };
+        let base_url = serve_library().await;
+        let events = RefCell::new(Vec::new());
+
+        let stats = import_library(&db, &library_client(&base_url), &options, |event| {
+            events.borrow_mut().push(event)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(stats.text_count(), 1);
+        assert_eq!(db.count("Author").await, Ok(2));
+        assert_eq!(db.count("Text").await, Ok(1));
+        assert_eq!(db.count("Word").await, Ok(4));
+
+        let events = events.into_inner();
+        assert_eq!(events.first(), Some(&ImportProgress::FetchingAuthors));
+        assert!(events.contains(&ImportProgress::Warning("no texts found for Cicero".into())));
+        assert!(events.contains(&ImportProgress::TextFetched {
+            url: format!("{}caesar/gall1.shtml", base_url)
+        }));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| matches!(e, ImportProgress::TextSkipped { .. }))
+                .count(),
+            2
+        );
+        assert_eq!(
+            events.last(),
+            Some(&ImportProgress::Stored { texts: 1, words: 4 })
+        );
+    }
+
+    #[tokio::test]
+    async fn import_library_resumes_after_imported_texts() {
+        let db = empty_db().await;
+        let options = ImportOptions {
+            exclude_urls: vec!["gall2".into()],
+            ..ImportOptions::default()
+        };
+        let base_url = serve_library().await;
+        let client = library_client(&base_url);
+        import_library(&db, &client, &options, |_| {})
+            .await
+            .unwrap();
+
+        let options = ImportOptions {
+            resume: true,
+            ..ImportOptions::default()
+        };
+        let events = RefCell::new(Vec::new());
+        let stats = import_library(&db, &client, &options, |event| {
+            events.borrow_mut().push(event)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(stats.text_count(), 1);
+        assert!(events
+            .into_inner()
+            .contains(&ImportProgress::Resuming { skipped: 1 }));
+
+        let rows = db
+            .run_immutable("?[text_id, url] := *Text{text_id, url}", DBParams::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            rows.rows,
+            vec![
+                vec![val(1usize), val(format!("{}caesar/gall1.shtml", base_url))],
+                vec![val(2usize), val(format!("{}caesar/gall2.shtml", base_url))],
+            ]
+        );
+    }
+
+    fn author(author_id: usize, name: &str) -> Author {
+        Author {
+            author_id,
+            name: name.into(),
+            url: format!("https://thelatinlibrary.com/{}.html", name.to_lowercase()),
+        }
+    }
+
+    #[test]
+    fn text_filter_reports_unknown_authors() {
+        let options = ImportOptions {
+            exclude_authors: vec!["caesar".into(), "Vergil".into()],
+            exclude_urls: vec![r"/spurious/".into()],
+            ..ImportOptions::default()
+        };
+        let authors = vec![author(0, "Caesar"), author(1, "Cicero")];
+        let events = RefCell::new(Vec::new());
+
+        let filter = options
+            .text_filter(&authors, &|event| events.borrow_mut().push(event))
+            .unwrap();
+
+        assert!(filter.excludes(Some(0), "https://thelatinlibrary.com/caesar/gall1.shtml"));
+        assert!(!filter.excludes(Some(1), "https://thelatinlibrary.com/cicero/cat1.shtml"));
+        assert!(filter.excludes(Some(1), "https://thelatinlibrary.com/spurious/x.html"));
+        assert_eq!(
+            events.into_inner(),
+            vec![ImportProgress::Warning(
+                "no author named Vergil to exclude".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn text_filter_rejects_invalid_patterns() {
+        let options = ImportOptions {
+            exclude_urls: vec!["(".into()],
+            ..ImportOptions::default()
+        };
+
+        assert!(matches!(
+            options.text_filter(&[], &|_| {}),
+            Err(SVLError::InvalidPattern(_))
+        ));
+    }
+}
@@ -6,20 +6,30 @@
 //!
 //! - `client`: Contains functionality for making HTTP requests and interacting with external APIs.
 //! - `db`: Provides abstractions and utilities for managing database connections and executing queries.
+//! - `embeddings`: Stores externally computed text embeddings for semantic search.
+//! - `export`: Writes query results to (optionally sharded) CSV files.
+//! - `import`: Imports the library into the database, reporting progress along the way.
 //! - `queries`: Defines pre-defined queries and functions for executing database queries.
 //! - `stats`: Handles statistical computations and manages data related to application statistics.
 //! - `text`: Contains data structures and operations for handling text and word processing tasks.
 
 pub mod client;
 pub mod db;
+pub mod embeddings;
 pub mod errors;
+pub mod export;
+pub mod import;
 pub mod queries;
 pub mod stats;
 pub mod text;
 
+use db::DBError;
 use errors::SVLError;
+use regex::{Captures, Regex};
 use std::path::PathBuf;
 
+const LOG_TARGET: &str = "svl::rules";
+
 pub type Result<T> = std::result::Result<T, SVLError>;
 
 pub enum LoadRulesFrom {
@@ -52,14 +62,89 @@ pub fn load_rules(lrf: LoadRulesFrom) -> Result<String> {
     let file_path = lrf.path()?;
 
     if !file_path.exists() {
-        log::warn!("rules.datalog not found in: {:?}", file_path);
+        log::warn!(target: LOG_TARGET, "Rules file not found: {:?}", file_path);
         return Err(SVLError::RulesFileNotFound(file_path));
     }
-    log::info!("Loading rules from: {:?}", file_path);
+    log::info!(target: LOG_TARGET, "Loading rules from: {:?}", file_path);
     let rules = std::fs::read_to_string(file_path).map_err(SVLError::LoadRulesFailed)?;
     Ok(rules)
 }
 
+/// A user script with the loaded rules prepended to it.
+/// Keeps track of how many bytes the rules take up, so that source spans in
+/// errors can be mapped back to the user's input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RulesScript {
+    pub script: String,
+    rules_len: usize,
+    shadowed_rules: Vec<String>,
+}
+
+impl RulesScript {
+    pub fn new(rules: &str, code: &str) -> Self {
+        if rules.trim().is_empty() {
+            return Self {
+                script: code.to_string(),
+                rules_len: 0,
+                shadowed_rules: Vec::new(),
+            };
+        }
+
+        let rule_names = Self::rule_names(rules);
+        let shadowed_rules = Self::rule_names(code)
+            .into_iter()
+            .filter(|name| rule_names.contains(name))
+            .collect();
+
+        Self {
+            script: format!("{}\n{}", rules, code),
+            // the rules and the newline separating them from the code
+            rules_len: rules.len() + 1,
+            shadowed_rules,
+        }
+    }
+
+    /// Names of rules defined both in the loaded rules and the user's code.
+    pub fn shadowed_rules(&self) -> &[String] {
+        &self.shadowed_rules
+    }
+
+    /// Rewrites source spans (byte offsets like `at 103..107`, as reported by Cozo)
+    /// in an error message so they refer to the user's code instead of the combined script.
+    pub fn adjust_error(&self, message: &str) -> String {
+        if self.rules_len == 0 {
+            return message.to_string();
+        }
+
+        let span = Regex::new(r"\b(\d+)\.\.(\d+)\b").expect("Regex should be valid");
+        span.replace_all(message, |caps: &Captures| {
+            let start = caps[1].parse::<usize>().unwrap_or(0);
+            let end = caps[2].parse::<usize>().unwrap_or(0);
+            if start >= self.rules_len {
+                format!("{}..{}", start - self.rules_len, end - self.rules_len)
+            } else {
+                format!("{}..{} (in rules)", start, end)
+            }
+        })
+        .into_owned()
+    }
+
+    pub fn adjust_db_error(&self, error: DBError) -> DBError {
+        match error {
+            DBError::Cozo(message) => DBError::Cozo(self.adjust_error(&message)),
+            error => error,
+        }
+    }
+
+    fn rule_names(code: &str) -> Vec<String> {
+        let rule_head = Regex::new(r"(?m)^\s*([A-Za-z_]\w*)\s*\[").expect("Regex should be valid");
+        rule_head
+            .captures_iter(code)
+            .map(|caps| caps[1].to_string())
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,6 +154,42 @@ mod tests {
         let mut root_path = std::env::current_dir().unwrap();
         root_path.pop();
         let rules = load_rules(LoadRulesFrom::DefaultInDir(root_path)).unwrap();
-        assert!(rules.len() > 0);
+        assert!(!rules.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rules_script_adjusts_error_spans() {
+        let rules = "rule_a[x] := x = 1\n\nrule_b[x] := x = 2";
+        let code = "?[x] := rule_a[x], x =";
+        let script = RulesScript::new(rules, code);
+        assert_eq!(script.script, format!("{}\n{}", rules, code));
+
+        let db = db::DBConnection::new_in_memory().unwrap();
+        let error = db
+            .run_mutable(&script.script, db::DBParams::new())
+            .await
+            .unwrap_err();
+        let end = format!("{}..{}", code.len(), code.len());
+        assert!(
+            script.adjust_db_error(error).to_string().contains(&end),
+            "error should point at the end of the code"
+        );
+
+        assert_eq!(script.adjust_error("at 3..5"), "at 3..5 (in rules)");
+        assert_eq!(
+            script.adjust_error("expected 3 columns"),
+            "expected 3 columns"
+        );
+    }
+
+    #[test]
+    fn test_rules_script_detects_shadowed_rules() {
+        let rules = "rule_a[x] := x = 1\nrule_b[x] := x = 2";
+        let script = RulesScript::new(rules, "rule_b[x] := x = 3\n?[x] := rule_b[x]");
+        assert_eq!(script.shadowed_rules(), &["rule_b".to_string()]);
+
+        let script = RulesScript::new("", "?[x] := x = 1");
+        assert_eq!(script.script, "?[x] := x = 1");
+        assert_eq!(script.adjust_error("at 1..1"), "at 1..1");
     }
 }
@@ -1,11 +1,17 @@
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    str::FromStr,
+};
 
 use crate::{
-    db::{DBConnection, DBError, DBParams, DataValue, NamedRows, ToDataValue},
-    text::TextId,
+    db::{val, ColumnInfo, DBConnection, DBError, DBParams, DataValue, NamedRows, ToDataValue},
+    embeddings,
+    text::{EndingLemmatizer, LatinTokenizer, Lemmatizer, Text, TextId, Tokenizer, Word},
 };
 use thiserror::Error;
 
+const LOG_TARGET: &str = "svl::queries";
+
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum QueryError {
     #[error("DB Error: {0}")]
@@ -14,6 +20,12 @@ pub enum QueryError {
     #[error("Unknown Query: {0}")]
     UnknownQuery(String),
 
+    #[error("Unknown command: {command}{}", did_you_mean(.suggestion))]
+    UnknownCommand {
+        command: String,
+        suggestion: Option<String>,
+    },
+
     #[error("Missing args for {0}: {1} expected but only {2} provided")]
     MissingArgs(QueryCommand, usize, usize),
 
@@ -25,20 +37,51 @@ pub enum QueryError {
 
     #[error("Unmatched quotes")]
     UnmatchedQuotes,
+
+    #[error("Invalid text id range: {0} (expected <from>-<to>)")]
+    InvalidRange(String),
+
+    #[error("Invalid histogram buckets: {0} (expected increasing counts, starting at 1 or more)")]
+    InvalidBuckets(String),
+
+    #[error("Invalid argument {arg}: {reason}")]
+    InvalidArgument { arg: String, reason: String },
+
+    #[error("No text embeddings imported (see `svl import-embeddings`)")]
+    NoEmbeddings,
+
+    /// Not an actual failure: the user asked to quit, which is up to the caller to handle.
+    #[error("Quit requested")]
+    Quit,
 }
 
 pub type QueryResult = Result<NamedRows, QueryError>;
 
+fn did_you_mean(suggestion: &Option<String>) -> String {
+    suggestion
+        .as_ref()
+        .map(|s| format!(" (did you mean {}?)", s))
+        .unwrap_or_default()
+}
+
 impl From<DBError> for QueryError {
     fn from(error: DBError) -> Self {
         Self::DBError(error)
     }
 }
 
+// descriptions of the optional limit and offset arguments of queries, for InvalidArgument errors
+const LIMIT: &str = "a number as limit";
+const OFFSET: &str = "a number as offset";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Query {
     pub cmd: QueryCommand,
     pub args: Args,
+    /// Match words exactly instead of lowercasing both the words and the search term.
+    pub case_sensitive: bool,
+    /// Seed for `/sample` if none is given as argument, e.g. from `--seed`.
+    pub seed: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -51,18 +94,106 @@ pub enum QueryCommand {
     EndsTexts,
     Contains,
     ContainsTexts,
+    Count,
     CountTexts,
     CountAuthors,
     CountWords,
+    Summary,
+    Schema,
+    Distinct,
+    Histogram,
+    Lengths,
+    Hapax,
+    Lemma,
+    Folded,
+    Collocations,
+    Concordance,
+    Distinctive,
+    Similar,
+    Nearest,
     Word,
     Text,
+    Authors,
     Author,
+    AuthorStats,
+    Trending,
+    Sample,
     Quit,
     Exit,
     Clear,
     Unknown(String),
 }
 
+impl QueryCommand {
+    /// All known commands, in the order they are listed in the help.
+    pub fn all() -> Vec<QueryCommand> {
+        vec![
+            QueryCommand::Help,
+            QueryCommand::Top,
+            QueryCommand::TopEnds,
+            QueryCommand::Texts,
+            QueryCommand::Ends,
+            QueryCommand::EndsTexts,
+            QueryCommand::Contains,
+            QueryCommand::ContainsTexts,
+            QueryCommand::Count,
+            QueryCommand::CountTexts,
+            QueryCommand::CountAuthors,
+            QueryCommand::CountWords,
+            QueryCommand::Summary,
+            QueryCommand::Schema,
+            QueryCommand::Distinct,
+            QueryCommand::Histogram,
+            QueryCommand::Lengths,
+            QueryCommand::Hapax,
+            QueryCommand::Lemma,
+            QueryCommand::Folded,
+            QueryCommand::Collocations,
+            QueryCommand::Concordance,
+            QueryCommand::Distinctive,
+            QueryCommand::Similar,
+            QueryCommand::Nearest,
+            QueryCommand::Word,
+            QueryCommand::Text,
+            QueryCommand::Authors,
+            QueryCommand::Author,
+            QueryCommand::AuthorStats,
+            QueryCommand::Trending,
+            QueryCommand::Sample,
+            QueryCommand::Quit,
+            QueryCommand::Exit,
+            QueryCommand::Clear,
+        ]
+    }
+
+    /// The known command closest to `cmd`, if any is close enough to be a likely typo.
+    pub fn suggest(cmd: &str) -> Option<QueryCommand> {
+        const MAX_DISTANCE: usize = 2;
+
+        Self::all()
+            .into_iter()
+            .map(|c| (levenshtein(cmd, &c.to_string()), c))
+            .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, c)| c)
+    }
+}
+
+/// Strict parsing that fails on unknown commands, unlike the lenient `From<&str>`.
+impl FromStr for QueryCommand {
+    type Err = QueryError;
+
+    fn from_str(cmd: &str) -> Result<Self, Self::Err> {
+        match QueryCommand::from(cmd) {
+            QueryCommand::Unknown(command) => Err(QueryError::UnknownCommand {
+                suggestion: QueryCommand::suggest(&command).map(|c| c.to_string()),
+                command,
+            }),
+            known => Ok(known),
+        }
+    }
+}
+
 impl From<&str> for QueryCommand {
     fn from(cmd: &str) -> Self {
         match cmd {
@@ -74,12 +205,30 @@ impl From<&str> for QueryCommand {
             "ends-texts" => QueryCommand::EndsTexts,
             "contains" => QueryCommand::Contains,
             "contains-texts" => QueryCommand::ContainsTexts,
+            "count" => QueryCommand::Count,
             "count-texts" => QueryCommand::CountTexts,
             "count-authors" => QueryCommand::CountAuthors,
             "count-words" => QueryCommand::CountWords,
+            "summary" => QueryCommand::Summary,
+            "schema" => QueryCommand::Schema,
+            "distinct" => QueryCommand::Distinct,
+            "histogram" => QueryCommand::Histogram,
+            "lengths" => QueryCommand::Lengths,
+            "hapax" => QueryCommand::Hapax,
+            "lemma" => QueryCommand::Lemma,
+            "folded" => QueryCommand::Folded,
+            "collocations" => QueryCommand::Collocations,
+            "concordance" => QueryCommand::Concordance,
+            "distinctive" => QueryCommand::Distinctive,
+            "similar" => QueryCommand::Similar,
+            "nearest" => QueryCommand::Nearest,
             "word" => QueryCommand::Word,
             "text" => QueryCommand::Text,
+            "authors" => QueryCommand::Authors,
             "author" => QueryCommand::Author,
+            "author-stats" => QueryCommand::AuthorStats,
+            "trending" => QueryCommand::Trending,
+            "sample" => QueryCommand::Sample,
             "quit" => QueryCommand::Quit,
             "exit" => QueryCommand::Exit,
             "clear" => QueryCommand::Clear,
@@ -99,12 +248,30 @@ impl std::fmt::Display for QueryCommand {
             QueryCommand::EndsTexts => write!(f, "ends-texts"),
             QueryCommand::Contains => write!(f, "contains"),
             QueryCommand::ContainsTexts => write!(f, "contains-texts"),
+            QueryCommand::Count => write!(f, "count"),
             QueryCommand::CountTexts => write!(f, "count-texts"),
             QueryCommand::CountAuthors => write!(f, "count-authors"),
             QueryCommand::CountWords => write!(f, "count-words"),
+            QueryCommand::Summary => write!(f, "summary"),
+            QueryCommand::Schema => write!(f, "schema"),
+            QueryCommand::Distinct => write!(f, "distinct"),
+            QueryCommand::Histogram => write!(f, "histogram"),
+            QueryCommand::Lengths => write!(f, "lengths"),
+            QueryCommand::Hapax => write!(f, "hapax"),
+            QueryCommand::Lemma => write!(f, "lemma"),
+            QueryCommand::Folded => write!(f, "folded"),
+            QueryCommand::Collocations => write!(f, "collocations"),
+            QueryCommand::Concordance => write!(f, "concordance"),
+            QueryCommand::Distinctive => write!(f, "distinctive"),
+            QueryCommand::Similar => write!(f, "similar"),
+            QueryCommand::Nearest => write!(f, "nearest"),
             QueryCommand::Word => write!(f, "word"),
             QueryCommand::Text => write!(f, "text"),
+            QueryCommand::Authors => write!(f, "authors"),
             QueryCommand::Author => write!(f, "author"),
+            QueryCommand::AuthorStats => write!(f, "author-stats"),
+            QueryCommand::Trending => write!(f, "trending"),
+            QueryCommand::Sample => write!(f, "sample"),
             QueryCommand::Quit => write!(f, "quit"),
             QueryCommand::Exit => write!(f, "exit"),
             QueryCommand::Clear => write!(f, "clear"),
@@ -118,22 +285,35 @@ impl Query {
         Self {
             cmd: QueryCommand::from(cmd.as_str()),
             args: Args { args },
+            case_sensitive: false,
+            seed: None,
+        }
+    }
+
+    pub fn with_case_sensitive(self, case_sensitive: bool) -> Self {
+        Self {
+            case_sensitive,
+            ..self
         }
     }
 
+    pub fn with_seed(self, seed: Option<u64>) -> Self {
+        Self { seed, ..self }
+    }
+
     pub fn parse(query: &str) -> Result<Self, QueryError> {
         let query = query.trim();
         if query.is_empty() {
             return Err(QueryError::EmptyQuery);
         }
 
-        let chars = query.chars().peekable();
+        let mut chars = query.chars().peekable();
         let mut cmd = String::new();
         let mut args = Args::new();
         let mut current_arg = String::new();
         let mut in_quotes = false;
 
-        for c in chars {
+        while let Some(c) = chars.next() {
             match c {
                 ' ' | '\t' if !in_quotes => {
                     if !current_arg.is_empty() {
@@ -155,6 +335,10 @@ impl Query {
                         current_arg = String::new();
                     }
                 }
+                // within quotes, `\"` and `\\` are a literal quote and backslash
+                '\\' if in_quotes && matches!(chars.peek(), Some('"' | '\\')) => {
+                    current_arg.extend(chars.next());
+                }
                 _ => current_arg.push(c),
             }
         }
@@ -178,71 +362,81 @@ impl Query {
         Ok(Self::new(cmd, args.args))
     }
 
+    // the argument at `idx`, failing with `MissingArgs` if there are fewer arguments
+    fn required(&self, idx: usize) -> Result<&String, QueryError> {
+        self.args
+            .get(idx)
+            .ok_or_else(|| QueryError::MissingArgs(self.cmd.clone(), idx + 1, self.args.len()))
+    }
+
+    // the argument at `idx` parsed as `T`, with `expected` describing it in case it doesn't parse
+    fn parsed<T: FromStr>(&self, idx: usize, expected: &str) -> Result<T, QueryError> {
+        self.args
+            .optional_at(idx, expected)?
+            .ok_or_else(|| QueryError::MissingArgs(self.cmd.clone(), idx + 1, self.args.len()))
+    }
+
     pub async fn eval(&self, db: &DBConnection) -> QueryResult {
-        let Query { cmd, args } = self;
+        let Query {
+            cmd,
+            args,
+            case_sensitive,
+            seed,
+        } = self;
 
         match cmd {
             QueryCommand::Help => print_help(),
             QueryCommand::Top => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
-                }
-                let prefix = args.get(0).expect("Expected a prefix argument");
-                let limit = args.optional_at(1);
-                top_words_starting_with(db, prefix, limit).await
+                let prefix = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                top_words_starting_with(db, prefix, limit, offset, *case_sensitive).await
             }
             QueryCommand::TopEnds => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
-                }
-                let suffix = args.get(0).expect("Expected a suffix argument");
-                let limit = args.optional_at(1);
-                top_words_ending_with(db, suffix, limit).await
+                let suffix = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                top_words_ending_with(db, suffix, limit, offset, *case_sensitive).await
             }
             QueryCommand::Texts => {
-                if args.len() < 2 {
-                    let limit = args.get(0).and_then(|a| a.parse::<usize>().ok());
-                    return texts_info(db, limit).await;
+                // a single argument is a limit if it's a number and a prefix otherwise
+                match args.get(0).map(|arg| arg.parse::<usize>()) {
+                    None => return texts_info(db, None).await,
+                    Some(Ok(limit)) if args.len() == 1 => return texts_info(db, Some(limit)).await,
+                    _ => {}
                 }
-                let prefix = args.get(0).expect("Expected a prefix argument");
-                let limit = args.optional_at(1);
-                texts_with_word_starting_with(db, prefix, limit).await
+                let prefix = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                texts_with_word_starting_with(db, prefix, limit, offset, *case_sensitive).await
             }
             QueryCommand::Ends => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
-                }
-                let suffix = args.get(0).expect("Expected a suffix argument");
-                let limit = args.optional_at(1);
-                words_ending_with(db, suffix, limit).await
+                let suffix = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                words_ending_with(db, suffix, limit, offset, *case_sensitive).await
             }
             QueryCommand::EndsTexts => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
-                }
-                let suffix = args.get(0).expect("Expected a suffix argument");
-                let limit = args.optional_at(1);
-                texts_with_word_ending_with(db, suffix, limit).await
+                let suffix = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                texts_with_word_ending_with(db, suffix, limit, offset, *case_sensitive).await
             }
             QueryCommand::Contains => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
-                }
-                let substring = args.get(0).expect("Expected a substring argument");
-                let limit = args.optional_at(1);
-                words_containing(db, substring, limit).await
+                let substring = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                words_containing(db, substring, limit, offset, *case_sensitive).await
             }
             QueryCommand::ContainsTexts => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(
-                        "contains-texts".into(),
-                        1,
-                        args.len(),
-                    ));
-                }
-                let substring = args.get(0).expect("Expected a substring argument");
-                let limit = args.optional_at(1);
-                texts_containing(db, substring, limit).await
+                let substring = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                texts_containing(db, substring, limit, offset, *case_sensitive).await
+            }
+            QueryCommand::Count => {
+                let relation = self.required(0)?;
+                count_relation(db, relation).await
             }
             QueryCommand::CountTexts => {
                 run_query(db, "?[count(text_id)] := *Text{text_id}", DBParams::new()).await
@@ -258,37 +452,96 @@ impl Query {
                 )
                 .await
             }
-            QueryCommand::Word => {
+            QueryCommand::Summary => summary(db).await,
+            QueryCommand::Schema => schema(db).await,
+            QueryCommand::Distinct => {
+                let prefix = self.required(0)?;
+                let limit = args.optional_at(1, LIMIT)?;
+                let offset = args.optional_at(2, OFFSET)?;
+                distinct_words_starting_with(db, prefix, limit, offset, *case_sensitive).await
+            }
+            QueryCommand::Histogram => {
                 if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
+                    return word_frequency_histogram(db, &DEFAULT_HISTOGRAM_BUCKETS).await;
                 }
-                let word = args.get(0).expect("Expected a word argument");
-                word_info(db, word, args.optional_at(1)).await
+                let buckets = args
+                    .args
+                    .iter()
+                    .map(|a| a.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| QueryError::InvalidBuckets(args.args.join(" ")))?;
+                word_frequency_histogram(db, &buckets).await
+            }
+            QueryCommand::Lengths => word_length_distribution(db).await,
+            QueryCommand::Hapax => hapax_legomena(db, args.optional_at(0, LIMIT)?).await,
+            QueryCommand::Lemma => {
+                let word = self.required(0)?;
+                lemma_info(db, word).await
+            }
+            QueryCommand::Folded => {
+                let word = self.required(0)?;
+                words_ignoring_macrons(db, word, args.optional_at(1, LIMIT)?).await
+            }
+            QueryCommand::Collocations => {
+                let word = self.required(0)?;
+                collocations(db, word, args.optional_at(1, LIMIT)?).await
+            }
+            QueryCommand::Concordance => {
+                let word = self.required(0)?;
+                let window = args
+                    .optional_at(1, "a number of words as window")?
+                    .unwrap_or(DEFAULT_CONCORDANCE_WINDOW);
+                concordance(db, word, window, args.optional_at(2, LIMIT)?).await
+            }
+            QueryCommand::Distinctive => {
+                let text_id = TextId::from(self.parsed::<usize>(0, "a numeric text id")?);
+                distinctive_words(db, text_id, args.optional_at(1, LIMIT)?).await
+            }
+            QueryCommand::Similar => {
+                let word = self.required(0)?;
+                let max_distance = args
+                    .optional_at(1, "a number as maximum distance")?
+                    .unwrap_or(DEFAULT_MAX_DISTANCE);
+                similar_words(db, word, max_distance).await
+            }
+            QueryCommand::Nearest => {
+                let text_id = TextId::from(self.parsed::<usize>(0, "a numeric text id")?);
+                let k = self.parsed(1, "a number of texts")?;
+                nearest_texts(db, text_id, k).await
+            }
+            QueryCommand::Word => {
+                let word = self.required(0)?;
+                word_info(db, word, args.optional_at(1, LIMIT)?, *case_sensitive).await
             }
             QueryCommand::Text => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
-                }
-                let text_id = TextId::from(
-                    args.get(0)
-                        .expect("Expected a text_id argument")
-                        .parse::<usize>()
-                        .expect("Expected a valid usize for text_id"),
-                );
-                text_info(db, text_id, args.optional_at(1)).await
+                let text_id = TextId::from(self.parsed::<usize>(0, "a numeric text id")?);
+                text_info(db, text_id, args.optional_at(1, LIMIT)?).await
             }
+            QueryCommand::Authors => authors_info(db, args.optional_at(0, LIMIT)?).await,
             QueryCommand::Author => {
-                if args.is_empty() {
-                    return Err(QueryError::MissingArgs(cmd.clone(), 1, args.len()));
-                }
-                let name = args.get(0).expect("Expected a name argument");
-                author_info(db, name, args.optional_at(1)).await
+                let name = self.required(0)?;
+                author_info(db, name, args.optional_at(1, LIMIT)?).await
+            }
+            QueryCommand::AuthorStats => {
+                let name = self.required(0)?;
+                author_stats(db, name).await
+            }
+            QueryCommand::Trending => {
+                let earlier = self.required(0)?.parse::<TextIdRange>()?;
+                let later = self.required(1)?.parse::<TextIdRange>()?;
+                trending_words(db, &earlier, &later, args.optional_at(2, LIMIT)?).await
             }
-            QueryCommand::Quit | QueryCommand::Exit => std::process::exit(0),
-            QueryCommand::Clear => {
-                print!("\x1B[2J\x1B[1;1H");
-                Ok(NamedRows::new(Vec::new(), Vec::new()))
+            QueryCommand::Sample => {
+                let per_author = self.parsed(0, "a number of texts per author")?;
+                let seed = args
+                    .optional_at(1, "a number as seed")?
+                    .or(*seed)
+                    .unwrap_or_else(random_seed);
+                sample_texts_per_author(db, per_author, seed).await
             }
+            QueryCommand::Quit | QueryCommand::Exit => Err(QueryError::Quit),
+            // clearing the screen is up to the caller, there's nothing to query
+            QueryCommand::Clear => Ok(NamedRows::new(Vec::new(), Vec::new())),
             QueryCommand::Unknown(cmd) => Err(QueryError::UnknownQuery(cmd.clone())),
         }
     }
@@ -312,11 +565,21 @@ impl Args {
         self.args.get(idx)
     }
 
-    pub fn optional_at<T: FromStr>(&self, idx: usize) -> Option<T> {
+    /// The argument at `idx` parsed as `T`, or `None` if there are fewer arguments.
+    /// An argument that doesn't parse is an `InvalidArgument` error, with `expected` describing it.
+    pub fn optional_at<T: FromStr>(
+        &self,
+        idx: usize,
+        expected: &str,
+    ) -> Result<Option<T>, QueryError> {
         self.get(idx)
-            .map(|a| a.parse::<T>())
-            .map(|a| a.ok())
-            .unwrap_or(None)
+            .map(|arg| {
+                arg.parse().map_err(|_| QueryError::InvalidArgument {
+                    arg: arg.clone(),
+                    reason: format!("expected {}", expected),
+                })
+            })
+            .transpose()
     }
 
     pub fn len(&self) -> usize {
@@ -334,39 +597,60 @@ impl Default for Args {
     }
 }
 
+// edit distance between two strings, counting single character insertions, deletions and substitutions
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
 pub fn print_help() -> QueryResult {
     Ok(NamedRows::new(
         vec!["Available queries:".into(), "Description:".into()],
         vec![
             vec![
-                "/top <prefix> ?<limit>".into(),
+                "/top <prefix> ?<limit> ?<offset>".into(),
                 "Get top words starting with a prefix by count".into(),
             ],
             vec![
-                "/top-ends <suffix> ?<limit>".into(),
+                "/top-ends <suffix> ?<limit> ?<offset>".into(),
                 "Get top words ending with a suffix by count".into(),
             ],
             vec![
-                "/texts <prefix> ?<limit>".into(),
+                "/texts <prefix> ?<limit> ?<offset>".into(),
                 "Get texts with words starting with prefix".into(),
             ],
             vec!["/texts ?<limit>".into(), "Get all texts".into()],
             vec![
-                "/ends <suffix> ?<limit>".into(),
+                "/ends <suffix> ?<limit> ?<offset>".into(),
                 "Get words ending with suffix".into(),
             ],
             vec![
-                "/ends-texts <suffix> ?<limit>".into(),
+                "/ends-texts <suffix> ?<limit> ?<offset>".into(),
                 "Get texts with words ending with suffix".into(),
             ],
             vec![
-                "/contains <substring> ?<limit>".into(),
+                "/contains <substring> ?<limit> ?<offset>".into(),
                 "Get words containing substring".into(),
             ],
             vec![
-                "/contains-texts <substring> ?<limit>".into(),
+                "/contains-texts <substring> ?<limit> ?<offset>".into(),
                 "Get texts containing substring".into(),
             ],
+            vec![
+                "/count <relation>".into(),
+                "Get the number of rows in a relation".into(),
+            ],
             vec![
                 "/count-texts".into(),
                 "Get the number of texts in the database".into(),
@@ -379,9 +663,82 @@ pub fn print_help() -> QueryResult {
                 "/count-words".into(),
                 "Get the number of words in the database".into(),
             ],
+            vec![
+                "/summary".into(),
+                "Get the total and unique words, texts and authors in the database".into(),
+            ],
+            vec![
+                "/schema".into(),
+                "Get all relations of the database with their key and value columns".into(),
+            ],
+            vec![
+                "/distinct <prefix> ?<limit> ?<offset>".into(),
+                "Get distinct words starting with prefix with counts summed across texts".into(),
+            ],
+            vec![
+                "/histogram ?<from> ?<from>...".into(),
+                "Get the number of distinct words per total occurrence count bucket".into(),
+            ],
+            vec![
+                "/lengths".into(),
+                "Get the number of distinct words and their occurrences per word length".into(),
+            ],
+            vec![
+                "/hapax ?<limit>".into(),
+                "Get words occurring exactly once across all texts".into(),
+            ],
+            vec![
+                "/lemma <word>".into(),
+                "Get counts of all inflections of a word (needs `import-library --lemmatize`)"
+                    .into(),
+            ],
+            vec![
+                "/folded <word> ?<limit>".into(),
+                "Get all spellings of a word with or without macrons (ā, ē, ī, ō, ū, ȳ)".into(),
+            ],
+            vec![
+                "/collocations <word> ?<limit>".into(),
+                "Get the most frequent words following a word (needs `import-library --bigrams`)"
+                    .into(),
+            ],
+            vec![
+                "/concordance <word> ?<window> ?<limit>".into(),
+                "Get every occurrence of a word with the words around it (default 5 on each side)"
+                    .into(),
+            ],
+            vec![
+                "/distinctive <text_id> ?<limit>".into(),
+                "Get the most characteristic words of a text by TF-IDF score".into(),
+            ],
+            vec![
+                "/similar <word> ?<max_distance>".into(),
+                "Get words within an edit distance of a word (default 2), e.g. spelling variants"
+                    .into(),
+            ],
+            vec![
+                "/nearest <text_id> <k>".into(),
+                "Get the k texts with the most similar embeddings, see `embeddings`".into(),
+            ],
             vec!["/word <word>".into(), "Get all info for a word".into()],
             vec!["/text <text_id>".into(), "Get all info for a text".into()],
+            vec![
+                "/authors ?<limit>".into(),
+                "Get all authors with their number of texts".into(),
+            ],
             vec!["/author <name>".into(), "Get all info for an author".into()],
+            vec![
+                "/author-stats <name>".into(),
+                "Get total and unique words and the type-token ratio across an author's texts"
+                    .into(),
+            ],
+            vec![
+                "/trending <from>-<to> <from>-<to> ?<limit>".into(),
+                "Get words by change in relative frequency between two text id ranges".into(),
+            ],
+            vec![
+                "/sample <per_author> ?<seed>".into(),
+                "Get a random sample of texts with the same number of texts per author".into(),
+            ],
             vec!["/quit".into(), "Quit the program".into()],
             vec!["/exit".into(), "Quit the program".into()],
             vec!["/clear".into(), "Clear the screen".into()],
@@ -394,15 +751,21 @@ pub async fn top_words_starting_with(
     db: &DBConnection,
     prefix: &str,
     limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
 ) -> QueryResult {
-    let (query, params) = query_with_optional_limit(
-        r#"
-        ?[word, sum(count), count(text_id)] := *Word{word,count,text_id},
-          starts_with(word, $prefix),
+    let (var, prefix) = var_and_term("word", prefix, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[word, sum(count), count(text_id)] := *Word{{word,count,text_id}},
+          starts_with({var}, $prefix),
           :sort -count(text_id), word
-        "#,
-        vec![("prefix".into(), prefix.to_lowercase().to_data_value())],
+        "#
+        ),
+        vec![("prefix".into(), prefix)],
         limit,
+        offset,
     );
 
     run_query(db, &query, params).await
@@ -412,15 +775,46 @@ pub async fn top_words_ending_with(
     db: &DBConnection,
     suffix: &str,
     limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
 ) -> QueryResult {
-    let (query, params) = query_with_optional_limit(
-        r#"
-        ?[word, sum(count), count(text_id)] := *Word{word,count,text_id},
-          ends_with(word, $suffix),
+    let (var, suffix) = var_and_term("word", suffix, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[word, sum(count), count(text_id)] := *Word{{word,count,text_id}},
+          ends_with({var}, $suffix),
           :sort -count(text_id), word
-        "#,
-        vec![("suffix".into(), suffix.to_lowercase().to_data_value())],
+        "#
+        ),
+        vec![("suffix".into(), suffix)],
         limit,
+        offset,
+    );
+
+    run_query(db, &query, params).await
+}
+
+// get each word starting with the given prefix once, with its count summed across all texts
+pub async fn distinct_words_starting_with(
+    db: &DBConnection,
+    prefix: &str,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
+) -> QueryResult {
+    let (var, prefix) = var_and_term("word", prefix, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[word, sum(count), count_unique(text_id)] := *Word{{word,count,text_id}},
+          starts_with({var}, $prefix),
+          :sort word
+        "#
+        ),
+        vec![("prefix".into(), prefix)],
+        limit,
+        offset,
     );
 
     run_query(db, &query, params).await
@@ -431,15 +825,21 @@ pub async fn texts_with_word_starting_with(
     db: &DBConnection,
     prefix: &str,
     limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
 ) -> QueryResult {
-    let (query, params) = query_with_optional_limit(
-        r#"
-        ?[text_id, url] := *Text{text_id,url},
-          *Word{word,count,text_id},
-          starts_with(word, $prefix)
-        "#,
-        vec![("prefix".into(), prefix.to_lowercase().to_data_value())],
+    let (var, prefix) = var_and_term("word", prefix, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[text_id, url] := *Text{{text_id,url}},
+          *Word{{word,count,text_id}},
+          starts_with({var}, $prefix)
+        "#
+        ),
+        vec![("prefix".into(), prefix)],
         limit,
+        offset,
     );
 
     run_query(db, &query, params).await
@@ -450,15 +850,21 @@ pub async fn words_ending_with(
     db: &DBConnection,
     suffix: &str,
     limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
 ) -> QueryResult {
-    let (query, params) = query_with_optional_limit(
-        r#"
-        ?[word, sum(count), count(text_id)] := *Word{word,count,text_id},
-          ends_with(word, $suffix),
+    let (var, suffix) = var_and_term("word", suffix, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[word, sum(count), count(text_id)] := *Word{{word,count,text_id}},
+          ends_with({var}, $suffix),
           :sort -count(text_id), word
-        "#,
-        vec![("suffix".into(), suffix.to_lowercase().to_data_value())],
+        "#
+        ),
+        vec![("suffix".into(), suffix)],
         limit,
+        offset,
     );
 
     run_query(db, &query, params).await
@@ -469,15 +875,21 @@ pub async fn texts_with_word_ending_with(
     db: &DBConnection,
     suffix: &str,
     limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
 ) -> QueryResult {
-    let (query, params) = query_with_optional_limit(
-        r#"
-        ?[text_id, url, text] := *Text{text_id,url,text},
-          *Word{word,count,text_id},
-          ends_with(word, $suffix)
-        "#,
-        vec![("suffix".into(), suffix.to_lowercase().to_data_value())],
+    let (var, suffix) = var_and_term("word", suffix, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[text_id, url, text] := *Text{{text_id,url,text}},
+          *Word{{word,count,text_id}},
+          ends_with({var}, $suffix)
+        "#
+        ),
+        vec![("suffix".into(), suffix)],
         limit,
+        offset,
     );
 
     run_query(db, &query, params).await
@@ -488,15 +900,21 @@ pub async fn words_containing(
     db: &DBConnection,
     substring: &str,
     limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
 ) -> QueryResult {
-    let (query, params) = query_with_optional_limit(
-        r#"
-        ?[word, sum(count), count(text_id)] := *Word{word,count,text_id},
-          str_includes(word, $substring),
+    let (var, substring) = var_and_term("word", substring, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[word, sum(count), count(text_id)] := *Word{{word,count,text_id}},
+          str_includes({var}, $substring),
           :sort -count(text_id), word
-        "#,
-        vec![("substring".into(), substring.to_lowercase().to_data_value())],
+        "#
+        ),
+        vec![("substring".into(), substring)],
         limit,
+        offset,
     );
 
     run_query(db, &query, params).await
@@ -507,33 +925,130 @@ pub async fn texts_containing(
     db: &DBConnection,
     substring: &str,
     limit: Option<usize>,
+    offset: Option<usize>,
+    case_sensitive: bool,
 ) -> QueryResult {
-    let (query, params) = query_with_optional_limit(
-        r#"
-        ?[text_id, url] := *Text{text_id,url,text},
-          str_includes(text, $substring)
-        "#,
-        vec![("substring".into(), substring.to_lowercase().to_data_value())],
+    let (var, substring) = var_and_term("text", substring, case_sensitive);
+    let (query, params) = query_with_limit_offset(
+        &format!(
+            r#"
+        ?[text_id, url] := *Text{{text_id,url,text}},
+          str_includes({var}, $substring)
+        "#
+        ),
+        vec![("substring".into(), substring)],
         limit,
+        offset,
     );
 
     run_query(db, &query, params).await
 }
 
-pub async fn word_info(db: &DBConnection, word: &str, limit: Option<usize>) -> QueryResult {
+// count the rows of any stored relation, including custom ones
+pub async fn count_relation(db: &DBConnection, relation: &str) -> QueryResult {
+    let count = db.count(relation).await?;
+
+    Ok(NamedRows::new(
+        vec!["relation".into(), "count".into()],
+        vec![vec![val(relation), val(count)]],
+    ))
+}
+
+pub async fn word_info(
+    db: &DBConnection,
+    word: &str,
+    limit: Option<usize>,
+    case_sensitive: bool,
+) -> QueryResult {
+    let (var, word) = var_and_term("word", word, case_sensitive);
     let (query, params) = query_with_optional_limit(
-        r#"
+        &format!(
+            r#"
         ?[word, count, text_id] :=
-            *Word{word,count,text_id},
-            word = $word
-        "#,
-        vec![("word".into(), word.to_lowercase().to_data_value())],
+            *Word{{word,count,text_id}},
+            {var} == $word
+        "#
+        ),
+        vec![("word".into(), word)],
         limit,
     );
 
     run_query(db, &query, params).await
 }
 
+/// Default number of words shown on each side of a word by `/concordance`.
+pub const DEFAULT_CONCORDANCE_WINDOW: usize = 5;
+
+// get every occurrence of a word in context (keyword in context), with up to `window`
+// words of the original text on each side, ordered by text and position
+pub async fn concordance(
+    db: &DBConnection,
+    word: &str,
+    window: usize,
+    limit: Option<usize>,
+) -> QueryResult {
+    let word = word.to_lowercase();
+    let texts = run_query(
+        db,
+        r#"
+        ?[text_id, url, text] := *Word{word: $word, text_id}, *Text{text_id, url, text}
+        :sort text_id
+        "#,
+        DBParams::from_iter(vec![("word".into(), word.to_data_value())]),
+    )
+    .await?;
+
+    let rows = texts
+        .rows
+        .iter()
+        .flat_map(|row| {
+            let text = row[2].get_str().unwrap_or_default();
+            concordance_snippets(text, &word, window)
+                .into_iter()
+                .map(|snippet| vec![row[0].clone(), row[1].clone(), val(snippet.as_str())])
+        })
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(NamedRows::new(
+        vec!["text_id".into(), "url".into(), "snippet".into()],
+        rows,
+    ))
+}
+
+// snippets of the plain text around each occurrence of `word`, keeping the original
+// spelling and punctuation. `...` marks snippets cut off before the start or end of the text.
+fn concordance_snippets(text: &str, word: &str, window: usize) -> Vec<String> {
+    let tokenizer = LatinTokenizer::default();
+    let plain_text = Text::new(String::new(), text.to_string())
+        .plain_text()
+        .into_owned();
+    let tokens: Vec<&str> = plain_text.split_whitespace().collect();
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| {
+            token
+                .split(|c| tokenizer.is_separator(c))
+                .filter_map(|t| tokenizer.word(t))
+                .any(|w| w.as_str() == word)
+        })
+        .map(|(idx, _)| {
+            let from = idx.saturating_sub(window);
+            let to = (idx + window + 1).min(tokens.len());
+            let mut snippet = tokens[from..to].join(" ");
+            if from > 0 {
+                snippet.insert_str(0, "... ");
+            }
+            if to < tokens.len() {
+                snippet.push_str(" ...");
+            }
+            snippet
+        })
+        .collect()
+}
+
 pub async fn text_info(db: &DBConnection, text_id: TextId, limit: Option<usize>) -> QueryResult {
     let (query, params) = query_with_optional_limit(
         r#"
@@ -548,7 +1063,46 @@ pub async fn text_info(db: &DBConnection, text_id: TextId, limit: Option<usize>)
         limit,
     );
 
-    run_query(db, &query, params).await
+    let mut info = run_query(db, &query, params).await?;
+
+    let body = run_query(
+        db,
+        "?[url, text] := *Text{text_id, url, text}, text_id = $text_id",
+        DBParams::from_iter(vec![("text_id".into(), text_id.to_data_value())]),
+    )
+    .await?;
+    let reading_time = body
+        .rows
+        .first()
+        .and_then(|row| {
+            Some(Text::from((
+                row.first()?.get_str()?,
+                row.get(1)?.get_str()?,
+            )))
+        })
+        .map(|text| format_duration(text.reading_time(Text::DEFAULT_LATIN_WPM)))
+        .unwrap_or_default();
+
+    info.headers.push("reading_time".into());
+    for row in info.rows.iter_mut() {
+        row.push(val(reading_time.as_str()));
+    }
+
+    Ok(info)
+}
+
+// format a duration as e.g. `1h 5m 30s`, leaving out leading zero units
+fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
 }
 
 pub async fn texts_info(db: &DBConnection, limit: Option<usize>) -> QueryResult {
@@ -566,6 +1120,22 @@ pub async fn texts_info(db: &DBConnection, limit: Option<usize>) -> QueryResult
     run_query(db, &query, params).await
 }
 
+// get all authors with their number of texts, most prolific first
+pub async fn authors_info(db: &DBConnection, limit: Option<usize>) -> QueryResult {
+    let (query, params) = query_with_optional_limit(
+        r#"
+        texts[author_id, count(text_id)] := *Text{text_id, author_id}
+        ?[name, url, texts] := *Author{author_id, name, url}, texts[author_id, texts]
+        ?[name, url, texts] := *Author{author_id, name, url}, not texts[author_id, _], texts = 0
+        :sort -texts, name
+        "#,
+        vec![],
+        limit,
+    );
+
+    run_query(db, &query, params).await
+}
+
 pub async fn author_info(db: &DBConnection, name: &str, limit: Option<usize>) -> QueryResult {
     let (query, params) = query_with_optional_limit(
         r#"
@@ -581,15 +1151,566 @@ pub async fn author_info(db: &DBConnection, name: &str, limit: Option<usize>) ->
     run_query(db, &query, params).await
 }
 
+// get vocabulary stats across all texts of an author, the type-token ratio
+// (unique / total words) being a simple measure of lexical richness
+pub async fn author_stats(db: &DBConnection, name: &str) -> QueryResult {
+    run_query(
+        db,
+        r#"
+        author_word[word, text_id, count] :=
+            *Author{author_id, name},
+            name = $name,
+            *Text{text_id, author_id},
+            *Word{word, text_id, count}
+        totals[count_unique(text_id), sum(count), count_unique(word)] :=
+            author_word[word, text_id, count]
+        ?[name, texts, total_words, unique_words, type_token_ratio] :=
+            totals[texts, total_words, unique_words],
+            texts > 0,
+            name = $name,
+            type_token_ratio = unique_words / total_words
+        "#,
+        DBParams::from_iter(vec![("name".into(), name.to_data_value())]),
+    )
+    .await
+}
+
+/// An inclusive range of text ids, written as `<from>-<to>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextIdRange {
+    pub from: TextId,
+    pub to: TextId,
+}
+
+impl FromStr for TextIdRange {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || QueryError::InvalidRange(s.to_string());
+        let (from, to) = s.split_once('-').ok_or_else(invalid)?;
+        let from = from.trim().parse::<usize>().map_err(|_| invalid())?;
+        let to = to.trim().parse::<usize>().map_err(|_| invalid())?;
+
+        if from > to {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+}
+
+// get the relative frequency of every word within the texts of the given id range
+async fn word_frequencies(
+    db: &DBConnection,
+    range: &TextIdRange,
+) -> Result<HashMap<String, f64>, QueryError> {
+    let rows = run_query(
+        db,
+        r#"
+        ?[word, sum(count)] := *Word{word, count, text_id},
+          text_id >= $from,
+          text_id <= $to
+        "#,
+        DBParams::from_iter(vec![
+            ("from".into(), range.from.to_data_value()),
+            ("to".into(), range.to.to_data_value()),
+        ]),
+    )
+    .await?;
+
+    let counts: Vec<(String, f64)> = rows
+        .rows
+        .iter()
+        .filter_map(|row| {
+            let word = row.first()?.get_str()?.to_string();
+            let count = row.get(1)?.get_float()?;
+            Some((word, count))
+        })
+        .collect();
+
+    let total: f64 = counts.iter().map(|(_, count)| count).sum();
+
+    Ok(counts
+        .into_iter()
+        .map(|(word, count)| (word, count / total))
+        .collect())
+}
+
+// get words ranked by how much their relative frequency grew from the earlier to the later range
+pub async fn trending_words(
+    db: &DBConnection,
+    earlier: &TextIdRange,
+    later: &TextIdRange,
+    limit: Option<usize>,
+) -> QueryResult {
+    let before = word_frequencies(db, earlier).await?;
+    let after = word_frequencies(db, later).await?;
+
+    let words: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut trends: Vec<(&String, f64, f64)> = words
+        .into_iter()
+        .map(|word| {
+            let b = before.get(word).copied().unwrap_or(0.0);
+            let a = after.get(word).copied().unwrap_or(0.0);
+            (word, b, a)
+        })
+        .collect();
+
+    trends.sort_by(|(w1, b1, a1), (w2, b2, a2)| {
+        (a2 - b2).total_cmp(&(a1 - b1)).then_with(|| w1.cmp(w2))
+    });
+
+    if let Some(limit) = limit {
+        trends.truncate(limit);
+    }
+
+    Ok(NamedRows::new(
+        vec![
+            "word".into(),
+            "frequency_before".into(),
+            "frequency_after".into(),
+            "change".into(),
+        ],
+        trends
+            .into_iter()
+            .map(|(word, b, a)| vec![val(word.as_str()), val(b), val(a), val(a - b)])
+            .collect(),
+    ))
+}
+
+/// Lower bounds of the default histogram buckets: 1, 2, 3-5, 6-10, ..., 1001+.
+pub const DEFAULT_HISTOGRAM_BUCKETS: [usize; 9] = [1, 2, 3, 6, 11, 21, 51, 101, 1001];
+
+// count distinct words by their total number of occurrences across all texts.
+// `buckets` are the (increasing) lower bounds of each bucket, the last one is open-ended.
+pub async fn word_frequency_histogram(db: &DBConnection, buckets: &[usize]) -> QueryResult {
+    if buckets.is_empty() || buckets[0] == 0 || buckets.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(QueryError::InvalidBuckets(format!("{:?}", buckets)));
+    }
+
+    let totals = run_query(
+        db,
+        "?[word, sum(count)] := *Word{word, count}",
+        DBParams::new(),
+    )
+    .await?;
+
+    let mut words_per_bucket = vec![0usize; buckets.len()];
+    for row in &totals.rows {
+        let Some(total) = row.get(1).and_then(|c| c.get_float()) else {
+            continue;
+        };
+        // words occurring less often than the first bound don't fall into any bucket
+        if let Some(bucket) = buckets.iter().rposition(|&from| total >= from as f64) {
+            words_per_bucket[bucket] += 1;
+        }
+    }
+
+    Ok(NamedRows::new(
+        vec!["occurrences".into(), "words".into()],
+        buckets
+            .iter()
+            .enumerate()
+            .map(|(i, &from)| {
+                let label = match buckets.get(i + 1) {
+                    Some(&next) if next == from + 1 => from.to_string(),
+                    Some(&next) => format!("{}-{}", from, next - 1),
+                    None => format!("{}+", from),
+                };
+                vec![val(label.as_str()), val(words_per_bucket[i])]
+            })
+            .collect(),
+    ))
+}
+
+// get an overview of the whole database in a single row
+pub async fn summary(db: &DBConnection) -> QueryResult {
+    run_query(
+        db,
+        r#"
+        words[sum(count), count_unique(word)] := *Word{word, text_id, count}
+        texts[count(text_id)] := *Text{text_id}
+        authors[count(author_id)] := *Author{author_id}
+        ?[total_words, unique_words, total_texts, total_authors] :=
+            words[word_sum, unique_words],
+            total_words = to_int(word_sum),
+            texts[total_texts],
+            authors[total_authors]
+        "#,
+        DBParams::new(),
+    )
+    .await
+}
+
+// get all relations with their columns, e.g. `word: String, text_id: Int` as keys of Word
+pub async fn schema(db: &DBConnection) -> QueryResult {
+    let columns = |columns: &mut dyn Iterator<Item = &ColumnInfo>| {
+        columns
+            .map(|c| format!("{}: {}", c.name, c.column_type))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let rows = db
+        .relations()
+        .await?
+        .iter()
+        .map(|relation| {
+            vec![
+                val(relation.name.as_str()),
+                val(columns(&mut relation.keys())),
+                val(columns(&mut relation.values())),
+            ]
+        })
+        .collect();
+
+    Ok(NamedRows::new(
+        vec!["relation".into(), "keys".into(), "values".into()],
+        rows,
+    ))
+}
+
+// count distinct words and their total occurrences per word length (in characters)
+pub async fn word_length_distribution(db: &DBConnection) -> QueryResult {
+    run_query(
+        db,
+        r#"
+        ?[length, count_unique(word), sum(count)] := *Word{word, text_id, count},
+            length = length(word)
+        :sort length
+        "#,
+        DBParams::new(),
+    )
+    .await
+}
+
+// get all words occurring exactly once across all texts (hapax legomena)
+pub async fn hapax_legomena(db: &DBConnection, limit: Option<usize>) -> QueryResult {
+    let (query, params) = query_with_optional_limit(
+        r#"
+        total[word, sum(count)] := *Word{word, count}
+        ?[word] := total[word, count], count == 1.0
+        :sort word
+        "#,
+        vec![],
+        limit,
+    );
+
+    run_query(db, &query, params).await
+}
+
+// get counts aggregated across all stored inflections of the given word's lemma
+pub async fn lemma_info(db: &DBConnection, word: &str) -> QueryResult {
+    let lemma = EndingLemmatizer.lemmatize(&Word::from(word.to_lowercase()));
+
+    run_query(
+        db,
+        r#"
+        ?[lemma, sum(count), count_unique(word), count_unique(text_id)] :=
+            *Lemma{word, lemma},
+            lemma = $lemma,
+            *Word{word, count, text_id}
+        "#,
+        DBParams::from_iter(vec![("lemma".into(), lemma.to_data_value())]),
+    )
+    .await
+}
+
+// get all stored forms of a word that only differ in macrons, e.g. `amicus` and `amīcus`
+pub async fn words_ignoring_macrons(
+    db: &DBConnection,
+    word: &str,
+    limit: Option<usize>,
+) -> QueryResult {
+    let folded = Word::from(word.to_lowercase()).fold_macrons();
+
+    // decomposing splits off macrons as combining characters (U+0304), which are then removed
+    let (query, params) = query_with_optional_limit(
+        r#"
+        ?[word, sum(count), count(text_id)] := *Word{word, count, text_id},
+          decomposed = unicode_normalize(word, 'nfd'),
+          unicode_normalize(regex_replace_all(decomposed, $macron, ''), 'nfc') == $folded,
+          :sort -count(text_id), word
+        "#,
+        vec![
+            ("folded".into(), folded.to_data_value()),
+            ("macron".into(), "\u{0304}".to_data_value()),
+        ],
+        limit,
+    );
+
+    run_query(db, &query, params).await
+}
+
+// get the words most frequently following the given word
+pub async fn collocations(db: &DBConnection, word: &str, limit: Option<usize>) -> QueryResult {
+    let (query, params) = query_with_optional_limit(
+        r#"
+        ?[word, next, sum(count), count(text_id)] := *BiGram{first: word, second: next, count, text_id},
+          word = $word,
+          :sort -sum(count), next
+        "#,
+        vec![("word".into(), word.to_lowercase().to_data_value())],
+        limit,
+    );
+
+    run_query(db, &query, params).await
+}
+
+// get the words of a text by TF-IDF score: their share of the text's words
+// times the log of the number of texts over the number of texts containing them
+pub async fn distinctive_words(
+    db: &DBConnection,
+    text_id: TextId,
+    limit: Option<usize>,
+) -> QueryResult {
+    let (query, params) = query_with_optional_limit(
+        r#"
+        text_word[word, count] := *Word{word, text_id, count}, text_id = $text_id
+        text_total[sum(count)] := text_word[word, count]
+        doc_freq[word, count(text_id)] := text_word[word, _], *Word{word, text_id}
+        text_count[count_unique(text_id)] := *Word{text_id}
+        ?[word, count, score] :=
+            text_word[word, count],
+            text_total[total],
+            doc_freq[word, df],
+            text_count[n],
+            score = count / total * ln(to_float(n) / df)
+        :sort -score, word
+        "#,
+        vec![("text_id".into(), text_id.to_data_value())],
+        limit,
+    );
+
+    run_query(db, &query, params).await
+}
+
+/// Default maximum edit distance for `/similar`.
+pub const DEFAULT_MAX_DISTANCE: usize = 2;
+
+/// Maximum number of candidate words compared by `/similar`, to keep it responsive.
+pub const MAX_SIMILAR_CANDIDATES: usize = 50_000;
+
+// get words within the given edit distance of a word, closest and most frequent first.
+// only words of a length that can be within the distance are compared at all.
+pub async fn similar_words(db: &DBConnection, word: &str, max_distance: usize) -> QueryResult {
+    let word = word.to_lowercase();
+    let len = word.chars().count();
+
+    let candidates = run_query(
+        db,
+        r#"
+        ?[word, sum(count)] := *Word{word, count},
+          len = length(word),
+          len >= $min_len,
+          len <= $max_len
+        :limit $limit
+        "#,
+        DBParams::from_iter(vec![
+            (
+                "min_len".into(),
+                len.saturating_sub(max_distance).to_data_value(),
+            ),
+            (
+                "max_len".into(),
+                len.saturating_add(max_distance).to_data_value(),
+            ),
+            ("limit".into(), MAX_SIMILAR_CANDIDATES.to_data_value()),
+        ]),
+    )
+    .await?;
+
+    let mut similar: Vec<(String, usize, f64)> = candidates
+        .rows
+        .iter()
+        .filter_map(|row| {
+            let candidate = row.first()?.get_str()?;
+            let count = row.get(1)?.get_float()?;
+            let distance = levenshtein(&word, candidate);
+            (distance <= max_distance).then(|| (candidate.to_string(), distance, count))
+        })
+        .collect();
+
+    similar.sort_by(|(w1, d1, c1), (w2, d2, c2)| {
+        d1.cmp(d2)
+            .then_with(|| c2.total_cmp(c1))
+            .then_with(|| w1.cmp(w2))
+    });
+
+    Ok(NamedRows::new(
+        vec!["word".into(), "distance".into(), "count".into()],
+        similar
+            .into_iter()
+            .map(|(word, distance, count)| vec![val(word.as_str()), val(distance), val(count)])
+            .collect(),
+    ))
+}
+
+/// Maximum number of texts returned by `/nearest`, as the HNSW search grows with it.
+pub const MAX_NEAREST_TEXTS: usize = 1_000;
+
+// get the k texts closest to a text by cosine distance of their embeddings, via the HNSW index.
+// the text itself is found as well, so one more neighbor is searched and then skipped.
+pub async fn nearest_texts(db: &DBConnection, text_id: TextId, k: usize) -> QueryResult {
+    let relations = db.relation_names().await?;
+    if !relations
+        .iter()
+        .any(|name| name == embeddings::TEXT_EMBEDDING_RELATION)
+    {
+        return Err(QueryError::NoEmbeddings);
+    }
+
+    let k = k.min(MAX_NEAREST_TEXTS);
+    let query = format!(
+        r#"
+        ?[text_id, url, distance] :=
+          *{relation}{{text_id: source, embedding: v}},
+          source = $text_id,
+          ~{index}{{text_id | query: v, k: {neighbors}, ef: {ef}, bind_distance: distance}},
+          text_id != source,
+          *Text{{text_id, url}}
+        :sort distance
+        :limit {k}
+        "#,
+        relation = embeddings::TEXT_EMBEDDING_RELATION,
+        index = embeddings::TEXT_EMBEDDING_INDEX,
+        neighbors = k + 1,
+        ef = (k + 1).max(50),
+        k = k,
+    );
+
+    run_query(
+        db,
+        &query,
+        DBParams::from_iter(vec![("text_id".into(), text_id.to_data_value())]),
+    )
+    .await
+}
+
+/// Small seedable pseudo random number generator (SplitMix64), so that
+/// samples can be reproduced by passing the same seed again.
+struct SampleRng(u64);
+
+impl SampleRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+fn random_seed() -> u64 {
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    log::info!(target: LOG_TARGET, "Sampling with seed: {}", seed);
+    seed
+}
+
+// get a random sample of up to `per_author` texts from every author
+pub async fn sample_texts_per_author(
+    db: &DBConnection,
+    per_author: usize,
+    seed: u64,
+) -> QueryResult {
+    let texts = run_query(
+        db,
+        r#"
+        ?[author_id, author_name, text_id, url] :=
+            *Author{author_id, name: author_name},
+            *Text{text_id, author_id, url}
+        :sort author_id, text_id
+        "#,
+        DBParams::new(),
+    )
+    .await?;
+
+    let mut by_author: BTreeMap<Option<i64>, Vec<Vec<DataValue>>> = BTreeMap::new();
+    for row in texts.rows {
+        let author_id = row.first().and_then(|a| a.get_int());
+        by_author.entry(author_id).or_default().push(row);
+    }
+
+    let mut rng = SampleRng(seed);
+    let mut sample = Vec::new();
+    for (_, mut rows) in by_author {
+        // partial Fisher-Yates shuffle picking `per_author` texts without replacement
+        let n = per_author.min(rows.len());
+        for i in 0..n {
+            let j = i + rng.below(rows.len() - i);
+            rows.swap(i, j);
+        }
+        rows.truncate(n);
+        sample.extend(rows);
+    }
+
+    Ok(NamedRows::new(texts.headers, sample))
+}
+
 async fn run_query(db: &DBConnection, query: &str, params: DBParams) -> QueryResult {
     db.run_immutable(query, params)
         .await
         .map_err(QueryError::from)
 }
 
+// the column expression and term to match, like `Search::var_and_term` in the UI:
+// unless matching case-sensitively, both the column and the term are lowercased
+fn var_and_term(var: &str, term: &str, case_sensitive: bool) -> (String, DataValue) {
+    if case_sensitive {
+        (var.to_string(), term.to_data_value())
+    } else {
+        (
+            format!("lowercase({})", var),
+            term.to_lowercase().to_data_value(),
+        )
+    }
+}
+
+fn query_with_optional_limit(
+    query: &str,
+    params: Vec<(String, DataValue)>,
+    limit: Option<usize>,
+) -> (String, DBParams) {
+    query_with_limit_offset(query, params, limit, None)
+}
+
+// like `query_with_optional_limit`, but also skipping the first `offset` rows for paging
+fn query_with_limit_offset(
+    query: &str,
+    params: Vec<(String, DataValue)>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> (String, DBParams) {
+    let mut query = query.to_string();
+    let mut params = DBParams::from_iter(params);
+
+    if let Some(limit) = limit {
+        query.push_str(format!(":limit {}\n", limit).as_str());
+        params.insert("limit".into(), limit.to_data_value());
+    }
+
+    if let Some(offset) = offset {
+        query.push_str(format!(":offset {}\n", offset).as_str());
+        params.insert("offset".into(), offset.to_data_value());
+    }
+
+    (query, params)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::db::{empty_db, seeded_db};
 
     #[test]
     fn test_parse_query() {
@@ -631,21 +1752,866 @@ mod test {
         );
 
         assert_eq!(Query::parse(r#""#), Err(QueryError::EmptyQuery));
+
+        assert_eq!(
+            Query::parse(r#"command "say \"hi\"" "back\\slash" "a\b""#),
+            Ok(Query::new(
+                "command".to_string(),
+                vec![
+                    r#"say "hi""#.to_string(),
+                    r"back\slash".to_string(),
+                    r"a\b".to_string()
+                ]
+            ))
+        );
+
+        assert_eq!(
+            Query::parse(r#"command "escaped \""#),
+            Err(QueryError::UnmatchedQuotes)
+        );
     }
-}
 
-fn query_with_optional_limit(
-    query: &str,
-    params: Vec<(String, DataValue)>,
-    limit: Option<usize>,
-) -> (String, DBParams) {
-    let mut query = query.to_string();
-    let mut params = DBParams::from_iter(params);
+    #[test]
+    fn test_query_command_from_str() {
+        for cmd in QueryCommand::all() {
+            assert_eq!(cmd.to_string().parse::<QueryCommand>(), Ok(cmd.clone()));
+            assert_eq!(QueryCommand::from(cmd.to_string().as_str()), cmd);
+        }
 
-    if let Some(limit) = limit {
-        query.push_str(format!(":limit {}", limit).as_str());
-        params.insert("limit".into(), limit.to_data_value());
+        assert_eq!(
+            "contians".parse::<QueryCommand>(),
+            Err(QueryError::UnknownCommand {
+                command: "contians".into(),
+                suggestion: Some("contains".into()),
+            })
+        );
+        assert_eq!(
+            "xyzzy".parse::<QueryCommand>(),
+            Err(QueryError::UnknownCommand {
+                command: "xyzzy".into(),
+                suggestion: None,
+            })
+        );
+        assert_eq!(
+            "tpo".parse::<QueryCommand>().unwrap_err().to_string(),
+            "Unknown command: tpo (did you mean top?)"
+        );
+
+        // lenient parsing keeps unknown commands around
+        assert_eq!(
+            QueryCommand::from("xyzzy"),
+            QueryCommand::Unknown("xyzzy".into())
+        );
     }
 
-    (query, params)
+    #[test]
+    fn test_format_duration() {
+        use std::time::Duration;
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(330)), "5m 30s");
+        assert_eq!(format_duration(Duration::from_secs(3930)), "1h 5m 30s");
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("amicus", "amicus"), 0);
+        assert_eq!(levenshtein("amicus", "amici"), 2);
+        assert_eq!(levenshtein("", "ave"), 3);
+        assert_eq!(levenshtein("tē", "te"), 1);
+    }
+
+    #[test]
+    fn test_parse_text_id_range() {
+        assert_eq!(
+            "1-10".parse::<TextIdRange>(),
+            Ok(TextIdRange {
+                from: 1usize.into(),
+                to: 10usize.into()
+            })
+        );
+        assert_eq!(
+            "10-1".parse::<TextIdRange>(),
+            Err(QueryError::InvalidRange("10-1".into()))
+        );
+        assert_eq!(
+            "10".parse::<TextIdRange>(),
+            Err(QueryError::InvalidRange("10".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trending_words() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amo', 1, 3], ['te', 1, 1],
+                ['amo', 2, 1], ['te', 2, 3], ['vale', 2, 4]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let rows = Query::parse("trending 1-1 2-2")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+
+        let words: Vec<_> = rows.rows.iter().map(|row| row[0].clone()).collect();
+        assert_eq!(words, vec![val("vale"), val("te"), val("amo")]);
+        assert_eq!(rows.rows[0][3], val(0.5));
+        assert_eq!(rows.rows[2][3], val(0.125 - 0.75));
+
+        let rows = Query::parse("trending 1-1 2-2 1")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_distinct_words() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amo', 1, 3], ['amas', 1, 1], ['te', 1, 2],
+                ['amo', 2, 1], ['amas', 3, 2], ['amo', 3, 4]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let rows = Query::parse("distinct am")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        // sum() aggregates to a float, so compare the counts numerically
+        let distinct: Vec<_> = rows
+            .rows
+            .iter()
+            .map(|row| (row[0].clone(), row[1].get_float(), row[2].get_int()))
+            .collect();
+        assert_eq!(
+            distinct,
+            vec![
+                (val("amas"), Some(3.0), Some(2)),
+                (val("amo"), Some(8.0), Some(3)),
+            ]
+        );
+
+        let rows = Query::parse("distinct am 1")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_word_frequency_histogram() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amo', 1, 3], ['amo', 2, 4], ['amas', 1, 1], ['amat', 2, 1],
+                ['te', 1, 2], ['et', 1, 20], ['et', 2, 30]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let rows = Query::parse("histogram").unwrap().eval(&db).await.unwrap();
+        assert_eq!(
+            rows.rows,
+            vec![
+                vec![val("1"), val(2usize)],
+                vec![val("2"), val(1usize)],
+                vec![val("3-5"), val(0usize)],
+                vec![val("6-10"), val(1usize)],
+                vec![val("11-20"), val(0usize)],
+                vec![val("21-50"), val(1usize)],
+                vec![val("51-100"), val(0usize)],
+                vec![val("101-1000"), val(0usize)],
+                vec![val("1001+"), val(0usize)],
+            ]
+        );
+
+        let rows = Query::parse("histogram 1 3")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(
+            rows.rows,
+            vec![vec![val("1-2"), val(3usize)], vec![val("3+"), val(2usize)]]
+        );
+
+        assert!(matches!(
+            Query::parse("histogram 3 1").unwrap().eval(&db).await,
+            Err(QueryError::InvalidBuckets(_))
+        ));
+        assert!(matches!(
+            Query::parse("histogram one").unwrap().eval(&db).await,
+            Err(QueryError::InvalidBuckets(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_paging_with_offset() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amo', 1, 1], ['amas', 1, 1], ['amo', 2, 1], ['amat', 1, 1],
+                ['amat', 2, 1], ['amat', 3, 1]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let page = |query: &str| {
+            let query = Query::parse(query).unwrap();
+            let db = &db;
+            async move {
+                query
+                    .eval(db)
+                    .await
+                    .unwrap()
+                    .rows
+                    .into_iter()
+                    .map(|row| row[0].clone())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            page("top am").await,
+            vec![val("amat"), val("amo"), val("amas")]
+        );
+        assert_eq!(page("top am 1").await, vec![val("amat")]);
+        assert_eq!(page("top am 1 1").await, vec![val("amo")]);
+        assert_eq!(page("top am 2 2").await, vec![val("amas")]);
+        assert!(page("top am 2 3").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lemma_info() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amīcus', 1, 2], ['amīcō', 1, 1], ['amīcō', 2, 3], ['amo', 2, 1]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[word, lemma] <- [['amīcus', 'amīc'], ['amīcō', 'amīc'], ['amo', 'am']];
+            :put Lemma { word => lemma }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("lemma Amīcī")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        let row = &rows.rows[0];
+        assert_eq!(row[0], val("amīc"));
+        assert_eq!(row[1].get_float(), Some(6.0));
+        assert_eq!(row[2].get_int(), Some(2));
+        assert_eq!(row[3].get_int(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_words_ignoring_macrons() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amīcus', 1, 2], ['amicus', 2, 1], ['amīcus', 3, 1], ['amīcī', 1, 1]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        for query in ["folded amicus", "folded Amīcus"] {
+            let rows = Query::parse(query).unwrap().eval(&db).await.unwrap();
+            let words: Vec<_> = rows.rows.iter().map(|row| row[0].clone()).collect();
+            assert_eq!(words, vec![val("amīcus"), val("amicus")], "{}", query);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collocations() {
+        let db = seeded_db(
+            r#"
+            ?[first, second, text_id, count] <- [
+                ['senatus', 'populusque', 1, 2], ['senatus', 'populusque', 2, 1],
+                ['senatus', 'consultum', 1, 1], ['populusque', 'romanus', 1, 2]
+            ];
+            :put BiGram { first, second, text_id => count }
+            "#,
+        )
+        .await;
+
+        let rows = Query::parse("collocations Senatus")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        let next: Vec<_> = rows
+            .rows
+            .iter()
+            .map(|row| (row[1].clone(), row[2].get_float(), row[3].get_int()))
+            .collect();
+        assert_eq!(
+            next,
+            vec![
+                (val("populusque"), Some(3.0), Some(2)),
+                (val("consultum"), Some(1.0), Some(1)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_distinctive_words() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amo', 1, 2], ['te', 1, 2], ['amo', 2, 1], ['amas', 2, 1], ['amat', 2, 1]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let rows = Query::parse("distinctive 2")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        let scores: Vec<_> = rows
+            .rows
+            .iter()
+            .map(|row| (row[0].clone(), row[2].get_float().unwrap()))
+            .collect();
+        let amas = 1.0 / 3.0 * 2f64.ln();
+        assert_eq!(
+            scores,
+            vec![(val("amas"), amas), (val("amat"), amas), (val("amo"), 0.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_and_missing_args() {
+        let db = empty_db().await;
+
+        let result = Query::parse("text abc").unwrap().eval(&db).await;
+        assert!(matches!(
+            result,
+            Err(QueryError::InvalidArgument { arg, .. }) if arg == "abc"
+        ));
+
+        let result = Query::parse("top am notanumber").unwrap().eval(&db).await;
+        assert!(matches!(
+            result,
+            Err(QueryError::InvalidArgument { arg, reason })
+                if arg == "notanumber" && reason == "expected a number as limit"
+        ));
+
+        let result = Query::parse("top am 10 x").unwrap().eval(&db).await;
+        assert!(matches!(
+            result,
+            Err(QueryError::InvalidArgument { arg, .. }) if arg == "x"
+        ));
+
+        assert!(Query::parse("top am").unwrap().eval(&db).await.is_ok());
+
+        let result = Query::parse("trending 1-2").unwrap().eval(&db).await;
+        assert!(matches!(
+            result,
+            Err(QueryError::MissingArgs(QueryCommand::Trending, 2, 1))
+        ));
+
+        let result = Query::parse("word").unwrap().eval(&db).await;
+        assert!(matches!(
+            result,
+            Err(QueryError::MissingArgs(QueryCommand::Word, 1, 0))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_quit_and_clear_are_left_to_the_caller() {
+        let db = DBConnection::new_in_memory().unwrap();
+
+        for cmd in ["quit", "exit"] {
+            let result = Query::parse(cmd).unwrap().eval(&db).await;
+            assert!(matches!(result, Err(QueryError::Quit)));
+        }
+
+        let rows = Query::parse("clear").unwrap().eval(&db).await.unwrap();
+        assert!(rows.headers.is_empty() && rows.rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_authors_info() {
+        let db = seeded_db(
+            r#"
+            ?[author_id, name, url] <- [[0, 'Caesar', 'a'], [1, 'Cicero', 'b'], [2, 'Ennius', 'c']];
+            :put Author { author_id, name => url }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[text_id, author_id, url, text] <- [[1, 0, 'a1', ''], [2, 1, 'b1', ''], [3, 1, 'b2', '']];
+            :put Text { text_id, author_id => url, text }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("authors").unwrap().eval(&db).await.unwrap();
+        assert_eq!(
+            rows.rows,
+            vec![
+                vec![val("Cicero"), val("b"), val(2i64)],
+                vec![val("Caesar"), val("a"), val(1i64)],
+                vec![val("Ennius"), val("c"), val(0i64)],
+            ]
+        );
+
+        let rows = Query::parse("authors 1").unwrap().eval(&db).await.unwrap();
+        assert_eq!(rows.rows, vec![vec![val("Cicero"), val("b"), val(2i64)]]);
+    }
+
+    #[tokio::test]
+    async fn test_author_stats() {
+        let db = seeded_db(
+            r#"
+            ?[author_id, name, url] <- [[0, 'Caesar', 'a'], [1, 'Cicero', 'b']];
+            :put Author { author_id, name => url }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[text_id, author_id, url, text] <- [[1, 0, 'a1', ''], [2, 0, 'a2', ''], [3, 1, 'b1', '']];
+            :put Text { text_id, author_id => url, text }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+        db.run_mutable(
+            r#"
+            ?[word, text_id, count] <- [
+                ['gallia', 1, 3], ['est', 1, 1], ['gallia', 2, 2], ['omnis', 2, 2], ['quo', 3, 5]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("author-stats Caesar")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        let row = &rows.rows[0];
+        assert_eq!(row[0], val("Caesar"));
+        assert_eq!(row[1].get_int(), Some(2));
+        assert_eq!(row[2].get_float(), Some(8.0));
+        assert_eq!(row[3].get_int(), Some(3));
+        assert_eq!(row[4].get_float(), Some(3.0 / 8.0));
+
+        let rows = Query::parse("author-stats Ennius")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert!(rows.rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_similar_words() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amicus', 1, 5], ['amicos', 1, 2], ['amici', 2, 3], ['inimicus', 2, 1],
+                ['arnicus', 3, 1], ['amicus', 3, 1]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let similar = |query: &str| {
+            let query = Query::parse(query).unwrap();
+            let db = &db;
+            async move {
+                query
+                    .eval(db)
+                    .await
+                    .unwrap()
+                    .rows
+                    .into_iter()
+                    .map(|row| (row[0].clone(), row[1].get_int().unwrap()))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            similar("similar amicus").await,
+            vec![
+                (val("amicus"), 0),
+                (val("amicos"), 1),
+                (val("amici"), 2),
+                (val("arnicus"), 2),
+            ]
+        );
+        assert_eq!(
+            similar("similar Amicus 1").await,
+            vec![(val("amicus"), 0), (val("amicos"), 1)]
+        );
+        assert_eq!(
+            similar("similar amicus 18446744073709551615").await.len(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_nearest_texts() {
+        let db = seeded_db(
+            r#"
+            ?[text_id, author_id, url, text] <- [
+                [1, 1, 'a.html', ''], [2, 1, 'b.html', ''], [3, 2, 'c.html', '']
+            ];
+            :put Text { text_id, author_id => url, text }
+            "#,
+        )
+        .await;
+        embeddings::create_embedding_schema(&db, 2).await.unwrap();
+        embeddings::store_text_embeddings(
+            &db,
+            &[
+                (1usize.into(), vec![1.0, 0.0]),
+                (2usize.into(), vec![0.0, 1.0]),
+                (3usize.into(), vec![0.9, 0.1]),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("nearest 1 1")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows.len(), 1);
+        assert_eq!(rows.rows[0][0], val(3i64));
+        assert_eq!(rows.rows[0][1], val("c.html"));
+
+        let rows = Query::parse("nearest 1 5")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        let ids: Vec<DataValue> = rows.rows.iter().map(|row| row[0].clone()).collect();
+        assert_eq!(ids, vec![val(3i64), val(2i64)]);
+
+        let rows = Query::parse("nearest 1 18446744073709551615")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_nearest_texts_without_embeddings() {
+        let db = empty_db().await;
+        let result = Query::parse("nearest 1 5").unwrap().eval(&db).await;
+        assert!(matches!(result, Err(QueryError::NoEmbeddings)));
+    }
+
+    #[tokio::test]
+    async fn test_case_sensitivity() {
+        let db = seeded_db(
+            r#"
+            ?[text_id, author_id, url, text] <- [[1, 0, 'a1', 'Gallia est omnis dīvīsa']];
+            :put Text { text_id, author_id => url, text }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[word, text_id, count] <- [['gallia', 1, 1], ['Roma', 1, 1]];
+            :put Word { word, text_id => count }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = |query: &str, case_sensitive: bool| {
+            let query = Query::parse(query)
+                .unwrap()
+                .with_case_sensitive(case_sensitive);
+            let db = &db;
+            async move { query.eval(db).await.unwrap().rows.len() }
+        };
+
+        assert_eq!(rows("contains-texts GALLIA", false).await, 1);
+        assert_eq!(rows("contains-texts gallia", true).await, 0);
+        assert_eq!(rows("contains-texts Gallia", true).await, 1);
+        assert_eq!(rows("top ROM", false).await, 1);
+        assert_eq!(rows("top rom", true).await, 0);
+        assert_eq!(rows("word roma", false).await, 1);
+        assert_eq!(rows("word Roma", true).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concordance() {
+        let db = seeded_db(
+            r#"
+            ?[text_id, author_id, url, text] <- [
+                [1, 0, 'a1', 'Gallia est omnis dīvīsa in partēs trēs, quārum ūnam incolunt Belgae.'],
+                [2, 0, 'a2', 'Hī omnēs linguā, īnstitūtīs, lēgibus inter sē differunt. Gallōs est'],
+                [3, 1, 'b1', 'Quō ūsque tandem']
+            ];
+            :put Text { text_id, author_id => url, text }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[word, text_id, count] <- [['est', 1, 1], ['est', 2, 1], ['tandem', 3, 1]];
+            :put Word { word, text_id => count }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("concordance Est 2")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(
+            rows.rows,
+            vec![
+                vec![val(1i64), val("a1"), val("Gallia est omnis dīvīsa ...")],
+                vec![val(2i64), val("a2"), val("... differunt. Gallōs est")],
+            ]
+        );
+
+        let rows = Query::parse("concordance est 2 1")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_schema() {
+        let db = empty_db().await;
+
+        let rows = Query::parse("schema").unwrap().eval(&db).await.unwrap();
+        assert_eq!(rows.headers, vec!["relation", "keys", "values"]);
+        assert!(rows.rows.contains(&vec![
+            val("Word"),
+            val("word: String, text_id: Int"),
+            val("count: Int"),
+        ]));
+    }
+
+    #[tokio::test]
+    async fn test_summary() {
+        let db = seeded_db(
+            r#"
+            ?[author_id, name, url] <- [[0, 'Caesar', 'a'], [1, 'Cicero', 'b']];
+            :put Author { author_id, name => url }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[text_id, author_id, url, text] <- [[1, 0, 'a1', ''], [2, 0, 'a2', ''], [3, 1, 'b1', '']];
+            :put Text { text_id, author_id => url, text }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+        db.run_mutable(
+            r#"
+            ?[word, text_id, count] <- [
+                ['gallia', 1, 3], ['est', 1, 1], ['gallia', 2, 3], ['quo', 3, 5]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("summary").unwrap().eval(&db).await.unwrap();
+        assert_eq!(
+            rows.headers,
+            vec![
+                "total_words",
+                "unique_words",
+                "total_texts",
+                "total_authors"
+            ]
+        );
+        assert_eq!(
+            rows.rows,
+            vec![vec![val(12i64), val(3i64), val(3i64), val(2i64)]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_word_length_distribution() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['amo', 1, 3], ['amo', 2, 4], ['amas', 1, 1], ['amat', 2, 1],
+                ['te', 1, 2], ['et', 1, 20], ['et', 2, 30]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let rows = Query::parse("lengths").unwrap().eval(&db).await.unwrap();
+        assert_eq!(
+            rows.rows,
+            vec![
+                vec![val(2i64), val(2i64), val(52.0)],
+                vec![val(3i64), val(1i64), val(7.0)],
+                vec![val(4i64), val(2i64), val(2.0)],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hapax_legomena() {
+        let db = seeded_db(
+            r#"
+            ?[word, text_id, count] <- [
+                ['vale', 1, 1], ['amo', 1, 1], ['amo', 2, 1], ['ave', 2, 1], ['te', 1, 2]
+            ];
+            :put Word { word, text_id => count }
+            "#,
+        )
+        .await;
+
+        let rows = Query::parse("hapax").unwrap().eval(&db).await.unwrap();
+        assert_eq!(rows.rows, vec![vec![val("ave")], vec![val("vale")]]);
+
+        let rows = Query::parse("hapax 1").unwrap().eval(&db).await.unwrap();
+        assert_eq!(rows.rows, vec![vec![val("ave")]]);
+    }
+
+    #[tokio::test]
+    async fn test_sample_texts_per_author() {
+        let db = seeded_db(
+            r#"
+            ?[author_id, name, url] <- [[0, 'Caesar', 'a'], [1, 'Cicero', 'b'], [2, 'Ennius', 'c']];
+            :put Author { author_id, name => url }
+            "#,
+        )
+        .await;
+        db.run_mutable(
+            r#"
+            ?[text_id, author_id, url, text] <- [
+                [1, 0, 'a1', ''], [2, 0, 'a2', ''], [3, 0, 'a3', ''],
+                [4, 1, 'b1', ''], [5, 1, 'b2', ''], [6, 1, 'b3', ''], [7, 1, 'b4', ''], [8, 1, 'b5', ''],
+                [9, 2, 'c1', '']
+            ];
+            :put Text { text_id, author_id => url, text }
+            "#,
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("sample 2 42")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        let per_author = |author_id: i64| {
+            rows.rows
+                .iter()
+                .filter(|row| row[0].get_int() == Some(author_id))
+                .count()
+        };
+        assert_eq!(per_author(0), 2);
+        assert_eq!(per_author(1), 2);
+        assert_eq!(per_author(2), 1);
+
+        // the same seed yields the same sample
+        let again = Query::parse("sample 2 42")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows, again.rows);
+
+        // also when passed as option instead of as argument
+        let seeded = Query::parse("sample 2")
+            .unwrap()
+            .with_seed(Some(42))
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows, seeded.rows);
+    }
+
+    #[tokio::test]
+    async fn test_count_custom_relation() {
+        let db = DBConnection::new_in_memory().unwrap();
+        db.run_mutable(
+            ":create Lemma { lemma: String => pos: String }",
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+        db.run_mutable(
+            "?[lemma, pos] <- [['amicus', 'noun'], ['amare', 'verb']]; :put Lemma { lemma => pos }",
+            DBParams::new(),
+        )
+        .await
+        .unwrap();
+
+        let rows = Query::parse("count Lemma")
+            .unwrap()
+            .eval(&db)
+            .await
+            .unwrap();
+        assert_eq!(rows.rows, vec![vec![val("Lemma"), val(2usize)]]);
+
+        assert!(matches!(
+            Query::parse("count Lemmata").unwrap().eval(&db).await,
+            Err(QueryError::DBError(DBError::UnknownRelation { name, known }))
+                if name == "Lemmata" && known == vec!["Lemma".to_string()]
+        ));
+    }
 }
@@ -1,38 +1,485 @@
+use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     fmt::{Display, Formatter},
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    sync::Arc,
 };
 
 use crate::{
-    db::{val, DBConnection, DBError, DBParams},
-    text::{Text, TextId, Word},
+    db::{get_str, get_usize, val, DBConnection, DBError, DBParams, DataValue, StallRetry},
+    text::{LatinTokenizer, Lemmatizer, Text, TextId, TokenMode, Tokenizer, Word, LATIN_STOPWORDS},
 };
 
+const LOG_TARGET: &str = "svl::stats";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("Storing stats failed after {committed} committed rows: {error}")]
+pub struct StoreError {
+    pub committed: usize,
+    pub error: DBError,
+}
+
+/// Number of rows written to each relation when storing stats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreSummary {
+    pub texts_written: usize,
+    pub words_written: usize,
+    pub lemmas_written: usize,
+    pub bigrams_written: usize,
+}
+
+impl StoreSummary {
+    pub fn rows_written(&self) -> usize {
+        self.texts_written + self.words_written + self.lemmas_written + self.bigrams_written
+    }
+
+    fn add(&mut self, rows: &[StoreRow]) {
+        for row in rows {
+            match row {
+                StoreRow::Text(_) => self.texts_written += 1,
+                StoreRow::Word(_) => self.words_written += 1,
+                StoreRow::Lemma(_) => self.lemmas_written += 1,
+                StoreRow::BiGram(_) => self.bigrams_written += 1,
+            }
+        }
+    }
+}
+
+/// How word counts are scaled into word cloud weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeightScale {
+    #[default]
+    Linear,
+    Log,
+}
+
+enum StoreRow {
+    Text(DataValue),
+    Word(DataValue),
+    Lemma(DataValue),
+    BiGram(DataValue),
+}
+
+/// Optional lemmatizer shared between clones of `Stats`.
+/// Lemmatizers can't be compared, so two of them are only equal if they are the same one.
+#[derive(Clone, Default)]
+struct SharedLemmatizer(Option<Arc<dyn Lemmatizer + Send + Sync>>);
+
+impl std::fmt::Debug for SharedLemmatizer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(Lemmatizer)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl PartialEq for SharedLemmatizer {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SharedLemmatizer {}
+
+/// Tokenizer shared between clones of `Stats`, `LatinTokenizer::default()` if none is set.
+/// Like lemmatizers, two tokenizers are only equal if they are the same one.
+#[derive(Clone, Default)]
+struct SharedTokenizer(Option<Arc<dyn Tokenizer + Send + Sync>>);
+
+impl std::fmt::Debug for SharedTokenizer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(Tokenizer)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl PartialEq for SharedTokenizer {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SharedTokenizer {}
+
+/// Excludes texts of specific authors or with matching URLs from the stats.
+#[derive(Debug, Clone, Default)]
+pub struct TextFilter {
+    author_ids: HashSet<usize>,
+    url_patterns: Vec<Regex>,
+}
+
+impl TextFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn exclude_author(&mut self, author_id: usize) {
+        self.author_ids.insert(author_id);
+    }
+
+    pub fn exclude_url_pattern(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        self.url_patterns.push(Regex::new(pattern)?);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.author_ids.is_empty() && self.url_patterns.is_empty()
+    }
+
+    pub fn excludes(&self, author_id: Option<usize>, url: &str) -> bool {
+        author_id.is_some_and(|id| self.author_ids.contains(&id))
+            || self.url_patterns.iter().any(|p| p.is_match(url))
+    }
+}
+
+impl PartialEq for TextFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.author_ids == other.author_ids
+            && self
+                .url_patterns
+                .iter()
+                .map(Regex::as_str)
+                .eq(other.url_patterns.iter().map(Regex::as_str))
+    }
+}
+
+impl Eq for TextFilter {}
+
+/// Index of a word interned in a `WordInterner`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WordId(usize);
+
+/// Symbol table that stores each distinct word string only once, shared
+/// between the list of words and the map looking up their ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<Word>", into = "Vec<Word>")]
+pub struct WordInterner {
+    words: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, WordId>,
+}
+
+impl WordInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, word: Word) -> WordId {
+        if let Some(id) = self.get(word.as_str()) {
+            return id;
+        }
+
+        let id = WordId(self.words.len());
+        let word: Arc<str> = Arc::from(word.as_str());
+        self.ids.insert(Arc::clone(&word), id);
+        self.words.push(word);
+        id
+    }
+
+    pub fn get(&self, word: &str) -> Option<WordId> {
+        self.ids.get(word).copied()
+    }
+
+    pub fn resolve(&self, id: WordId) -> &str {
+        &self.words[id.0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (WordId, &str)> {
+        self.words
+            .iter()
+            .enumerate()
+            .map(|(idx, word)| (WordId(idx), word.as_ref()))
+    }
+}
+
+impl From<Vec<Word>> for WordInterner {
+    fn from(words: Vec<Word>) -> Self {
+        let mut interner = Self::new();
+        for word in words {
+            interner.intern(word);
+        }
+        interner
+    }
+}
+
+impl From<WordInterner> for Vec<Word> {
+    fn from(interner: WordInterner) -> Self {
+        interner
+            .words
+            .iter()
+            .map(|word| Word::from(word.as_ref()))
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Stats {
     texts: Vec<Text>,
     word_count: usize,
-    words: HashMap<Word, WordStats>,
+    interner: WordInterner,
+    // indexed by `WordId`
+    words: Vec<WordStats>,
+    #[serde(skip)]
+    filter: TextFilter,
+    #[serde(skip)]
+    tokenizer: SharedTokenizer,
+    #[serde(default)]
+    text_id_offset: usize,
+    #[serde(skip)]
+    lemmatizer: SharedLemmatizer,
+    // lemmas of all words added while a lemmatizer was set
+    #[serde(default)]
+    lemmas: HashMap<WordId, Word>,
+    #[serde(skip)]
+    track_bigrams: bool,
+    // counts of pairs of consecutive words, only tracked if enabled
+    #[serde(default, with = "bigram_entries")]
+    bigrams: HashMap<(WordId, WordId), WordStats>,
+    #[serde(skip)]
+    stopwords: BTreeSet<String>,
+}
+
+/// (De)serializes bigrams as a list of entries, since JSON only allows string keys.
+mod bigram_entries {
+    use super::{WordId, WordStats};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    type Bigrams = HashMap<(WordId, WordId), WordStats>;
+
+    pub fn serialize<S: Serializer>(bigrams: &Bigrams, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(bigrams.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bigrams, D::Error> {
+        let entries = Vec::<((WordId, WordId), WordStats)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
 }
 
 impl Stats {
+    /// Rows inserted by a single `:put` script when storing stats.
+    const ROWS_PER_SCRIPT: usize = 1000;
+
+    /// Used unless another tokenizer is set.
+    const DEFAULT_TOKENIZER: LatinTokenizer = LatinTokenizer::new(TokenMode::Alphabetic);
+
     pub fn new() -> Self {
         Stats {
             texts: Vec::new(),
             word_count: 0,
-            words: HashMap::new(),
+            interner: WordInterner::new(),
+            words: Vec::new(),
+            filter: TextFilter::new(),
+            tokenizer: SharedTokenizer::default(),
+            text_id_offset: 0,
+            lemmatizer: SharedLemmatizer::default(),
+            lemmas: HashMap::new(),
+            track_bigrams: false,
+            bigrams: HashMap::new(),
+            stopwords: BTreeSet::new(),
+        }
+    }
+
+    pub fn new_with_stopwords(stopwords: BTreeSet<String>) -> Self {
+        Stats {
+            stopwords,
+            ..Self::new()
         }
     }
 
+    /// Texts added afterwards get ids starting at `offset + 1`, e.g. to
+    /// continue after the texts already stored in the DB.
+    pub fn set_text_id_offset(&mut self, offset: usize) {
+        self.text_id_offset = offset;
+    }
+
+    /// Uses a `LatinTokenizer` with the given mode and otherwise default settings.
+    pub fn set_token_mode(&mut self, token_mode: TokenMode) {
+        self.set_tokenizer(LatinTokenizer::new(token_mode));
+    }
+
+    /// Texts added afterwards are split into words by `tokenizer`.
+    pub fn set_tokenizer<T: Tokenizer + Send + Sync + 'static>(&mut self, tokenizer: T) {
+        self.tokenizer = SharedTokenizer(Some(Arc::new(tokenizer)));
+    }
+
+    /// Words added afterwards also get their lemma stored, in addition to their surface form.
+    pub fn set_lemmatizer<L: Lemmatizer + Send + Sync + 'static>(&mut self, lemmatizer: L) {
+        self.lemmatizer = SharedLemmatizer(Some(Arc::new(lemmatizer)));
+    }
+
+    pub fn lemma(&self, word: &str) -> Option<&Word> {
+        let id = self.interner.get(word)?;
+        self.lemmas.get(&id)
+    }
+
+    /// Texts added afterwards also get the counts of their bigrams (pairs of consecutive words) tracked.
+    pub fn set_track_bigrams(&mut self, track_bigrams: bool) {
+        self.track_bigrams = track_bigrams;
+    }
+
+    /// Words added afterwards are skipped if they're one of the (lowercase) stopwords,
+    /// so they're neither counted nor stored.
+    pub fn set_stopwords(&mut self, stopwords: BTreeSet<String>) {
+        self.stopwords = stopwords;
+    }
+
+    pub fn bigram_stats(&self, first: &str, second: &str) -> Option<&WordStats> {
+        let first = self.interner.get(first)?;
+        let second = self.interner.get(second)?;
+        self.bigrams.get(&(first, second))
+    }
+
+    pub fn with_filter(filter: TextFilter) -> Self {
+        Stats {
+            filter,
+            ..Self::new()
+        }
+    }
+
+    pub fn text_count(&self) -> usize {
+        self.texts.len()
+    }
+
     pub fn unique_word_count(&self) -> usize {
-        self.words.len()
+        self.interner.len()
+    }
+
+    /// Unique words per word (0.0 without any words). Shrinks as texts get
+    /// longer, so only compare it between texts of similar length.
+    pub fn type_token_ratio(&self) -> f64 {
+        if self.word_count == 0 {
+            return 0.0;
+        }
+        self.unique_word_count() as f64 / self.word_count as f64
+    }
+
+    /// Guiraud's root TTR: unique words over the square root of all words,
+    /// which depends less on the text length than the plain type-token ratio.
+    pub fn root_type_token_ratio(&self) -> f64 {
+        if self.word_count == 0 {
+            return 0.0;
+        }
+        self.unique_word_count() as f64 / (self.word_count as f64).sqrt()
+    }
+
+    /// Herdan's C: log of unique words over log of all words.
+    pub fn herdans_c(&self) -> f64 {
+        // ln(1) = 0, so a single word has no meaningful ratio either
+        if self.word_count <= 1 {
+            return 0.0;
+        }
+        (self.unique_word_count() as f64).ln() / (self.word_count as f64).ln()
+    }
+
+    pub fn word_stats(&self, word: &str) -> Option<&WordStats> {
+        let id = self.interner.get(word)?;
+        self.words.get(id.0)
+    }
+
+    /// Every distinct word with its stats, in the order the words were first added.
+    pub fn iter_words(&self) -> impl Iterator<Item = (&str, &WordStats)> {
+        self.interner
+            .iter()
+            .map(|(id, word)| (word, &self.words[id.0]))
+    }
+
+    /// Returns the `top_n` most frequent non-stopwords with their global counts
+    /// scaled to weights between 0.0 and 1.0, the most frequent word mapping to 1.0.
+    pub fn word_cloud_data(&self, top_n: usize, scale: WeightScale) -> Vec<(Word, f64)> {
+        let mut words: Vec<(&str, usize)> = self
+            .iter_words()
+            .filter(|(word, _)| !LATIN_STOPWORDS.contains(word))
+            .map(|(word, stats)| (word, stats.global_count()))
+            .collect();
+
+        words.sort_by(|(w1, c1), (w2, c2)| c2.cmp(c1).then_with(|| w1.cmp(w2)));
+        words.truncate(top_n);
+
+        let scaled = |count: usize| match scale {
+            WeightScale::Linear => count as f64,
+            WeightScale::Log => (count as f64).ln_1p(),
+        };
+
+        let max = words
+            .first()
+            .map(|(_, count)| scaled(*count))
+            .unwrap_or(0.0);
+        if max == 0.0 {
+            return Vec::new();
+        }
+
+        words
+            .into_iter()
+            .map(|(word, count)| (Word::from(word), scaled(count) / max))
+            .collect()
+    }
+
+    /// TF-IDF score of every word in every text, highest scores first: the word's
+    /// share of the text's words times the log of the number of texts over the
+    /// number of texts containing the word.
+    pub fn tf_idf(&self) -> Vec<(&str, TextId, f64)> {
+        let mut text_totals: HashMap<TextId, usize> = HashMap::new();
+        for word_stats in &self.words {
+            for (text_id, count) in &word_stats.count {
+                *text_totals.entry(*text_id).or_default() += count;
+            }
+        }
+        let text_totals = &text_totals;
+        let text_count = self.texts.len() as f64;
+
+        let mut scores: Vec<(&str, TextId, f64)> = self
+            .iter_words()
+            .flat_map(|(word, word_stats)| {
+                let idf = (text_count / word_stats.text_ids.len() as f64).ln();
+                word_stats.count.iter().map(move |(text_id, count)| {
+                    let tf = *count as f64 / text_totals[text_id] as f64;
+                    (word, *text_id, tf * idf)
+                })
+            })
+            .collect();
+
+        scores.sort_by(|(w1, t1, s1), (w2, t2, s2)| {
+            s2.total_cmp(s1)
+                .then_with(|| w1.cmp(w2))
+                .then_with(|| t1.cmp(t2))
+        });
+        scores
     }
 
     pub fn add_text(&mut self, text: Text) {
-        let id = TextId::from(self.texts.len() + 1);
-        let words: Vec<Word> = text.words().collect();
+        if self.filter.excludes(text.author_id, &text.url) {
+            log::info!(target: LOG_TARGET, "Skipping excluded Text: {}", text.url);
+            return;
+        }
+
+        let id = TextId::from(self.text_id_offset + self.texts.len() + 1);
+        let words: Vec<Word> = text.tokenize(self.tokenizer()).collect();
         log::info!(
+            target: LOG_TARGET,
             "Processing Text {} ({} words): {}",
             id,
             words.len(),
@@ -41,21 +488,120 @@ impl Stats {
         let mut text = text;
         text.set_id(id);
         self.texts.push(text.clone());
-        for word in words {
-            self.add_word(id, word);
+        for word in &words {
+            self.count_word(id, word);
+        }
+        if !self.track_bigrams {
+            return;
+        }
+        // pairs with a skipped word aren't counted
+        for bigram in words.windows(2) {
+            if let (Some(first), Some(second)) =
+                (self.counted_id(&bigram[0]), self.counted_id(&bigram[1]))
+            {
+                self.bigrams
+                    .entry((first, second))
+                    .or_insert_with(|| WordStats::new(id))
+                    .count_text(id);
+            }
+        }
+    }
+
+    fn tokenizer(&self) -> &dyn Tokenizer {
+        match &self.tokenizer.0 {
+            Some(tokenizer) => tokenizer.as_ref(),
+            None => &Self::DEFAULT_TOKENIZER,
+        }
+    }
+
+    // the id of a word that has been counted, i.e. that isn't empty or a stopword
+    fn counted_id(&self, word: &Word) -> Option<WordId> {
+        if self.stopwords.contains(word.as_str()) {
+            return None;
         }
+        self.interner.get(word.as_str())
     }
 
     pub fn add_word(&mut self, text_id: TextId, word: Word) {
-        if word.is_empty() {
+        self.count_word(text_id, &word);
+    }
+
+    fn count_word(&mut self, text_id: TextId, word: &Word) {
+        if word.is_empty() || self.stopwords.contains(word.as_str()) {
             return;
         }
         self.word_count += 1;
-        let word_stats = self
-            .words
-            .entry(word.clone())
-            .or_insert_with(|| WordStats::new(text_id, word));
-        word_stats.count_text(text_id);
+        // only new words are copied into the interner
+        let id = match self.interner.get(word.as_str()) {
+            Some(id) => id,
+            None => self.interner.intern(word.clone()),
+        };
+        if id.0 == self.words.len() {
+            self.words.push(WordStats::new(text_id));
+            if let Some(lemmatizer) = &self.lemmatizer.0 {
+                let lemma = lemmatizer.lemmatize(&Word::from(self.interner.resolve(id)));
+                self.lemmas.insert(id, lemma);
+            }
+        }
+        self.words[id.0].count_text(text_id);
+    }
+
+    /// Rebuilds the stats of all texts and words stored in the DB, the inverse of `store_in_db`.
+    /// Lemmas and bigrams aren't restored.
+    pub async fn from_db(db: &DBConnection) -> crate::Result<Self> {
+        let mut stats = Self::new();
+
+        let texts = db
+            .run_immutable(
+                "?[text_id, url, author_id, text] := *Text{text_id, url, author_id, text}",
+                DBParams::new(),
+            )
+            .await?;
+        let mut max_text_id = 0;
+        for row in &texts.rows {
+            let text_id = get_usize(row, 0)?;
+            max_text_id = max_text_id.max(text_id);
+            let mut text = Text::new(get_str(row, 1)?, get_str(row, 3)?);
+            text.set_id(TextId::from(text_id));
+            text.author_id = Some(get_usize(row, 2)?);
+            stats.texts.push(text);
+        }
+        // texts added afterwards must not reuse the ids of stored ones
+        stats.set_text_id_offset(max_text_id.saturating_sub(stats.texts.len()));
+
+        let words = db
+            .run_immutable(
+                "?[word, text_id, count] := *Word{word, text_id, count}",
+                DBParams::new(),
+            )
+            .await?;
+        for row in &words.rows {
+            let text_id = TextId::from(get_usize(row, 1)?);
+            let count = get_usize(row, 2)?;
+            let id = stats.interner.intern(Word::from(get_str(row, 0)?));
+            if id.0 == stats.words.len() {
+                stats.words.push(WordStats::new(text_id));
+            }
+            stats.words[id.0].add_count(text_id, count);
+            stats.word_count += count;
+        }
+
+        Ok(stats)
+    }
+
+    /// Saves the stats as JSON, e.g. to reload them later instead of importing again.
+    /// Settings like the filter and tokenizer aren't saved.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads stats saved with `save_to_file`.
+    pub fn load_from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
     }
 
     pub fn merge(&mut self, other: &Self) {
@@ -64,52 +610,237 @@ impl Stats {
         }
     }
 
-    pub async fn store_in_db(&self, db: &DBConnection) -> Result<(), DBError> {
-        log::info!("Storing Stats in DB");
-        let tx = db.multi_tx(true);
+    pub async fn store_in_db(&self, db: &DBConnection) -> Result<StoreSummary, DBError> {
+        self.store_in_db_batched(db, None)
+            .await
+            .map_err(|e| e.error)
+    }
+
+    /// Stores all texts and word counts, committing a transaction after every
+    /// `batch_size` rows (or a single transaction for everything if `None`).
+    /// A text is always committed together with its counts, so batches can be a
+    /// bit larger than `batch_size`.
+    /// Returns the number of committed rows per relation. On failure, rows of
+    /// earlier batches stay committed and their number is reported in the error.
+    ///
+    /// Counts are the number of occurrences of a word within a whole text, so they
+    /// replace the stored counts of each text instead of adding to them: storing a
+    /// text again is idempotent, and words no longer in a changed text are removed.
+    /// Old counts are removed in the same transaction that stores the new ones.
+    pub async fn store_in_db_batched(
+        &self,
+        db: &DBConnection,
+        batch_size: Option<usize>,
+    ) -> Result<StoreSummary, StoreError> {
+        log::info!(target: LOG_TARGET, "Storing Stats in DB");
+        let rows = self.store_rows();
+        let batch_size = batch_size.unwrap_or(rows.len()).max(1);
+        let mut committed = 0;
+        let mut summary = StoreSummary::default();
+
+        let retry = StallRetry::default();
+        let existing = retry
+            .run(|| db.relation_names())
+            .await
+            .map_err(|error| StoreError { committed, error })?;
 
-        for text in &self.texts {
+        for batch in Self::store_batches(&rows, batch_size) {
+            // each batch is its own transaction, so a stalled batch can safely be retried
+            retry
+                .run(|| Self::store_batch(db, batch, &existing))
+                .await
+                .map_err(|error| StoreError { committed, error })?;
+            committed += batch.len();
+            summary.add(batch);
+            log::info!(target: LOG_TARGET, "Committed {} of {} rows", committed, rows.len());
+        }
+
+        Ok(summary)
+    }
+
+    // each text's row directly followed by its word and bigram rows, then all lemmas
+    fn store_rows(&self) -> Vec<StoreRow> {
+        let mut counts: HashMap<TextId, Vec<StoreRow>> = HashMap::new();
+
+        for (word, word_stats) in self.iter_words() {
+            for text_id in &word_stats.text_ids {
+                counts
+                    .entry(*text_id)
+                    .or_default()
+                    .push(StoreRow::Word(val(vec![
+                        val(word),
+                        val(word_stats.count(text_id)),
+                        val(text_id),
+                    ])));
+            }
+        }
+
+        for ((first, second), stats) in &self.bigrams {
+            for text_id in &stats.text_ids {
+                counts
+                    .entry(*text_id)
+                    .or_default()
+                    .push(StoreRow::BiGram(val(vec![
+                        val(self.interner.resolve(*first)),
+                        val(self.interner.resolve(*second)),
+                        val(stats.count(text_id)),
+                        val(text_id),
+                    ])));
+            }
+        }
+
+        let texts = self.texts.iter().flat_map(|text| {
             let text_id = text.id.expect("Text should have an id");
             let author_id = text.author_id.expect("Text should have an author id");
-            let text_url = text.url.clone();
+            let text_row = StoreRow::Text(val(vec![
+                val(text_id),
+                val(text.url.clone()),
+                val(author_id),
+                val(text.text.clone()),
+            ]));
+            std::iter::once(text_row).chain(counts.remove(&text_id).unwrap_or_default())
+        });
+
+        let lemmas = self.lemmas.iter().map(|(id, lemma)| {
+            StoreRow::Lemma(val(vec![val(self.interner.resolve(*id)), val(lemma)]))
+        });
+
+        texts.chain(lemmas).collect()
+    }
+
+    // splits rows into batches of about `batch_size` rows, never separating a text
+    // from its counts, so a batch that fails leaves no text stored without them
+    fn store_batches(rows: &[StoreRow], batch_size: usize) -> Vec<&[StoreRow]> {
+        let mut batches = Vec::new();
+        let mut start = 0;
+
+        for (i, row) in rows.iter().enumerate() {
+            let starts_text = matches!(row, StoreRow::Text(_) | StoreRow::Lemma(_));
+            if starts_text && i - start >= batch_size {
+                batches.push(&rows[start..i]);
+                start = i;
+            }
+        }
+        if start < rows.len() {
+            batches.push(&rows[start..]);
+        }
+
+        batches
+    }
 
-            tx.run_script(
+    // scripts removing the word and bigram counts stored for the texts, e.g. by an earlier
+    // import, for those of the relations that exist
+    fn remove_counts_scripts(
+        text_ids: Vec<DataValue>,
+        existing: &[String],
+    ) -> Vec<(&'static str, DBParams)> {
+        if text_ids.is_empty() {
+            return Vec::new();
+        }
+
+        let text_ids = val(text_ids);
+        [
+            (
+                "Word",
                 "
-                ?[text_id, url, author_id, text] <- [$props];
-                :put Text { text_id, author_id => url, text }
+                text_ids[text_id] <- $text_ids
+                ?[word, text_id] := text_ids[text_id], *Word{word, text_id}
+                :rm Word { word, text_id }
                 ",
-                DBParams::from_iter(vec![(
-                    "props".into(),
-                    val(vec![
-                        val(text_id),
-                        val(text_url),
-                        val(author_id),
-                        val(text.text.clone()),
-                    ]),
-                )]),
-            )?;
-        }
+            ),
+            (
+                "BiGram",
+                "
+                text_ids[text_id] <- $text_ids
+                ?[first, second, text_id] := text_ids[text_id], *BiGram{first, second, text_id}
+                :rm BiGram { first, second, text_id }
+                ",
+            ),
+        ]
+        .into_iter()
+        .filter(|(relation, _)| existing.iter().any(|name| name == relation))
+        .map(|(_, script)| {
+            let params = DBParams::from_iter(vec![("text_ids".into(), text_ids.clone())]);
+            (script, params)
+        })
+        .collect()
+    }
 
-        for (word, word_stats) in &self.words {
-            for text_id in &word_stats.text_ids {
-                tx.run_script(
-                    "
-                    ?[word, count, text_id] <- [$props];
-                    :put Word { word, text_id => count }
-                    ",
-                    DBParams::from_iter(vec![(
-                        "props".into(),
-                        val(vec![
-                            val(word),
-                            val(word_stats.count(text_id)),
-                            val(text_id),
-                        ]),
-                    )]),
-                )?;
+    // stores the rows in one transaction, replacing the stored counts of the batch's texts
+    async fn store_batch(
+        db: &DBConnection,
+        batch: &[StoreRow],
+        existing: &[String],
+    ) -> Result<(), DBError> {
+        let mut text_ids = Vec::new();
+        let mut texts = Vec::new();
+        let mut words = Vec::new();
+        let mut lemmas = Vec::new();
+        let mut bigrams = Vec::new();
+
+        for row in batch {
+            match row {
+                StoreRow::Text(text) => {
+                    // the text id is the first column of a Text row
+                    if let DataValue::List(columns) = text {
+                        text_ids.extend(columns.first().map(|id| val(vec![id.clone()])));
+                    }
+                    texts.push(text);
+                }
+                StoreRow::Word(word) => words.push(word),
+                StoreRow::Lemma(lemma) => lemmas.push(lemma),
+                StoreRow::BiGram(bigram) => bigrams.push(bigram),
             }
         }
-        tx.commit().await?;
-        Ok(())
+
+        let relations: [(&str, Vec<&DataValue>); 4] = [
+            (
+                "
+                ?[text_id, url, author_id, text] <- $rows;
+                :put Text { text_id, author_id => url, text }
+                ",
+                texts,
+            ),
+            (
+                "
+                ?[word, count, text_id] <- $rows;
+                :put Word { word, text_id => count }
+                ",
+                words,
+            ),
+            (
+                "
+                ?[word, lemma] <- $rows;
+                :put Lemma { word => lemma }
+                ",
+                lemmas,
+            ),
+            (
+                "
+                ?[first, second, count, text_id] <- $rows;
+                :put BiGram { first, second, text_id => count }
+                ",
+                bigrams,
+            ),
+        ];
+
+        // a text's counts are all in its batch, so its old counts can be removed right here
+        // each chunk's rows are only copied into its params right before it's run
+        let chunks = relations.iter().flat_map(|(script, rows)| {
+            rows.chunks(Self::ROWS_PER_SCRIPT).map(|chunk| {
+                let rows: Vec<DataValue> = chunk.iter().map(|row| (*row).clone()).collect();
+                let params = DBParams::from_iter(vec![("rows".into(), val(rows))]);
+                (*script, params)
+            })
+        });
+        let scripts = Self::remove_counts_scripts(text_ids, existing)
+            .into_iter()
+            .chain(chunks);
+
+        // all scripts share the transaction, so a batch is stored atomically
+        let tx = db.multi_tx(true);
+        tx.run_scripts(scripts)?;
+        tx.commit().await
     }
 }
 
@@ -123,10 +854,17 @@ impl Display for Stats {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Total words: {}", self.word_count)?;
         writeln!(f, "Unique words: {}", self.unique_word_count())?;
+        writeln!(f, "Type-token ratio: {:.4}", self.type_token_ratio())?;
+        writeln!(
+            f,
+            "Root type-token ratio: {:.4}",
+            self.root_type_token_ratio()
+        )?;
+        writeln!(f, "Herdan's C: {:.4}", self.herdans_c())?;
         writeln!(f, "Texts: {}", self.texts.len())?;
         if std::env::var("SHOW_WORDS").is_ok() {
             writeln!(f, "Words:")?;
-            for (word, stats) in &self.words {
+            for (word, stats) in self.iter_words() {
                 writeln!(f, "\t{} : {}", word, stats.global_count())?;
             }
         }
@@ -137,15 +875,13 @@ impl Display for Stats {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WordStats {
     text_ids: HashSet<TextId>,
-    word: Word,
     count: HashMap<TextId, usize>,
 }
 
 impl WordStats {
-    pub fn new(text_id: TextId, word: Word) -> Self {
+    pub fn new(text_id: TextId) -> Self {
         Self {
             text_ids: HashSet::from_iter(vec![text_id]),
-            word,
             count: HashMap::new(),
         }
     }
@@ -164,14 +900,22 @@ impl WordStats {
     }
 
     pub fn incr_count(&mut self, text_id: TextId) {
-        let count = self.count.entry(text_id).or_insert(0);
-        *count += 1;
+        self.add_count(text_id, 1);
+    }
+
+    pub fn add_count(&mut self, text_id: TextId, count: usize) {
+        self.text_ids.insert(text_id);
+        *self.count.entry(text_id).or_insert(0) += count;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        db::{empty_db, TEXT_SCHEMA, WORD_SCHEMA},
+        text::EndingLemmatizer,
+    };
 
     #[test]
     fn add_text() {
@@ -185,18 +929,15 @@ mod tests {
         assert_eq!(stats.texts.len(), 1);
         assert_eq!(stats.word_count, 10);
         assert_eq!(stats.unique_word_count(), 9);
-        assert_eq!(stats.words.get(&"salvē".into()).unwrap().global_count(), 1);
-        assert_eq!(stats.words.get(&"amīcē".into()).unwrap().global_count(), 1);
-        assert_eq!(
-            stats.words.get(&"quōmodo".into()).unwrap().global_count(),
-            1
-        );
-        assert_eq!(stats.words.get(&"tē".into()).unwrap().global_count(), 2);
-        assert_eq!(stats.words.get(&"hodiē".into()).unwrap().global_count(), 1);
-        assert_eq!(stats.words.get(&"habēs".into()).unwrap().global_count(), 1);
-        assert_eq!(stats.words.get(&"nunc".into()).unwrap().global_count(), 1);
-        assert_eq!(stats.words.get(&"vidēre".into()).unwrap().global_count(), 1);
-        assert_eq!(stats.words.get(&"possum".into()).unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("salvē").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("amīcē").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("quōmodo").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("tē").unwrap().global_count(), 2);
+        assert_eq!(stats.word_stats("hodiē").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("habēs").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("nunc").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("vidēre").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("possum").unwrap().global_count(), 1);
 
         let text = Text::new(
             "URL".into(),
@@ -207,7 +948,448 @@ mod tests {
         assert_eq!(stats.texts.len(), 2);
         assert_eq!(stats.word_count, 16);
         assert_eq!(stats.unique_word_count(), 13);
-        assert_eq!(stats.words.get(&"quid".into()).unwrap().global_count(), 1);
-        assert_eq!(stats.words.get(&"possum".into()).unwrap().global_count(), 2);
+        assert_eq!(stats.word_stats("quid").unwrap().global_count(), 1);
+        assert_eq!(stats.word_stats("possum").unwrap().global_count(), 2);
+    }
+
+    #[test]
+    fn add_text_with_custom_tokenizer() {
+        // keeps words as they are, only splitting on whitespace
+        struct WhitespaceTokenizer;
+
+        impl Tokenizer for WhitespaceTokenizer {
+            fn is_separator(&self, c: char) -> bool {
+                c.is_whitespace()
+            }
+
+            fn word(&self, token: &str) -> Option<Word> {
+                (!token.is_empty()).then(|| Word::from(token))
+            }
+        }
+
+        let mut stats = Stats::new();
+        stats.set_tokenizer(WhitespaceTokenizer);
+        stats.add_text(Text::new("URL".into(), "Tē amō, tē".into()));
+
+        assert_eq!(stats.unique_word_count(), 3);
+        assert!(stats.word_stats("Tē").is_some());
+        assert!(stats.word_stats("amō,").is_some());
+    }
+
+    #[test]
+    fn add_text_skips_stopwords() {
+        let stopwords = BTreeSet::from_iter(vec!["et".to_string(), "in".to_string()]);
+        let mut stats = Stats::new_with_stopwords(stopwords);
+        stats.set_track_bigrams(true);
+        stats.add_text(Text::new(
+            "URL".into(),
+            "Et in Arcadia ego, et in Italia.".into(),
+        ));
+
+        assert_eq!(stats.word_count, 3);
+        assert_eq!(stats.unique_word_count(), 3);
+        assert!(stats.word_stats("et").is_none());
+        assert!(stats.word_stats("in").is_none());
+        assert_eq!(stats.word_stats("arcadia").unwrap().global_count(), 1);
+        // words separated by a stopword aren't a bigram
+        assert!(stats.bigram_stats("arcadia", "ego").is_some());
+        assert!(stats.bigram_stats("ego", "italia").is_none());
+    }
+
+    #[test]
+    fn add_text_skips_excluded_texts() {
+        let mut filter = TextFilter::new();
+        filter.exclude_author(1);
+        filter.exclude_url_pattern(r"/spurious/").unwrap();
+        let mut stats = Stats::with_filter(filter);
+
+        let mut text = Text::new(
+            "https://example.com/caesar.html".into(),
+            "Gallia est".into(),
+        );
+        text.author_id = Some(0);
+        stats.add_text(text);
+
+        let mut text = Text::new("https://example.com/cicero.html".into(), "Quo usque".into());
+        text.author_id = Some(1);
+        stats.add_text(text);
+
+        let mut text = Text::new(
+            "https://example.com/spurious/x.html".into(),
+            "Gallia".into(),
+        );
+        text.author_id = Some(0);
+        stats.add_text(text);
+
+        assert_eq!(stats.texts.len(), 1);
+        assert_eq!(stats.word_count, 2);
+        assert_eq!(stats.word_stats("gallia").unwrap().global_count(), 1);
+        assert!(stats.word_stats("usque").is_none());
+    }
+
+    #[test]
+    fn word_cloud_data() {
+        let mut stats = Stats::new();
+        stats.add_text(Text::new(
+            "URL".into(),
+            "Et amīcus et amīcus et amīcus amat, et poēta amat.".into(),
+        ));
+
+        let linear = stats.word_cloud_data(10, WeightScale::Linear);
+        assert_eq!(
+            linear,
+            vec![
+                (Word::from("amīcus"), 1.0),
+                (Word::from("amat"), 2.0 / 3.0),
+                (Word::from("poēta"), 1.0 / 3.0),
+            ]
+        );
+
+        let log = stats.word_cloud_data(2, WeightScale::Log);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0], (Word::from("amīcus"), 1.0));
+        assert!(log[1].1 > 0.0 && log[1].1 < 1.0);
+    }
+
+    #[tokio::test]
+    async fn lemmas_are_stored_along_with_words() {
+        let db = empty_db().await;
+
+        let mut stats = Stats::new();
+        stats.set_lemmatizer(EndingLemmatizer);
+        let mut text = Text::new("URL".into(), "Amīcus amīcō amīcum dat.".into());
+        text.author_id = Some(0);
+        stats.add_text(text);
+
+        assert_eq!(stats.unique_word_count(), 4);
+        assert_eq!(stats.lemma("amīcō"), Some(&Word::from("amīc")));
+
+        stats.store_in_db(&db).await.unwrap();
+        assert_eq!(db.count("Word").await, Ok(4));
+        assert_eq!(db.count("Lemma").await, Ok(4));
+
+        let rows = db
+            .run_immutable(
+                "?[count(word)] := *Lemma{word, lemma}, lemma = 'amīc'",
+                DBParams::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(rows.rows, vec![vec![val(3i64)]]);
+    }
+
+    #[test]
+    fn vocabulary_richness() {
+        let mut stats = Stats::new();
+        assert_eq!(stats.type_token_ratio(), 0.0);
+        assert_eq!(stats.root_type_token_ratio(), 0.0);
+        assert_eq!(stats.herdans_c(), 0.0);
+
+        stats.add_text(Text::new("URL".into(), "Amo te, te amo.".into()));
+        assert_eq!(stats.type_token_ratio(), 0.5);
+        assert_eq!(stats.root_type_token_ratio(), 1.0);
+        assert!((stats.herdans_c() - 0.5).abs() < 1e-12);
+        assert!(stats.to_string().contains("Type-token ratio: 0.5000"));
+    }
+
+    #[tokio::test]
+    async fn from_db_restores_stored_stats() {
+        let db = empty_db().await;
+
+        let mut stats = Stats::new();
+        for (author_id, txt) in [(0, "Amo te, te amo."), (1, "Amo amas amat.")] {
+            let mut text = Text::new(format!("URL{}", author_id), txt.into());
+            text.author_id = Some(author_id);
+            stats.add_text(text);
+        }
+        stats.store_in_db(&db).await.unwrap();
+
+        let restored = Stats::from_db(&db).await.unwrap();
+        assert_eq!(restored.texts, stats.texts);
+        assert_eq!(restored.word_count, stats.word_count);
+        assert_eq!(restored.unique_word_count(), stats.unique_word_count());
+        for (word, word_stats) in stats.iter_words() {
+            assert_eq!(restored.word_stats(word), Some(word_stats));
+        }
+
+        // new texts continue after the restored ones
+        let mut restored = restored;
+        restored.add_text(Text::new("URL2".into(), "Ave".into()));
+        assert_eq!(restored.texts[2].id, Some(TextId::from(3usize)));
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let mut stats = Stats::new();
+        stats.set_track_bigrams(true);
+        stats.set_lemmatizer(EndingLemmatizer);
+        let mut text = Text::new("URL".into(), "Amīcus amīcum amat.".into());
+        text.author_id = Some(0);
+        stats.add_text(text);
+
+        let path = std::env::temp_dir().join(format!("svl-stats-{}.json", std::process::id()));
+        stats.save_to_file(&path).unwrap();
+        let loaded = Stats::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.texts, stats.texts);
+        assert_eq!(loaded.word_count, stats.word_count);
+        assert_eq!(loaded.interner, stats.interner);
+        assert_eq!(loaded.words, stats.words);
+        assert_eq!(loaded.lemmas, stats.lemmas);
+        assert_eq!(loaded.bigrams, stats.bigrams);
+
+        assert!(matches!(
+            Stats::load_from_file(&path),
+            Err(crate::errors::SVLError::IOError(_))
+        ));
+    }
+
+    #[test]
+    fn tf_idf() {
+        let mut stats = Stats::new();
+        stats.add_text(Text::new("URL".into(), "Amo te, te amo.".into()));
+        stats.add_text(Text::new("URL".into(), "Amo amas amat.".into()));
+
+        let score = |word: &str, text_id: usize| {
+            stats
+                .tf_idf()
+                .into_iter()
+                .find(|(w, t, _)| *w == word && *t == TextId::from(text_id))
+                .map(|(_, _, score)| score)
+        };
+
+        // words in all texts aren't distinctive at all
+        assert_eq!(score("amo", 1), Some(0.0));
+        assert_eq!(score("amo", 2), Some(0.0));
+        assert_eq!(score("te", 1), Some(0.5 * 2f64.ln()));
+        assert_eq!(score("amas", 2), Some(1.0 / 3.0 * 2f64.ln()));
+        assert_eq!(score("te", 2), None);
+
+        let scores = stats.tf_idf();
+        assert_eq!(scores[0].0, "te");
+        assert!(scores.windows(2).all(|w| w[0].2 >= w[1].2));
+
+        // ties are ordered by text id numerically, 2 before 10
+        let mut stats = Stats::new();
+        for id in 1..=11 {
+            let txt = if id == 2 || id == 10 {
+                "unicus"
+            } else {
+                "alius"
+            };
+            stats.add_text(Text::new("URL".into(), txt.into()));
+        }
+        let ids: Vec<TextId> = stats
+            .tf_idf()
+            .into_iter()
+            .filter(|(w, _, _)| *w == "unicus")
+            .map(|(_, t, _)| t)
+            .collect();
+        assert_eq!(ids, vec![TextId::from(2usize), TextId::from(10usize)]);
+    }
+
+    #[tokio::test]
+    async fn bigrams_are_tracked_and_stored() {
+        let db = empty_db().await;
+
+        let mut stats = Stats::new();
+        stats.set_track_bigrams(true);
+        for txt in ["Senatus populusque Romanus. Senatus populusque", "Ave"] {
+            let mut text = Text::new("URL".into(), txt.into());
+            text.author_id = Some(0);
+            stats.add_text(text);
+        }
+
+        let senatus = stats.bigram_stats("senatus", "populusque").unwrap();
+        assert_eq!(senatus.global_count(), 2);
+        assert_eq!(
+            stats
+                .bigram_stats("romanus", "senatus")
+                .unwrap()
+                .global_count(),
+            1
+        );
+        // bigrams don't span texts
+        assert!(stats.bigram_stats("populusque", "ave").is_none());
+
+        let summary = stats.store_in_db(&db).await.unwrap();
+        assert_eq!(summary.bigrams_written, 3);
+        assert_eq!(db.count("BiGram").await, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn store_in_db_chunks_large_batches() {
+        let db = empty_db().await;
+
+        // distinct alphabetic words "a", "b", ..., "ba", "bb", ...
+        let word = |mut n: usize| {
+            let mut word = String::new();
+            loop {
+                word.insert(0, (b'a' + (n % 26) as u8) as char);
+                n /= 26;
+                if n == 0 {
+                    return word;
+                }
+            }
+        };
+        let word_count = Stats::ROWS_PER_SCRIPT * 2 + 500;
+        let txt: Vec<String> = (0..word_count).map(word).collect();
+
+        let mut stats = Stats::new();
+        let mut text = Text::new("URL".into(), txt.join(" "));
+        text.author_id = Some(0);
+        stats.add_text(text);
+        assert_eq!(stats.unique_word_count(), word_count);
+
+        let summary = stats.store_in_db(&db).await.unwrap();
+        assert_eq!(
+            summary,
+            StoreSummary {
+                texts_written: 1,
+                words_written: word_count,
+                ..StoreSummary::default()
+            }
+        );
+        assert_eq!(summary.rows_written(), word_count + 1);
+        assert_eq!(db.count("Text").await, Ok(1));
+        assert_eq!(db.count("Word").await, Ok(word_count));
+    }
+
+    #[tokio::test]
+    async fn storing_texts_again_replaces_their_counts() {
+        let db = empty_db().await;
+
+        let stats_of = |txt: &str| {
+            let mut stats = Stats::new();
+            stats.set_track_bigrams(true);
+            let mut text = Text::new("URL".into(), txt.into());
+            text.author_id = Some(0);
+            stats.add_text(text);
+            stats
+        };
+        let counts = || async {
+            db.run_immutable(
+                "?[word, count] := *Word{word, text_id: 1, count}",
+                DBParams::new(),
+            )
+            .await
+            .unwrap()
+            .rows
+        };
+
+        let stats = stats_of("amo te amo");
+        stats.store_in_db(&db).await.unwrap();
+        stats.store_in_db(&db).await.unwrap();
+        assert_eq!(
+            counts().await,
+            vec![vec![val("amo"), val(2i64)], vec![val("te"), val(1i64)]]
+        );
+        assert_eq!(db.count("BiGram").await, Ok(2));
+
+        // a changed text replaces all of its counts, including words no longer in it
+        stats_of("amo amo amo").store_in_db(&db).await.unwrap();
+        assert_eq!(counts().await, vec![vec![val("amo"), val(3i64)]]);
+        assert_eq!(db.count("BiGram").await, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn failed_stores_keep_the_old_counts() {
+        let db = DBConnection::new_in_memory().unwrap();
+        db.run_mutable(TEXT_SCHEMA, DBParams::new()).await.unwrap();
+        db.run_mutable(WORD_SCHEMA, DBParams::new()).await.unwrap();
+
+        let stats_of = |txt: &str, lemmatize: bool| {
+            let mut stats = Stats::new();
+            if lemmatize {
+                stats.set_lemmatizer(EndingLemmatizer);
+            }
+            let mut text = Text::new("URL".into(), txt.into());
+            text.author_id = Some(0);
+            stats.add_text(text);
+            stats
+        };
+        stats_of("amo te amo", false)
+            .store_in_db(&db)
+            .await
+            .unwrap();
+
+        // storing the lemmas fails without a Lemma relation, rolling back the whole store
+        assert!(stats_of("amo amo amo", true)
+            .store_in_db(&db)
+            .await
+            .is_err());
+        assert_eq!(db.count("Word").await, Ok(2));
+    }
+
+    #[tokio::test]
+    async fn store_in_db_batched_keeps_committed_batches() {
+        let db = DBConnection::new_in_memory().unwrap();
+        // without a Lemma relation, storing fails right after all texts and words were committed
+        db.run_mutable(TEXT_SCHEMA, DBParams::new()).await.unwrap();
+        db.run_mutable(WORD_SCHEMA, DBParams::new()).await.unwrap();
+
+        let mut stats = Stats::new();
+        stats.set_lemmatizer(EndingLemmatizer);
+        for txt in ["Salvē amīcē!", "Quid nunc?"] {
+            let mut text = Text::new("URL".into(), txt.into());
+            text.author_id = Some(0);
+            stats.add_text(text);
+        }
+
+        // each text is committed along with its words, even with a smaller batch size
+        let err = stats.store_in_db_batched(&db, Some(1)).await.unwrap_err();
+        assert_eq!(err.committed, 6);
+        assert_eq!(db.count("Text").await, Ok(2));
+        assert_eq!(db.count("Word").await, Ok(4));
+    }
+
+    #[test]
+    fn interning_stores_each_word_once() {
+        let mut interner = WordInterner::new();
+        let amo = interner.intern("amo".into());
+        let amas = interner.intern("amas".into());
+
+        assert_ne!(amo, amas);
+        assert_eq!(interner.intern("amo".into()), amo);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.get("amas"), Some(amas));
+        assert_eq!(interner.get("amat"), None);
+        assert_eq!(interner.resolve(amo), "amo");
+
+        let roundtrip = WordInterner::from(Vec::<Word>::from(interner.clone()));
+        assert_eq!(roundtrip, interner);
+    }
+
+    #[test]
+    fn interned_stats_match_word_counts() {
+        let mut stats = Stats::new();
+        let txt = "Amo, amas, amat. Amo tē, amīcē, et tē amat poēta.";
+        let mut text = Text::new("URL".into(), txt.into());
+        text.author_id = Some(0);
+        stats.add_text(text);
+
+        let mut expected: HashMap<Word, usize> = HashMap::new();
+        for word in Text::new("URL".into(), txt.into()).words() {
+            *expected.entry(word).or_default() += 1;
+        }
+
+        assert_eq!(stats.unique_word_count(), expected.len());
+        for (word, count) in expected {
+            let word_stats = stats.word_stats(word.as_str()).unwrap();
+            assert_eq!(word_stats.global_count(), count, "count of {}", word);
+        }
+
+        let stored_words: HashSet<String> = stats
+            .store_rows()
+            .into_iter()
+            .filter_map(|row| match row {
+                StoreRow::Word(DataValue::List(values)) => {
+                    values.first().and_then(|w| w.get_str()).map(String::from)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(stored_words.len(), stats.unique_word_count());
     }
 }
@@ -1,5 +1,5 @@
 use serde_derive::{Deserialize, Serialize};
-use std::fmt::Display;
+use std::{borrow::Cow, collections::BTreeSet, fmt::Display, path::Path, time::Duration};
 
 use crate::db::{DataValue, Num, ToDataValue};
 
@@ -10,7 +10,19 @@ pub struct Author {
     pub url: String,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+impl ToDataValue for Author {
+    /// Encodes the author as an `[author_id, name, url]` row, matching the
+    /// column order used when storing into the `Author` relation.
+    fn to_data_value(&self) -> DataValue {
+        DataValue::List(vec![
+            self.author_id.to_data_value(),
+            self.name.to_data_value(),
+            self.url.to_data_value(),
+        ])
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct TextId(usize);
 
 impl From<TextId> for DataValue {
@@ -49,6 +61,138 @@ impl ToDataValue for TextId {
     }
 }
 
+/// Which characters are kept within a word when tokenizing a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TokenMode {
+    /// Only letters, dropping digits (the default for Latin texts).
+    #[default]
+    Alphabetic,
+    /// Letters and digits, keeping numbers like dates in mixed-content texts.
+    Alphanumeric,
+}
+
+impl TokenMode {
+    pub fn keeps(&self, c: char) -> bool {
+        match self {
+            TokenMode::Alphabetic => c.is_alphabetic(),
+            TokenMode::Alphanumeric => c.is_alphanumeric(),
+        }
+    }
+}
+
+/// Splits a text into words.
+pub trait Tokenizer {
+    /// Whether `c` separates two tokens.
+    fn is_separator(&self, c: char) -> bool;
+
+    /// Turns a token between separators into a word, or `None` to drop it.
+    fn word(&self, token: &str) -> Option<Word>;
+
+    /// All words of a token. Override this to split a token into several words.
+    fn words(&self, token: &str) -> Vec<Word> {
+        self.word(token).into_iter().collect()
+    }
+}
+
+/// The default tokenizer: splits on whitespace and punctuation, lowercases all words
+/// and folds the ligatures `æ` and `œ`, see `Word::normalize_latin`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatinTokenizer {
+    pub mode: TokenMode,
+    /// Split enclitics (`-que`, `-ve`, `-ne`) off into words of their own.
+    pub split_enclitics: bool,
+    /// Words with fewer characters are dropped, e.g. single letters from OCR noise.
+    pub min_word_len: usize,
+    /// Also fold the orthographic variants `v` and `j` into `u` and `i`.
+    pub fold_orthography: bool,
+}
+
+impl Default for LatinTokenizer {
+    fn default() -> Self {
+        Self::new(TokenMode::default())
+    }
+}
+
+impl LatinTokenizer {
+    /// Keeps all words, even single letters.
+    pub const DEFAULT_MIN_WORD_LEN: usize = 1;
+
+    pub const fn new(mode: TokenMode) -> Self {
+        Self {
+            mode,
+            split_enclitics: false,
+            min_word_len: Self::DEFAULT_MIN_WORD_LEN,
+            fold_orthography: false,
+        }
+    }
+
+    pub fn with_min_word_len(self, min_word_len: usize) -> Self {
+        Self {
+            min_word_len,
+            ..self
+        }
+    }
+
+    pub fn folding_orthography(self) -> Self {
+        Self {
+            fold_orthography: true,
+            ..self
+        }
+    }
+
+    // normalizes a trimmed word, or drops it if it's too short
+    fn finish(&self, word: Word) -> Option<Word> {
+        let word = word.normalize_latin(self.fold_orthography);
+        (word.as_str().chars().count() >= self.min_word_len).then_some(word)
+    }
+
+    pub fn splitting_enclitics(self) -> Self {
+        Self {
+            split_enclitics: true,
+            ..self
+        }
+    }
+}
+
+impl Tokenizer for LatinTokenizer {
+    fn is_separator(&self, c: char) -> bool {
+        Text::word_splitter(c)
+    }
+
+    fn word(&self, token: &str) -> Option<Word> {
+        Text::trim_word(token, self.mode).and_then(|word| self.finish(word))
+    }
+
+    fn words(&self, token: &str) -> Vec<Word> {
+        let Some(word) = Text::trim_word(token, self.mode) else {
+            return Vec::new();
+        };
+
+        // split before folding, so `-ve` is still recognized when folding `v` into `u`
+        match word.split_enclitic().filter(|_| self.split_enclitics) {
+            Some((word, enclitic)) => [word, enclitic]
+                .into_iter()
+                .filter_map(|word| self.finish(word))
+                .collect(),
+            None => self.finish(word).into_iter().collect(),
+        }
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for &T {
+    fn is_separator(&self, c: char) -> bool {
+        (*self).is_separator(c)
+    }
+
+    fn word(&self, token: &str) -> Option<Word> {
+        (*self).word(token)
+    }
+
+    fn words(&self, token: &str) -> Vec<Word> {
+        (*self).words(token)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Text {
     pub id: Option<TextId>,
@@ -58,6 +202,9 @@ pub struct Text {
 }
 
 impl Text {
+    /// Typical reading speed for Latin prose, which is read slower than a native language.
+    pub const DEFAULT_LATIN_WPM: usize = 120;
+
     pub fn new(url: String, text: String) -> Self {
         Self {
             id: None,
@@ -72,39 +219,86 @@ impl Text {
     }
 
     pub fn words(&self) -> impl Iterator<Item = Word> + '_ {
-        self.text
-            .split(Self::word_splitter)
-            .filter_map(Self::trim_latin_word)
+        self.words_with(TokenMode::default())
     }
 
-    pub fn word_splitter(c: char) -> bool {
-        c.is_whitespace() || c.is_ascii_punctuation() || !c.is_alphanumeric()
+    pub fn words_with(&self, mode: TokenMode) -> impl Iterator<Item = Word> + '_ {
+        self.tokenize(LatinTokenizer::new(mode))
     }
 
-    pub fn trim_latin_word(word: &str) -> Option<Word> {
-        if word.starts_with('<') || word.starts_with('>') {
-            return None;
+    /// Splits the text into words with a custom tokenizer, after stripping any HTML.
+    /// Tokenizers that aren't `Copy` can be passed by reference.
+    pub fn tokenize<'a, T: Tokenizer + Copy + 'a>(
+        &'a self,
+        tokenizer: T,
+    ) -> impl Iterator<Item = Word> + 'a {
+        let words: Vec<Word> = self
+            .plain_text()
+            .split(move |c| tokenizer.is_separator(c))
+            .flat_map(move |token| tokenizer.words(token))
+            .collect();
+        words.into_iter()
+    }
+
+    /// All sequences of `n` consecutive words. Texts with fewer than `n` words have none.
+    pub fn ngrams(&self, n: usize) -> impl Iterator<Item = Vec<Word>> {
+        let words: Vec<Word> = self.words().collect();
+        let ngrams: Vec<Vec<Word>> = if n == 0 {
+            Vec::new()
+        } else {
+            words.windows(n).map(<[Word]>::to_vec).collect()
+        };
+        ngrams.into_iter()
+    }
+
+    /// The visible text with all HTML tags removed and entities decoded.
+    /// The HTML is only parsed if the text looks like it contains any.
+    pub fn plain_text(&self) -> Cow<'_, str> {
+        if !self.text.contains(['<', '&']) {
+            return Cow::Borrowed(&self.text);
         }
 
-        let trimmed = word.trim().replace("&nbsp;", " ");
+        Cow::Owned(
+            // separate text nodes, so words in neighbouring elements don't run together
+            scraper::Html::parse_fragment(&self.text)
+                .root_element()
+                .text()
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
 
-        if trimmed.is_empty() {
-            return None;
+    /// Estimated time needed to read the text at the given words per minute.
+    pub fn reading_time(&self, words_per_minute: usize) -> Duration {
+        if words_per_minute == 0 {
+            return Duration::ZERO;
         }
+        let words = self.words().count();
+        Duration::from_secs_f64(words as f64 * 60.0 / words_per_minute as f64)
+    }
+
+    pub fn word_splitter(c: char) -> bool {
+        c.is_whitespace() || c.is_ascii_punctuation() || !c.is_alphanumeric()
+    }
 
-        // remove all non-alphabetic characters
-        let trimmed = trimmed
+    pub fn trim_latin_word(word: &str) -> Option<Word> {
+        Self::trim_word(word, TokenMode::Alphabetic)
+    }
+
+    /// Turns a token of plain text (see `plain_text`) into a lowercase word,
+    /// dropping all characters not kept by the token mode.
+    pub fn trim_word(word: &str, mode: TokenMode) -> Option<Word> {
+        let trimmed = word
+            .trim()
             .chars()
-            .filter(|c| c.is_alphabetic())
+            .filter(|c| mode.keeps(*c))
             .collect::<String>();
 
-        let trimmed = scraper::Html::parse_fragment(&trimmed)
-            .root_element()
-            .text()
-            .collect::<String>()
-            .to_lowercase();
+        if trimmed.is_empty() {
+            return None;
+        }
 
-        Some(Word(trimmed))
+        Some(Word(trimmed.to_lowercase()))
     }
 }
 
@@ -114,17 +308,285 @@ impl<Url: ToString, Txt: ToString> From<(Url, Txt)> for Text {
     }
 }
 
+/// Very common Latin function words that carry little meaning on their own.
+pub const LATIN_STOPWORDS: &[&str] = &[
+    "a", "ab", "ac", "ad", "an", "at", "atque", "aut", "autem", "cum", "de", "e", "enim", "esse",
+    "est", "et", "etiam", "ex", "hic", "iam", "in", "nam", "ne", "nec", "neque", "non", "per",
+    "quae", "qui", "quod", "sed", "si", "sunt", "tam", "ut",
+];
+
+/// Reads a stopword list with one word per line, ignoring empty lines and `#` comments.
+/// Words are lowercased like all stored words.
+pub fn load_stopwords(path: &Path) -> crate::Result<BTreeSet<String>> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(parse_stopwords(&contents))
+}
+
+fn parse_stopwords(contents: &str) -> BTreeSet<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Maps inflected word forms to a common lemma, so that counts can be
+/// aggregated across all inflections of a word.
+pub trait Lemmatizer {
+    fn lemmatize(&self, word: &Word) -> Word;
+}
+
+/// Common Latin declension and conjugation endings, with and without macrons.
+pub const LATIN_ENDINGS: &[&str] = &[
+    // nouns and adjectives
+    "a",
+    "ā",
+    "ae",
+    "am",
+    "ārum",
+    "arum",
+    "ās",
+    "as",
+    "e",
+    "ē",
+    "ēbus",
+    "ebus",
+    "ei",
+    "em",
+    "ēs",
+    "es",
+    "ī",
+    "i",
+    "ibus",
+    "is",
+    "īs",
+    "ium",
+    "ius",
+    "ō",
+    "o",
+    "ōrum",
+    "orum",
+    "ōs",
+    "os",
+    "u",
+    "ū",
+    "ubus",
+    "um",
+    "us",
+    "ūs",
+    // verbs
+    "ābam",
+    "abam",
+    "ābant",
+    "abant",
+    "ābat",
+    "abat",
+    "āmus",
+    "amus",
+    "ant",
+    "āre",
+    "are",
+    "ārunt",
+    "arunt",
+    "at",
+    "ātis",
+    "atis",
+    "āvērunt",
+    "averunt",
+    "āvit",
+    "avit",
+    "ēbam",
+    "ebam",
+    "ēbant",
+    "ebant",
+    "ēbat",
+    "ebat",
+    "ēmus",
+    "emus",
+    "ent",
+    "ere",
+    "ēre",
+    "ērunt",
+    "erunt",
+    "et",
+    "ētis",
+    "etis",
+    "imus",
+    "īre",
+    "ire",
+    "it",
+    "itis",
+    "īvit",
+    "ivit",
+    "nt",
+    "unt",
+    "ur",
+];
+
+/// Rule based lemmatizer that strips the longest known Latin ending.
+/// The resulting lemma is the word's stem (e.g. `amīc` for `amīcus`,
+/// `amīcī` and `amīcō`), not its dictionary form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EndingLemmatizer;
+
+impl EndingLemmatizer {
+    /// Stems never get shorter than this, so that short words stay intact.
+    pub const MIN_STEM_LEN: usize = 2;
+}
+
+impl Lemmatizer for EndingLemmatizer {
+    fn lemmatize(&self, word: &Word) -> Word {
+        let len = word.as_str().chars().count();
+
+        LATIN_ENDINGS
+            .iter()
+            .filter(|ending| word.as_str().ends_with(*ending))
+            .map(|ending| ending.chars().count())
+            .filter(|ending_len| len - ending_len >= Self::MIN_STEM_LEN)
+            .max()
+            .map(|ending_len| Word(word.as_str().chars().take(len - ending_len).collect()))
+            .unwrap_or_else(|| word.clone())
+    }
+}
+
+/// Enclitics that can be attached to the end of any Latin word.
+pub const LATIN_ENCLITICS: &[&str] = &["que", "ve", "ne"];
+
+/// Words ending like an enclitic without having one attached.
+pub const ENCLITIC_EXCEPTIONS: &[&str] = &[
+    // -que
+    "absque",
+    "atque",
+    "cuique",
+    "cuiusque",
+    "denique",
+    "itaque",
+    "namque",
+    "neque",
+    "plerique",
+    "plerumque",
+    "quaeque",
+    "quamque",
+    "quemque",
+    "quibusque",
+    "quicumque",
+    "quidque",
+    "quinque",
+    "quisque",
+    "quodque",
+    "quoque",
+    "ubique",
+    "undique",
+    "usque",
+    "uterque",
+    "utique",
+    "utraque",
+    "utrumque",
+    // -ve
+    "ave",
+    "breve",
+    "cave",
+    "grave",
+    "leve",
+    "nave",
+    "neve",
+    "salve",
+    "sive",
+    "suave",
+    "vive",
+    // -ne
+    "bene",
+    "mane",
+    "paene",
+    "pone",
+    "sine",
+];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Word(String);
 
 impl Word {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
 
+    pub fn is_stopword(&self) -> bool {
+        LATIN_STOPWORDS.contains(&self.0.as_str())
+    }
+
     pub fn to_lowercase(&self) -> Self {
         Self(self.0.to_lowercase())
     }
+
+    /// Splits an enclitic off the word, e.g. `arma` and `que` for `armaque`.
+    /// Known exceptions like `quinque` or `namque` are never split. Since `-ne`
+    /// is also a common ending after vowels (`ratione`), it's only split after consonants.
+    pub fn split_enclitic(&self) -> Option<(Word, Word)> {
+        if ENCLITIC_EXCEPTIONS.contains(&self.fold_macrons().as_str()) {
+            return None;
+        }
+
+        LATIN_ENCLITICS.iter().find_map(|enclitic| {
+            let stem = self.0.strip_suffix(enclitic)?;
+            if stem.chars().count() < 2 {
+                return None;
+            }
+            if *enclitic == "ne" && stem.ends_with(|c: char| "aeiouyāēīōūȳ".contains(c)) {
+                return None;
+            }
+            Some((Word::from(stem), Word::from(*enclitic)))
+        })
+    }
+
+    /// The word with the ligatures `æ` and `œ` written out as `ae` and `oe`, e.g.
+    /// `caelum` for `cælum`. With `fold_orthography`, the orthographic variants
+    /// `v` and `j` also become `u` and `i`, e.g. `iuuenis` for `juvenis`.
+    pub fn normalize_latin(&self, fold_orthography: bool) -> Self {
+        let mut normalized = String::with_capacity(self.0.len());
+        for c in self.0.chars() {
+            match c {
+                'æ' => normalized.push_str("ae"),
+                'Æ' => normalized.push_str("Ae"),
+                'œ' => normalized.push_str("oe"),
+                'Œ' => normalized.push_str("Oe"),
+                'v' if fold_orthography => normalized.push('u'),
+                'V' if fold_orthography => normalized.push('U'),
+                'j' if fold_orthography => normalized.push('i'),
+                'J' if fold_orthography => normalized.push('I'),
+                c => normalized.push(c),
+            }
+        }
+        Self(normalized)
+    }
+
+    /// The word without vowel length marks, e.g. `amicus` for `amīcus`.
+    pub fn fold_macrons(&self) -> Self {
+        Self(
+            self.0
+                .chars()
+                .map(|c| match c {
+                    'ā' => 'a',
+                    'ē' => 'e',
+                    'ī' => 'i',
+                    'ō' => 'o',
+                    'ū' => 'u',
+                    'ȳ' => 'y',
+                    'Ā' => 'A',
+                    'Ē' => 'E',
+                    'Ī' => 'I',
+                    'Ō' => 'O',
+                    'Ū' => 'U',
+                    'Ȳ' => 'Y',
+                    c => c,
+                })
+                .collect(),
+        )
+    }
 }
 
 impl Display for Word {
@@ -171,6 +633,15 @@ mod tests {
         Text::new("https://example.com".into(), txt.to_string())
     }
 
+    #[test]
+    fn test_parse_stopwords() {
+        let stopwords = parse_stopwords("# site boilerplate\nLatin\n\n  library \nbr\nlatin\n");
+        assert_eq!(
+            stopwords.into_iter().collect::<Vec<_>>(),
+            vec!["br", "latin", "library"]
+        );
+    }
+
     #[test]
     fn test_words() {
         assert_eq!(
@@ -218,6 +689,209 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_words_with_token_mode() {
+        let txt = text("Anno 1492 Columbus navigāvit.");
+        assert_eq!(
+            txt.words().collect::<Vec<_>>(),
+            vec![
+                Word::from("anno"),
+                Word::from("columbus"),
+                Word::from("navigāvit")
+            ]
+        );
+        assert_eq!(
+            txt.words_with(TokenMode::Alphanumeric).collect::<Vec<_>>(),
+            vec![
+                Word::from("anno"),
+                Word::from("1492"),
+                Word::from("columbus"),
+                Word::from("navigāvit")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_tokenizer() {
+        // keeps hyphenated words together and preserves case
+        struct HyphenTokenizer;
+
+        impl Tokenizer for HyphenTokenizer {
+            fn is_separator(&self, c: char) -> bool {
+                c != '-' && Text::word_splitter(c)
+            }
+
+            fn word(&self, token: &str) -> Option<Word> {
+                (!token.is_empty()).then(|| Word::from(token))
+            }
+        }
+
+        let txt = text("Gallia est omnis dīvīsa in partēs trēs; Ave-Maria.");
+        assert_eq!(
+            txt.tokenize(&HyphenTokenizer).collect::<Vec<_>>(),
+            vec![
+                Word::from("Gallia"),
+                Word::from("est"),
+                Word::from("omnis"),
+                Word::from("dīvīsa"),
+                Word::from("in"),
+                Word::from("partēs"),
+                Word::from("trēs"),
+                Word::from("Ave-Maria")
+            ]
+        );
+        assert_eq!(
+            txt.tokenize(LatinTokenizer::default()).collect::<Vec<_>>(),
+            txt.words().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_ngrams() {
+        let txt = text("Amo, amas, amat.");
+        assert_eq!(
+            txt.ngrams(2).collect::<Vec<_>>(),
+            vec![
+                vec![Word::from("amo"), Word::from("amas")],
+                vec![Word::from("amas"), Word::from("amat")]
+            ]
+        );
+        assert_eq!(txt.ngrams(3).count(), 1);
+        assert_eq!(txt.ngrams(4).count(), 0);
+        assert_eq!(txt.ngrams(0).count(), 0);
+        assert_eq!(text("").ngrams(1).count(), 0);
+    }
+
+    #[test]
+    fn test_reading_time() {
+        let txt = text("Quī linguam Latīnam discere vult variīs modīs id facere potest.");
+        assert_eq!(txt.reading_time(120), Duration::from_secs(5));
+        assert_eq!(txt.reading_time(60), Duration::from_secs(10));
+        assert_eq!(txt.reading_time(0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_split_enclitics() {
+        let txt = text("Arma virumque canō, Trōiae quī prīmus ab ōrīs. Estne plusve?");
+        assert_eq!(
+            txt.tokenize(LatinTokenizer::default().splitting_enclitics())
+                .collect::<Vec<_>>(),
+            vec![
+                Word::from("arma"),
+                Word::from("virum"),
+                Word::from("que"),
+                Word::from("canō"),
+                Word::from("trōiae"),
+                Word::from("quī"),
+                Word::from("prīmus"),
+                Word::from("ab"),
+                Word::from("ōrīs"),
+                Word::from("est"),
+                Word::from("ne"),
+                Word::from("plus"),
+                Word::from("ve")
+            ]
+        );
+        // not split by default
+        assert!(txt.words().any(|w| w.as_str() == "virumque"));
+        // split off enclitics can be too short to be kept
+        let long_words = txt
+            .tokenize(
+                LatinTokenizer::default()
+                    .splitting_enclitics()
+                    .with_min_word_len(4),
+            )
+            .collect::<Vec<_>>();
+        assert!(long_words.iter().any(|w| w.as_str() == "virum"));
+        assert!(!long_words
+            .iter()
+            .any(|w| ["que", "ne", "ve"].contains(&w.as_str())));
+
+        for word in [
+            "quinque", "namque", "atque", "itaque", "quōque", "sive", "salve", "bene", "ratiōne",
+            "que", "ne",
+        ] {
+            assert_eq!(Word::from(word).split_enclitic(), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_fold_macrons() {
+        assert_eq!(Word::from("amīcus").fold_macrons(), Word::from("amicus"));
+        assert_eq!(
+            Word::from("Quōmodo hodiē Ūnā").fold_macrons(),
+            Word::from("Quomodo hodie Una")
+        );
+        assert_eq!(Word::from("zȳthum").fold_macrons(), Word::from("zythum"));
+        assert_eq!(Word::from("amicus").fold_macrons(), Word::from("amicus"));
+    }
+
+    #[test]
+    fn test_ending_lemmatizer() {
+        let lemma = |word: &str| EndingLemmatizer.lemmatize(&Word::from(word));
+
+        for form in ["amīcus", "amīcī", "amīcō", "amīcum", "amīcōrum", "amīcīs"] {
+            assert_eq!(lemma(form), Word::from("amīc"), "lemma of {}", form);
+        }
+        for form in ["amo", "amas", "amat", "amant", "amabat"] {
+            assert_eq!(lemma(form), Word::from("am"), "lemma of {}", form);
+        }
+        // too short to strip an ending
+        assert_eq!(lemma("et"), Word::from("et"));
+        assert_eq!(lemma("tē"), Word::from("tē"));
+    }
+
+    #[test]
+    fn test_normalize_latin() {
+        let normalized = |word: &str, fold_orthography| {
+            Word::from(word)
+                .normalize_latin(fold_orthography)
+                .as_str()
+                .to_string()
+        };
+
+        assert_eq!(normalized("cælum", false), "caelum");
+        assert_eq!(normalized("pœna", false), "poena");
+        assert_eq!(normalized("Æneas", false), "Aeneas");
+        assert_eq!(normalized("Œdipus", false), "Oedipus");
+        assert_eq!(normalized("juvenis", false), "juvenis");
+        assert_eq!(normalized("juvenis", true), "iuuenis");
+        assert_eq!(normalized("Julius", true), "Iulius");
+
+        let tokenizer = LatinTokenizer::default();
+        assert_eq!(tokenizer.word("Cœlum,"), Some(Word::from("coelum")));
+        assert_eq!(tokenizer.word("vivo"), Some(Word::from("vivo")));
+
+        let tokenizer = tokenizer.folding_orthography().splitting_enclitics();
+        assert_eq!(tokenizer.word("vivo"), Some(Word::from("uiuo")));
+        assert_eq!(
+            tokenizer.words("virumve"),
+            vec![Word::from("uirum"), Word::from("ue")]
+        );
+    }
+
+    #[test]
+    fn test_min_word_len() {
+        let tokenizer = LatinTokenizer::default();
+        assert_eq!(tokenizer.min_word_len, 1);
+        assert_eq!(tokenizer.word(" a..."), Some(Word::from("a")));
+
+        let tokenizer = tokenizer.with_min_word_len(2);
+        assert_eq!(tokenizer.word(" a..."), None);
+        assert_eq!(tokenizer.word(" ab "), Some(Word::from("ab")));
+        assert_eq!(
+            text("Ō Tite, e caelo a deis datur.")
+                .tokenize(tokenizer)
+                .collect::<Vec<_>>(),
+            vec![
+                Word::from("tite"),
+                Word::from("caelo"),
+                Word::from("deis"),
+                Word::from("datur")
+            ]
+        );
+    }
+
     #[test]
     fn test_trim_latin_word() {
         assert_eq!(Text::trim_latin_word(" a..."), Some(Word::from("a")));
@@ -228,13 +902,42 @@ mod tests {
             Text::trim_latin_word(" habemus "),
             Some(Word::from("habemus"))
         );
-        assert_eq!(Text::trim_latin_word("<html>"), None);
-        assert_eq!(Text::trim_latin_word("<body>"), None);
-        assert_eq!(Text::trim_latin_word("<head>"), None);
-        assert_eq!(Text::trim_latin_word("</html>"), None);
-        assert_eq!(Text::trim_latin_word("</body>"), None);
-        assert_eq!(Text::trim_latin_word("</head>"), None);
-        assert_eq!(Text::trim_latin_word("<p>"), None);
-        assert_eq!(Text::trim_latin_word("<br/>"), None);
+        assert_eq!(Text::trim_latin_word("..."), None);
+
+        // tags are stripped from the whole text before it's split into words,
+        // so trimming a single token only drops the brackets
+        assert_eq!(Text::trim_latin_word("<p>"), Some(Word::from("p")));
+        assert_eq!(Text::trim_latin_word("<br/>"), Some(Word::from("br")));
+        for tag in [
+            "<html>", "<body>", "<head>", "</html>", "</body>", "</head>", "<p>", "<br/>",
+        ] {
+            assert_eq!(text(tag).words().count(), 0, "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_words_strip_html() {
+        assert_eq!(
+            text("<html><body><p>Gallia <b>est</b> omnis</p><br/>dīvīsa&nbsp;in partēs&amp;trēs</body></html>")
+                .words()
+                .collect::<Vec<_>>(),
+            vec![
+                Word::from("gallia"),
+                Word::from("est"),
+                Word::from("omnis"),
+                Word::from("dīvīsa"),
+                Word::from("in"),
+                Word::from("partēs"),
+                Word::from("trēs")
+            ]
+        );
+
+        // malformed tags don't leak into the words either
+        assert_eq!(
+            text("<p class=\"x y\">Ave<br / >vale</p>")
+                .words()
+                .collect::<Vec<_>>(),
+            vec![Word::from("ave"), Word::from("vale")]
+        );
     }
 }
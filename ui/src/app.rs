@@ -1,43 +1,103 @@
 use iced::{
-    widget::{Column, Container, PickList, Row, Scrollable, Text, TextInput},
-    Application, Command, Element, Theme,
+    event,
+    futures::future::{AbortHandle, Abortable},
+    keyboard, subscription,
+    widget::{text_input, Button, Column, Container, PickList, Row, Scrollable, Text, TextInput},
+    window, Application, Color, Command, Element, Event, Subscription, Theme,
 };
+use std::path::PathBuf;
+use std::time::Duration;
 
-use svl_core::{db::DBConnection, text};
+use svl_core::{
+    db::{format_data_value, DBConnection},
+    text,
+};
 
 use crate::{
+    config::UiConfig,
     errors::SearchError,
     message::Message,
-    query,
-    search::{Search, SearchKind, SearchMode, SearchResult, SearchState},
+    query::{self, Details, Selection},
+    search::{
+        AuthorResult, Search, SearchKind, SearchMode, SearchResult, SearchState, SortOrder,
+        WordResult,
+    },
 };
 
 pub struct App {
     current_search_kind: SearchKind,
     current_search_mode: SearchMode,
-    author_search: SearchState<text::Author>,
+    current_sort_order: SortOrder,
+    author_search: SearchState<AuthorResult>,
     text_search: SearchState<text::Text>,
-    word_search: SearchState<text::Word>,
+    word_search: SearchState<WordResult>,
     db: svl_core::db::DBConnection,
+    // last failed search, shown until dismissed or the next search starts
+    error_message: Option<String>,
+    // where the last export was written to, shown next to the result count
+    export_message: Option<String>,
+    // aborts the searches still running, for cancelling them
+    search_aborts: Vec<AbortHandle>,
+    // advanced on every tick while searching
+    spinner_frame: usize,
+    // details of the last clicked search result
+    details: Option<Details>,
+    // index of the result on the current page chosen with the arrow keys
+    selected_result: Option<usize>,
+    window_size: (u32, u32),
+    // where the UI state is saved on close, not saved if there's no home directory
+    config_path: Option<PathBuf>,
 }
 
 pub struct Args {
     pub db: DBConnection,
+    pub config: UiConfig,
+    pub config_path: Option<PathBuf>,
 }
 
 impl App {
     fn new(args: Args) -> Self {
-        Self {
+        let mut app = Self {
             db: args.db,
-            current_search_kind: SearchKind::default(),
-            current_search_mode: SearchMode::default(),
+            current_search_kind: args.config.search_kind,
+            current_search_mode: args.config.search_mode,
+            current_sort_order: SortOrder::default(),
             author_search: SearchState::default(),
             text_search: SearchState::default(),
             word_search: SearchState::default(),
+            error_message: None,
+            export_message: None,
+            search_aborts: Vec::new(),
+            spinner_frame: 0,
+            details: None,
+            selected_result: None,
+            window_size: args.config.window_size,
+            config_path: args.config_path,
+        };
+        app.update_search(&args.config.search_term);
+        app
+    }
+
+    fn config(&self) -> UiConfig {
+        UiConfig {
+            search_kind: self.current_search_kind,
+            search_mode: self.current_search_mode,
+            search_term: self.search_term(),
+            window_size: self.window_size,
         }
     }
 
-    fn view_search_kind(&self) -> Element<Message> {
+    fn save_config(&self) {
+        let Some(path) = &self.config_path else {
+            return;
+        };
+        // the window is closing, so there's no error banner left to show this in
+        if let Err(err) = self.config().save(path) {
+            eprintln!("Could not save UI config to {}: {}", path.display(), err);
+        }
+    }
+
+    fn view_search_kind(&self) -> Element<'_, Message> {
         match self.current_search_kind {
             SearchKind::Author => self.view_authors(),
             SearchKind::Text => self.view_texts(),
@@ -45,34 +105,86 @@ impl App {
         }
     }
 
-    fn view_words(&self) -> Element<Message> {
-        // list all words from search results
+    fn view_words(&self) -> Element<'_, Message> {
+        // list all words from search results, highlighting the part matching the search
+        let search = self.word_search.results_search();
         self.word_search
-            .search_results_iter()
-            .fold(Column::new(), |col, word| {
-                col.push(Text::new(word.to_string()))
+            .page_results_iter()
+            .enumerate()
+            .fold(Column::new(), |col, (index, result)| {
+                let label = Row::new()
+                    .spacing(10)
+                    .push(highlighted_word(result.word.as_str(), search))
+                    .push(Text::new(format!(
+                        "{} times in {} texts",
+                        result.count, result.texts
+                    )));
+                col.push(result_button(
+                    label,
+                    Selection::Word(result.word.clone()),
+                    self.selected_result == Some(index),
+                ))
             })
             .into()
     }
 
-    fn view_texts(&self) -> Element<Message> {
+    fn view_texts(&self) -> Element<'_, Message> {
         // list all texts from search results
         self.text_search
-            .search_results_iter()
-            .fold(Column::new(), |col, text| col.push(Text::new(&text.url)))
+            .page_results_iter()
+            .enumerate()
+            .fold(Column::new(), |col, (index, text)| {
+                col.push(result_button(
+                    Text::new(text.url.clone()),
+                    Selection::Text(text.clone()),
+                    self.selected_result == Some(index),
+                ))
+            })
             .into()
     }
 
-    fn view_authors(&self) -> Element<Message> {
+    fn view_authors(&self) -> Element<'_, Message> {
         // list all authors from search results
         self.author_search
-            .search_results_iter()
-            .fold(Column::new(), |col, author| {
-                col.push(Text::new(&author.name))
+            .page_results_iter()
+            .enumerate()
+            .fold(Column::new(), |col, (index, result)| {
+                col.push(result_button(
+                    Text::new(format!("{} ({} texts)", result.author.name, result.texts)),
+                    Selection::Author(result.author.clone()),
+                    self.selected_result == Some(index),
+                ))
             })
             .into()
     }
 
+    fn view_details(&self) -> Option<Element<'_, Message>> {
+        let details = self.details.as_ref()?;
+
+        let header = Row::new()
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .push(Text::new(&details.title).size(20))
+            .push(Button::new(Text::new("Close")).on_press(Message::CloseDetails));
+
+        let header_row = table_row(details.rows.headers.iter().cloned());
+        let rows = details
+            .rows
+            .rows
+            .iter()
+            .fold(Column::new().spacing(5).push(header_row), |col, row| {
+                col.push(table_row(row.iter().map(format_data_value)))
+            });
+
+        Some(
+            Column::new()
+                .spacing(10)
+                .push(header)
+                .push(Scrollable::new(rows))
+                .into(),
+        )
+    }
+
     fn search_term(&self) -> String {
         match self.current_search_kind {
             SearchKind::Author => self.author_search.search_term(),
@@ -89,6 +201,108 @@ impl App {
         }
     }
 
+    fn is_searching_any(&self) -> bool {
+        self.author_search.is_searching()
+            || self.text_search.is_searching()
+            || self.word_search.is_searching()
+    }
+
+    fn is_searching_for(&self, search: &Search) -> bool {
+        match search.kind {
+            SearchKind::Author => self.author_search.is_searching_for(search),
+            SearchKind::Text => self.text_search.is_searching_for(search),
+            SearchKind::Word => self.word_search.is_searching_for(search),
+        }
+    }
+
+    // total number of results, current page (starting at 1) and number of pages
+    fn pagination(&self) -> (usize, usize, usize) {
+        match self.current_search_kind {
+            SearchKind::Author => pagination(&self.author_search),
+            SearchKind::Text => pagination(&self.text_search),
+            SearchKind::Word => pagination(&self.word_search),
+        }
+    }
+
+    fn next_page(&mut self) {
+        match self.current_search_kind {
+            SearchKind::Author => self.author_search.next_page(),
+            SearchKind::Text => self.text_search.next_page(),
+            SearchKind::Word => self.word_search.next_page(),
+        }
+    }
+
+    fn prev_page(&mut self) {
+        match self.current_search_kind {
+            SearchKind::Author => self.author_search.prev_page(),
+            SearchKind::Text => self.text_search.prev_page(),
+            SearchKind::Word => self.word_search.prev_page(),
+        }
+    }
+
+    fn clear_search(&mut self) {
+        match self.current_search_kind {
+            SearchKind::Author => self.author_search.clear(),
+            SearchKind::Text => self.text_search.clear(),
+            SearchKind::Word => self.word_search.clear(),
+        }
+        self.selected_result = None;
+        self.details = None;
+    }
+
+    // the search result at an index of the current page
+    fn page_selection(&self, index: usize) -> Option<Selection> {
+        match self.current_search_kind {
+            SearchKind::Author => self
+                .author_search
+                .page_results_iter()
+                .nth(index)
+                .map(|result| Selection::Author(result.author.clone())),
+            SearchKind::Text => self
+                .text_search
+                .page_results_iter()
+                .nth(index)
+                .map(|text| Selection::Text(text.clone())),
+            SearchKind::Word => self
+                .word_search
+                .page_results_iter()
+                .nth(index)
+                .map(|result| Selection::Word(result.word.clone())),
+        }
+    }
+
+    fn page_results_count(&self) -> usize {
+        match self.current_search_kind {
+            SearchKind::Author => self.author_search.page_results_iter().count(),
+            SearchKind::Text => self.text_search.page_results_iter().count(),
+            SearchKind::Word => self.word_search.page_results_iter().count(),
+        }
+    }
+
+    // moves the arrow key selection up or down the current page and shows its details
+    fn select_result(&mut self, down: bool) -> Command<Message> {
+        let count = self.page_results_count();
+        if count == 0 {
+            return Command::none();
+        }
+
+        let index = match (self.selected_result, down) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(index), true) => (index + 1).min(count - 1),
+            (Some(index), false) => index.saturating_sub(1),
+        };
+        self.selected_result = Some(index);
+
+        match self.page_selection(index) {
+            Some(selection) => Command::perform(
+                query::load_details(self.db.clone(), selection),
+                Message::DetailsLoaded,
+            ),
+            None => Command::none(),
+        }
+    }
+
     fn search_kind(&self) -> SearchKind {
         self.current_search_kind
     }
@@ -133,26 +347,55 @@ impl App {
     fn search_command(&mut self) -> Command<Message> {
         let db = self.db.clone();
         let search = self.current_search();
+        self.error_message = None;
+
+        if let Err(err) = search.validate() {
+            self.report_error(err);
+            return Command::none();
+        }
+
+        // don't hammer the DB with the same search while it's still running
+        if self.is_searching_for(&search) {
+            return Command::none();
+        }
 
         match search.kind {
             SearchKind::Author => {
                 self.author_search.started_search(search.clone());
-                let task = query::search_authors(db, search);
-                Command::perform(task, Message::SearchCompleted)
+                self.cancellable(query::search_authors(db, search))
             }
             SearchKind::Text => {
                 self.text_search.started_search(search.clone());
-                let task = query::search_texts(db, search);
-                Command::perform(task, Message::SearchCompleted)
+                self.cancellable(query::search_texts(db, search))
             }
             SearchKind::Word => {
                 self.word_search.started_search(search.clone());
-                let task = query::search_words(db, search);
-                Command::perform(task, Message::SearchCompleted)
+                self.cancellable(query::search_words(db, search))
             }
         }
     }
 
+    // runs the search so that `cancel_searches` can drop it before it completes
+    fn cancellable(
+        &mut self,
+        task: impl std::future::Future<Output = SearchResult> + Send + 'static,
+    ) -> Command<Message> {
+        let (abort, registration) = AbortHandle::new_pair();
+        self.search_aborts.push(abort);
+        Command::perform(Abortable::new(task, registration), |result| {
+            Message::SearchCompleted(result.unwrap_or(Err(SearchError::Cancelled)))
+        })
+    }
+
+    fn cancel_searches(&mut self) {
+        for abort in self.search_aborts.drain(..) {
+            abort.abort();
+        }
+        self.author_search.clear_active_searches();
+        self.text_search.clear_active_searches();
+        self.word_search.clear_active_searches();
+    }
+
     fn update_search_results(&mut self, result: SearchResult) -> Result<(), SearchError> {
         match result {
             Ok(rows) => {
@@ -160,21 +403,65 @@ impl App {
                     SearchKind::Author => {
                         self.author_search.ended_search(rows.search());
                         self.author_search.update_search_results(rows.try_into()?);
+                        self.author_search.sort_results(self.current_sort_order);
                     }
                     SearchKind::Text => {
                         self.text_search.ended_search(rows.search());
                         self.text_search.update_search_results(rows.try_into()?);
+                        self.text_search.sort_results(self.current_sort_order);
                     }
                     SearchKind::Word => {
                         self.word_search.ended_search(rows.search());
                         self.word_search.update_search_results(rows.try_into()?);
+                        self.word_search.sort_results(self.current_sort_order);
                     }
                 }
                 Ok(())
             }
-            Err(err) => Err(err),
+            Err(err) => {
+                // failed searches don't tell which search they belonged to
+                self.author_search.clear_active_searches();
+                self.text_search.clear_active_searches();
+                self.word_search.clear_active_searches();
+                Err(err)
+            }
         }
     }
+
+    // writes the current kind's results to the JSON file chosen in the save dialog
+    fn export_results(&mut self, path: PathBuf) {
+        let result = match self.current_search_kind {
+            SearchKind::Author => self.author_search.export_json(&path),
+            SearchKind::Text => self.text_search.export_json(&path),
+            SearchKind::Word => self.word_search.export_json(&path),
+        };
+
+        match result {
+            Ok(()) => self.export_message = Some(format!("Exported to {}", path.display())),
+            Err(err) => {
+                self.export_message = None;
+                self.error_message = Some(format!("Export failed: {}", err));
+            }
+        }
+    }
+
+    fn report_error(&mut self, err: SearchError) {
+        self.error_message = Some(format!("Search failed: {}", err));
+    }
+
+    fn view_error(&self) -> Container<'_, Message> {
+        let Some(error_message) = &self.error_message else {
+            return empty_placeholder_container();
+        };
+
+        let banner = Row::new()
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .push(Text::new(error_message).style(Color::from_rgb(0.9, 0.3, 0.3)))
+            .push(Button::new(Text::new("Dismiss")).on_press(Message::DismissError));
+
+        padded_container(banner)
+    }
 }
 
 impl Application for App {
@@ -193,7 +480,14 @@ impl Application for App {
 
     fn update(&mut self, message: Self::Message) -> Command<Message> {
         match message {
-            Message::Closed => Command::none(),
+            Message::Closed => {
+                self.save_config();
+                window::close()
+            }
+            Message::WindowResized(width, height) => {
+                self.window_size = (width, height);
+                Command::none()
+            }
 
             Message::InputChanged(term) => {
                 self.update_search(&term);
@@ -212,37 +506,120 @@ impl Application for App {
             }
             Message::SearchKindChanged(kind) => {
                 self.current_search_kind = kind;
+                self.selected_result = None;
                 Command::none()
             }
             Message::SearchModeChanged(mode) => {
                 self.current_search_mode = mode;
                 Command::none()
             }
+            Message::SortChanged(order) => {
+                self.current_sort_order = order;
+                self.selected_result = None;
+                self.author_search.sort_results(order);
+                self.text_search.sort_results(order);
+                self.word_search.sort_results(order);
+                Command::none()
+            }
+            Message::SearchCompleted(Err(SearchError::Cancelled)) => Command::none(),
             Message::SearchCompleted(result) => {
+                self.selected_result = None;
                 match self.update_search_results(result) {
                     Ok(_) => println!("Search completed successfully"),
-                    Err(err) => println!("Search failed: {}", err),
+                    Err(err) => self.report_error(err),
+                }
+                if !self.is_searching_any() {
+                    self.search_aborts.clear();
                 }
                 Command::none()
             }
+            Message::CancelSearch => {
+                self.cancel_searches();
+                Command::none()
+            }
             Message::CaseSensitiveChanged(is_case_sensitive) => {
                 self.update_case_sensitive(is_case_sensitive);
                 Command::none()
             }
+            Message::DismissError => {
+                self.error_message = None;
+                Command::none()
+            }
+            Message::SelectResult(selection) => Command::perform(
+                query::load_details(self.db.clone(), selection),
+                Message::DetailsLoaded,
+            ),
+            Message::DetailsLoaded(result) => {
+                match result {
+                    Ok(details) => self.details = Some(details),
+                    Err(err) => self.report_error(err),
+                }
+                Command::none()
+            }
+            Message::CloseDetails => {
+                self.details = None;
+                Command::none()
+            }
+            Message::NextPage => {
+                self.next_page();
+                self.selected_result = None;
+                Command::none()
+            }
+            Message::PrevPage => {
+                self.prev_page();
+                self.selected_result = None;
+                Command::none()
+            }
+            Message::Tick => {
+                self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+                Command::none()
+            }
+            Message::ClearSearch => {
+                self.clear_search();
+                Command::none()
+            }
+            Message::FocusSearch => text_input::focus(search_input_id()),
+            Message::ExportResults => Command::perform(
+                choose_export_path(self.current_search_kind),
+                Message::ExportPathChosen,
+            ),
+            Message::ExportPathChosen(Some(path)) => {
+                self.export_results(path);
+                Command::none()
+            }
+            Message::ExportPathChosen(None) => Command::none(),
+            Message::SelectNextResult => self.select_result(true),
+            Message::SelectPrevResult => self.select_result(false),
         }
     }
 
-    fn view(&self) -> Element<Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
         let search_term: String = self.search_term();
 
-        let result_counter = Text::new(format!(
-            "Found {} results",
-            match self.current_search_kind {
-                SearchKind::Author => self.author_search.search_results_count(),
-                SearchKind::Text => self.text_search.search_results_count(),
-                SearchKind::Word => self.word_search.search_results_count(),
-            }
-        ));
+        let (total, page, pages) = self.pagination();
+        let mut prev_button = Button::new(Text::new("<"));
+        if page > 1 {
+            prev_button = prev_button.on_press(Message::PrevPage);
+        }
+        let mut next_button = Button::new(Text::new(">"));
+        if page < pages {
+            next_button = next_button.on_press(Message::NextPage);
+        }
+        let mut export_button = Button::new(Text::new("Export"));
+        if total > 0 {
+            export_button = export_button.on_press(Message::ExportResults);
+        }
+        let mut result_counter = Row::new()
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .push(Text::new(format!("Found {} results", total)))
+            .push(prev_button)
+            .push(Text::new(format!("page {} of {}", page, pages)))
+            .push(next_button)
+            .push(export_button);
+        if let Some(export_message) = &self.export_message {
+            result_counter = result_counter.push(Text::new(export_message));
+        }
 
         let side_padding = iced::Padding {
             left: 10.0,
@@ -252,10 +629,22 @@ impl Application for App {
         };
         let fill = iced::Length::Fill;
 
-        let input = TextInput::new("Search...", &search_term)
-            .on_input(Message::InputChanged)
-            .on_submit(Message::Search)
+        // without handlers, the input and button are disabled while searching
+        let is_searching = self.is_searching();
+        let mut input = TextInput::new("Search...", &search_term)
+            .id(search_input_id())
             .padding(10);
+        let mut search_button = Button::new(Text::new("Search")).padding(10);
+        if !is_searching {
+            input = input
+                .on_input(Message::InputChanged)
+                .on_submit(Message::Search);
+            search_button = search_button.on_press(Message::Search);
+        }
+        let search_row = Row::new()
+            .spacing(10)
+            .push(input.width(fill))
+            .push(search_button);
 
         let search_kind_pick_list = PickList::new(
             SearchKind::all_kinds(),
@@ -269,6 +658,12 @@ impl Application for App {
             Message::SearchModeChanged,
         );
 
+        let sort_order_pick_list = PickList::new(
+            SortOrder::all_orders(),
+            Some(self.current_sort_order),
+            Message::SortChanged,
+        );
+
         // checkbox for case sensitive search
         let case_sensitive_checkbox = iced::widget::checkbox::Checkbox::new(
             "Case sensitive",
@@ -280,23 +675,35 @@ impl Application for App {
             .spacing(10)
             .push(search_kind_pick_list)
             .push(search_mode_pick_list)
+            .push(sort_order_pick_list)
             .push(case_sensitive_checkbox);
 
-        let search_indicator = if self.is_searching() {
-            padded_container(Text::new("Searching...")).padding(side_padding)
+        let search_indicator = if is_searching {
+            let frame = SPINNER_FRAMES[self.spinner_frame];
+            let indicator = Row::new()
+                .spacing(10)
+                .align_items(iced::Alignment::Center)
+                .push(Text::new(format!("{} Searching...", frame)))
+                .push(Button::new(Text::new("Cancel")).on_press(Message::CancelSearch));
+            padded_container(indicator).padding(side_padding)
         } else {
             empty_placeholder_container()
         };
 
+        let results = Scrollable::new(padded_container(self.view_search_kind()).width(fill));
+        let mut results_and_details = Row::new().push(Container::new(results).width(fill));
+        if let Some(details) = self.view_details() {
+            results_and_details = results_and_details.push(padded_container(details).width(fill));
+        }
+
         Container::new(
             Column::new()
                 .push(padded_container(picklist_row))
+                .push(self.view_error())
                 .push(padded_container(result_counter).padding(side_padding))
-                .push(padded_container(input.padding(10)).width(fill))
+                .push(padded_container(search_row).width(fill))
                 .push(search_indicator)
-                .push(Scrollable::new(
-                    padded_container(self.view_search_kind()).width(fill),
-                )),
+                .push(results_and_details),
         )
         .width(fill)
         .height(fill)
@@ -306,6 +713,121 @@ impl Application for App {
     fn theme(&self) -> Theme {
         Theme::Dark
     }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let spinner = if self.is_searching() {
+            iced::time::every(Duration::from_millis(120)).map(|_| Message::Tick)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([spinner, subscription::events_with(app_event)])
+    }
+}
+
+fn search_input_id() -> text_input::Id {
+    text_input::Id::new("search-input")
+}
+
+// asks where to export results to, suggesting a file named after the kind of results.
+// the dialog itself asks before overwriting an existing file
+async fn choose_export_path(kind: SearchKind) -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .set_file_name(format!(
+            "svl_{}_results.json",
+            kind.to_string().to_lowercase()
+        ))
+        .add_filter("JSON", &["json"])
+        .save_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+fn app_event(event: Event, _status: event::Status) -> Option<Message> {
+    match event {
+        Event::Window(window::Event::CloseRequested) => Some(Message::Closed),
+        Event::Window(window::Event::Resized { width, height }) => {
+            Some(Message::WindowResized(width, height))
+        }
+        Event::Keyboard(event) => keyboard_shortcut(event),
+        _ => None,
+    }
+}
+
+// Escape clears the search, Ctrl+L focuses the search box and Up/Down move through the results.
+// Enter is handled by the search box itself.
+fn keyboard_shortcut(event: keyboard::Event) -> Option<Message> {
+    let keyboard::Event::KeyPressed {
+        key_code,
+        modifiers,
+    } = event
+    else {
+        return None;
+    };
+
+    match key_code {
+        keyboard::KeyCode::Escape => Some(Message::ClearSearch),
+        keyboard::KeyCode::L if modifiers.control() => Some(Message::FocusSearch),
+        keyboard::KeyCode::Down => Some(Message::SelectNextResult),
+        keyboard::KeyCode::Up => Some(Message::SelectPrevResult),
+        _ => None,
+    }
+}
+
+const SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+fn pagination<T>(state: &SearchState<T>) -> (usize, usize, usize) {
+    (
+        state.search_results_count(),
+        state.page() + 1,
+        state.page_count(),
+    )
+}
+
+// a row of equally wide table cells
+fn table_row<'a>(cells: impl Iterator<Item = String>) -> Element<'a, Message> {
+    cells
+        .fold(Row::new().spacing(10), |row, cell| {
+            row.push(Text::new(cell).width(iced::Length::FillPortion(1)))
+        })
+        .into()
+}
+
+// a search result that shows its details when clicked, highlighted if chosen with the arrow keys
+fn result_button<'a>(
+    label: impl Into<Element<'a, Message>>,
+    selection: Selection,
+    is_selected: bool,
+) -> Button<'a, Message> {
+    let style = if is_selected {
+        iced::theme::Button::Primary
+    } else {
+        iced::theme::Button::Text
+    };
+    Button::new(label)
+        .style(style)
+        .on_press(Message::SelectResult(selection))
+}
+
+// the word with the part matched by the search colored, or plain if there's no single match
+fn highlighted_word<'a>(word: &str, search: Option<&Search>) -> Element<'a, Message> {
+    let Some(range) = search.and_then(|s| s.match_range(word)) else {
+        return Text::new(word.to_string()).into();
+    };
+    // lowercasing can change byte offsets for some characters, don't split within one
+    let (Some(before), Some(matched), Some(after)) = (
+        word.get(..range.start),
+        word.get(range.clone()),
+        word.get(range.end..),
+    ) else {
+        return Text::new(word.to_string()).into();
+    };
+
+    Row::new()
+        .push(Text::new(before.to_string()))
+        .push(Text::new(matched.to_string()).style(Color::from_rgb(0.4, 0.8, 1.0)))
+        .push(Text::new(after.to_string()))
+        .into()
 }
 
 fn padded_container<'a>(content: impl Into<Element<'a, Message>>) -> Container<'a, Message> {
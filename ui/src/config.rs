@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+use serde_derive::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::search::{SearchKind, SearchMode};
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Invalid config file: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Could not write config: {0}")]
+    Serialize(#[from] toml::ser::Error),
+
+    #[error("IOError: {0}")]
+    IO(#[from] std::io::Error),
+}
+
+/// The UI state restored on startup, saved to a small TOML file when the window is closed.
+/// Missing values get their defaults, unknown ones (e.g. of newer versions) are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    #[serde(with = "display_from_str")]
+    pub search_kind: SearchKind,
+    #[serde(with = "display_from_str")]
+    pub search_mode: SearchMode,
+    pub search_term: String,
+    pub window_size: (u32, u32),
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            search_kind: SearchKind::default(),
+            search_mode: SearchMode::default(),
+            search_term: String::new(),
+            window_size: Self::DEFAULT_WINDOW_SIZE,
+        }
+    }
+}
+
+impl UiConfig {
+    // same as iced's default window size
+    pub const DEFAULT_WINDOW_SIZE: (u32, u32) = (1024, 768);
+
+    /// The default config file in the home directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".svl_ui_config.toml"))
+    }
+
+    /// Loads the config from a file, falling back to the defaults if it doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+// search kinds and modes are stored by their displayed names, e.g. "ends with"
+mod display_from_str {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T: FromStr, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| D::Error::custom(format!("invalid value: {}", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_roundtrips_through_config_file_format() {
+        let config = UiConfig {
+            search_kind: SearchKind::Author,
+            search_mode: SearchMode::EndsWith,
+            search_term: "\"ius\" = ".to_string(),
+            window_size: (800, 600),
+        };
+
+        let contents = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<UiConfig>(&contents).unwrap(), config);
+        assert_eq!(toml::from_str::<UiConfig>("").unwrap(), UiConfig::default());
+        assert_eq!(
+            toml::from_str::<UiConfig>("search_mode = \"ends with\"\ncolour = \"red\"").unwrap(),
+            UiConfig {
+                search_mode: SearchMode::EndsWith,
+                ..UiConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_config_values_are_errors() {
+        assert!(toml::from_str::<UiConfig>("search_mode = \"sounds like\"").is_err());
+        assert!(toml::from_str::<UiConfig>("window_size").is_err());
+    }
+}
@@ -1,42 +1,44 @@
-use std::fmt::Display;
+use std::time::Duration;
+
+pub use svl_core::db::ExpectedType;
 
 use thiserror::Error;
 
+/// All the ways a search can fail, from validating the search over running
+/// the query to decoding its results.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Error)]
 pub enum SearchError {
-    #[error("DBError: {0}")]
+    #[error("Database error: {0}")]
     Db(String),
 
-    #[error("Missing column: {0}")]
+    #[error("Search timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("Search cancelled")]
+    Cancelled,
+
+    #[error("Invalid search: {0}")]
+    Invalid(String),
+
+    #[error("Could not decode search results: missing column {0}")]
     MissingColumn(String),
 
+    #[error("Could not decode search results: invalid type for {0}, expected {1}")]
+    InvalidType(String, ExpectedType),
+
     #[error("Other error: {0}")]
     Other(String),
-
-    #[error("Invalid type for {0} - Expected {1}")]
-    InvalidType(String, ExpectedType),
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum ExpectedType {
-    Boolean,
-    Float,
-    Integer,
-    String,
-    Usize,
-}
+/// Why search results couldn't be exported to a file.
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("IOError: {0}")]
+    Io(#[from] std::io::Error),
 
-impl Display for ExpectedType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ExpectedType::Boolean => write!(f, "Boolean"),
-            ExpectedType::Float => write!(f, "Float"),
-            ExpectedType::Integer => write!(f, "Integer"),
-            ExpectedType::String => write!(f, "String"),
-            ExpectedType::Usize => write!(f, "Usize"),
-        }
-    }
+    #[error("Could not serialize results: {0}")]
+    Json(#[from] serde_json::Error),
 }
 
 impl SearchError {
@@ -52,7 +54,45 @@ impl SearchError {
         Self::Other(err.to_string())
     }
 
+    pub fn timeout(after: Duration) -> Self {
+        Self::Timeout(after)
+    }
+
+    pub fn invalid<S: ToString>(reason: S) -> Self {
+        Self::Invalid(reason.to_string())
+    }
+
     pub fn invalid_type<S: ToString>(property: S, expected_type: ExpectedType) -> Self {
         Self::InvalidType(property.to_string(), expected_type)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(
+            SearchError::db("no such relation: Word").to_string(),
+            "Database error: no such relation: Word"
+        );
+        assert_eq!(
+            SearchError::timeout(Duration::from_secs(30)).to_string(),
+            "Search timed out after 30s"
+        );
+        assert_eq!(
+            SearchError::invalid("search term is empty").to_string(),
+            "Invalid search: search term is empty"
+        );
+        assert_eq!(
+            SearchError::missing_column("url").to_string(),
+            "Could not decode search results: missing column url"
+        );
+        assert_eq!(
+            SearchError::invalid_type("author_id", ExpectedType::Usize).to_string(),
+            "Could not decode search results: invalid type for author_id, expected Usize"
+        );
+        assert_eq!(SearchError::other("oops").to_string(), "Other error: oops");
+    }
+}
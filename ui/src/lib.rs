@@ -5,20 +5,51 @@
 //! # Modules
 //!
 //! - `app`: Contains the main application logic and state management.
+//! - `config`: Persists the last search and window size between sessions.
 //! - `errors`: Defines error types and utilities for handling UI-specific errors.
 //! - `message`: Defines the message passing mechanism between UI components.
 //! - `search`: Implements search-related functionality, including search states and views.
 
 mod app;
+mod config;
 mod errors;
 mod message;
 mod query;
 mod search;
 
 use app::App;
+use config::UiConfig;
 use iced::{Application, Settings};
+use std::time::Duration;
 use svl_core::db::DBConnection;
 
+/// Searches taking longer fail, unless the connection already has a timeout.
+const DEFAULT_SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub fn run_ui(db: DBConnection) -> iced::Result {
-    App::run(Settings::with_flags(app::Args { db }))
+    let db = match db.timeout() {
+        Some(_) => db,
+        None => db.with_timeout(DEFAULT_SEARCH_TIMEOUT),
+    };
+    let config_path = UiConfig::default_path();
+    let config = config_path
+        .as_deref()
+        .map(UiConfig::load)
+        .transpose()
+        .unwrap_or_else(|err| {
+            eprintln!("Could not load UI config, using defaults: {}", err);
+            None
+        })
+        .unwrap_or_default();
+
+    let mut settings = Settings::with_flags(app::Args {
+        db,
+        config: config.clone(),
+        config_path,
+    });
+    settings.window.size = config.window_size;
+    // the app saves its state on Message::Closed and then closes the window itself
+    settings.exit_on_close_request = false;
+
+    App::run(settings)
 }
@@ -1,13 +1,35 @@
-use crate::search::{SearchKind, SearchMode, SearchResult};
+use std::path::PathBuf;
+
+use crate::{
+    query::{DetailsResult, Selection},
+    search::{SearchKind, SearchMode, SearchResult, SortOrder},
+};
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum Message {
     Closed,
+    WindowResized(u32, u32),
     InputChanged(String),
     Search,
     SearchKindChanged(SearchKind),
     SearchModeChanged(SearchMode),
+    SortChanged(SortOrder),
     SearchCompleted(SearchResult),
+    CancelSearch,
     CaseSensitiveChanged(bool),
+    DismissError,
+    Tick,
+    SelectResult(Selection),
+    DetailsLoaded(DetailsResult),
+    CloseDetails,
+    NextPage,
+    PrevPage,
+    ClearSearch,
+    FocusSearch,
+    SelectNextResult,
+    SelectPrevResult,
+    ExportResults,
+    /// The file picked in the save dialog, `None` if it was cancelled.
+    ExportPathChosen(Option<PathBuf>),
 }
@@ -1,28 +1,50 @@
-use crate::search::{Search, SearchResult, SearchRows};
-use svl_core::db::DBConnection;
+use crate::{
+    errors::SearchError,
+    search::{Search, SearchResult, SearchRows},
+};
+use svl_core::{
+    db::{DBConnection, DBParams, NamedRows, ToDataValue},
+    queries, text,
+};
+
+// validate the search and run its script, mapping every failure into a SearchError
+async fn run_search(
+    db: &DBConnection,
+    search: &Search,
+    script: &str,
+    params: DBParams,
+) -> Result<NamedRows, SearchError> {
+    search.validate()?;
+    let rows = db.run_immutable(script, params).await?;
+    Ok(rows)
+}
 
 #[allow(dead_code)]
 pub async fn search_authors(db: DBConnection, search: Search) -> SearchResult {
     let query = search.query("name");
     let script = format!(
-        "?[name, url] :=
-            *Author {{ name, url }},
-            {}",
+        "texts[author_id, count(text_id)] := *Text {{ text_id, author_id }}
+        authors[author_id, name, url] :=
+            *Author {{ author_id, name, url }},
+            {}
+        ?[name, url, texts] := authors[author_id, name, url], texts[author_id, texts]
+        ?[name, url, texts] := authors[author_id, name, url], not texts[author_id, _], texts = 0",
         query.code
     );
-    let rows = db.run_immutable(&script, query.params).await?;
+    let rows = run_search(&db, &search, &script, query.params).await?;
     Ok(SearchRows::new(search, rows))
 }
 
 pub async fn search_words(db: DBConnection, search: Search) -> SearchResult {
     let query = search.query("word");
     let script = format!(
-        "?[word] :=
-            *Word {{ word }},
-            {}",
+        "counts[word, sum(count), count(text_id)] :=
+            *Word {{ word, text_id, count }},
+            {}
+        ?[word, count, texts] := counts[word, total, texts], count = to_int(total)",
         query.code
     );
-    let rows = db.run_immutable(&script, query.params).await?;
+    let rows = run_search(&db, &search, &script, query.params).await?;
     Ok(SearchRows::new(search, rows))
 }
 
@@ -35,6 +57,74 @@ pub async fn search_texts(db: DBConnection, search: Search) -> SearchResult {
             {}",
         query.code
     );
-    let rows = db.run_immutable(&script, query.params).await?;
+    let rows = run_search(&db, &search, &script, query.params).await?;
     Ok(SearchRows::new(search, rows))
 }
+
+/// The texts a word occurs in with its count in each, most frequent first.
+pub async fn word_occurrences(
+    db: DBConnection,
+    word: text::Word,
+) -> Result<NamedRows, SearchError> {
+    let rows = db
+        .run_immutable(
+            "?[text_id, url, count] :=
+                *Word { word, text_id, count },
+                word = $word,
+                *Text { text_id, url }
+            :sort -count, text_id",
+            DBParams::from_iter(vec![("word".into(), word.to_data_value())]),
+        )
+        .await?;
+    Ok(rows)
+}
+
+/// A search result that was clicked to show its details.
+#[derive(Debug, Clone)]
+pub enum Selection {
+    Author(text::Author),
+    Text(text::Text),
+    Word(text::Word),
+}
+
+/// Everything known about a selected search result, shown next to the results.
+#[derive(Debug, Clone)]
+pub struct Details {
+    pub title: String,
+    pub rows: NamedRows,
+}
+
+pub type DetailsResult = Result<Details, SearchError>;
+
+pub async fn load_details(db: DBConnection, selection: Selection) -> DetailsResult {
+    let (title, rows) = match selection {
+        Selection::Author(author) => {
+            let rows = db
+                .run_immutable(
+                    "?[text_id, url] :=
+                        *Author { author_id, name },
+                        name = $name,
+                        *Text { text_id, author_id, url }
+                    :sort text_id",
+                    DBParams::from_iter(vec![("name".into(), author.name.to_data_value())]),
+                )
+                .await?;
+            (format!("Texts by {}", author.name), rows)
+        }
+        Selection::Text(text) => {
+            let text_id = text
+                .id
+                .ok_or_else(|| SearchError::invalid(format!("text without id: {}", text.url)))?;
+            let rows = queries::text_info(&db, text_id, None)
+                .await
+                .map_err(SearchError::db)?;
+            (text.url, rows)
+        }
+        Selection::Word(word) => {
+            let title = format!("Occurrences of {}", word);
+            (title, word_occurrences(db, word).await?)
+        }
+    };
+
+    Ok(Details { title, rows })
+}
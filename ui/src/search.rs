@@ -1,6 +1,13 @@
-use svl_core::db::{DBError, DBParams, NamedRows};
+use std::cmp::Ordering;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
 
-use crate::errors::{ExpectedType, SearchError};
+use serde_derive::Serialize;
+
+use svl_core::db::{get_str, get_usize, DBError, DBParams, NamedRows};
+
+use crate::errors::{ExportError, SearchError};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SearchKind {
@@ -16,6 +23,18 @@ impl SearchKind {
     }
 }
 
+impl std::str::FromStr for SearchKind {
+    type Err = ();
+
+    /// Parses the displayed name of a kind, e.g. from a config file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all_kinds()
+            .into_iter()
+            .find(|kind| kind.to_string() == s)
+            .ok_or(())
+    }
+}
+
 impl std::fmt::Display for SearchKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -35,6 +54,8 @@ pub struct Search {
 }
 
 impl Search {
+    const MAX_TERM_LENGTH: usize = 256;
+
     pub fn new(kind: SearchKind, term: String, mode: SearchMode, is_case_sensitive: bool) -> Self {
         Self {
             kind,
@@ -44,6 +65,36 @@ impl Search {
         }
     }
 
+    /// Checks the search for problems that would otherwise only surface as DB errors.
+    pub fn validate(&self) -> Result<(), SearchError> {
+        if self.term.trim().is_empty() && self.mode.requires_term() {
+            return Err(SearchError::invalid(format!(
+                "search term must not be empty for \"{}\" searches",
+                self.mode
+            )));
+        }
+
+        let length = self.term.chars().count();
+        if length > Self::MAX_TERM_LENGTH {
+            return Err(SearchError::invalid(format!(
+                "search term is too long ({} characters, at most {} allowed)",
+                length,
+                Self::MAX_TERM_LENGTH
+            )));
+        }
+
+        if self.mode == SearchMode::Regex {
+            if let Err(err) = regex::Regex::new(&self.term) {
+                return Err(SearchError::invalid(format!(
+                    "invalid regular expression: {}",
+                    err
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn query(&self, var: &str) -> SearchQuery {
         let (var, term) = self.var_and_term(var);
         let (code, params) = self.mode.query(var.as_str(), term);
@@ -54,9 +105,35 @@ impl Search {
         }
     }
 
+    /// The byte range of `word` matched by the search term, e.g. to highlight it.
+    /// `None` for modes without a single matched range, like regexes.
+    pub fn match_range(&self, word: &str) -> Option<Range<usize>> {
+        let (word, term) = if self.is_case_sensitive {
+            (word.to_string(), self.term.clone())
+        } else {
+            (word.to_lowercase(), self.term.to_lowercase())
+        };
+        if term.is_empty() {
+            return None;
+        }
+
+        match self.mode {
+            SearchMode::Contains => word.find(&term).map(|start| start..start + term.len()),
+            SearchMode::StartsWith => word.starts_with(&term).then_some(0..term.len()),
+            SearchMode::EndsWith => word
+                .ends_with(&term)
+                .then(|| word.len() - term.len()..word.len()),
+            SearchMode::IsEqual => (word == term).then_some(0..word.len()),
+            SearchMode::IsNotEqual | SearchMode::Regex => None,
+        }
+    }
+
     fn var_and_term(&self, var: &str) -> (String, String) {
         if self.is_case_sensitive {
             (var.to_string(), self.term.clone())
+        } else if self.mode == SearchMode::Regex {
+            // lowercasing the pattern would change escapes like \D, so use a case-insensitive regex
+            (var.to_string(), format!("(?i){}", self.term))
         } else {
             (format!("lowercase({})", var), self.term.to_lowercase())
         }
@@ -78,6 +155,7 @@ pub enum SearchMode {
     EndsWith,
     IsEqual,
     IsNotEqual,
+    Regex,
     #[default]
     StartsWith,
 }
@@ -89,18 +167,31 @@ impl SearchMode {
             SearchMode::EndsWith,
             SearchMode::IsEqual,
             SearchMode::IsNotEqual,
+            SearchMode::Regex,
             SearchMode::StartsWith,
         ]
     }
 }
 
 impl SearchMode {
+    // an empty term would match everything for these modes
+    pub const fn requires_term(&self) -> bool {
+        matches!(
+            self,
+            SearchMode::Contains
+                | SearchMode::EndsWith
+                | SearchMode::Regex
+                | SearchMode::StartsWith
+        )
+    }
+
     pub fn query(&self, var: &str, term: String) -> SearchModeQuery {
         let func_name = match self {
             SearchMode::Contains => "str_includes",
             SearchMode::EndsWith => "ends_with",
             SearchMode::IsEqual => "eq",
             SearchMode::IsNotEqual => "neq",
+            SearchMode::Regex => "regex_matches",
             SearchMode::StartsWith => "starts_with",
         };
         let code = format!("{}({}, $term)", func_name, var);
@@ -109,6 +200,18 @@ impl SearchMode {
     }
 }
 
+impl std::str::FromStr for SearchMode {
+    type Err = ();
+
+    /// Parses the displayed name of a mode, e.g. from a config file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all_modes()
+            .into_iter()
+            .find(|mode| mode.to_string() == s)
+            .ok_or(())
+    }
+}
+
 impl std::fmt::Display for SearchMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -116,20 +219,107 @@ impl std::fmt::Display for SearchMode {
             SearchMode::EndsWith => write!(f, "ends with"),
             SearchMode::IsEqual => write!(f, "is equal to"),
             SearchMode::IsNotEqual => write!(f, "is not equal to"),
+            SearchMode::Regex => write!(f, "matches regex"),
             SearchMode::StartsWith => write!(f, "starts with"),
         }
     }
 }
 
+/// How search results are ordered in the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Alphabetical,
+    Count,
+    TextCount,
+}
+
+impl SortOrder {
+    pub fn all_orders() -> Vec<SortOrder> {
+        vec![
+            SortOrder::Alphabetical,
+            SortOrder::Count,
+            SortOrder::TextCount,
+        ]
+    }
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortOrder::Alphabetical => write!(f, "alphabetical"),
+            SortOrder::Count => write!(f, "by count"),
+            SortOrder::TextCount => write!(f, "by text count"),
+        }
+    }
+}
+
+/// A search result that can be ordered by a `SortOrder`.
+/// Results without counts fall back to alphabetical order.
+pub trait Sortable {
+    fn compare(&self, other: &Self, order: SortOrder) -> Ordering;
+}
+
+/// An author found by a search with the number of texts they wrote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthorResult {
+    pub author: svl_core::text::Author,
+    pub texts: usize,
+}
+
+impl Sortable for AuthorResult {
+    fn compare(&self, other: &Self, order: SortOrder) -> Ordering {
+        let alphabetical = self.author.name.cmp(&other.author.name);
+        match order {
+            SortOrder::Alphabetical => alphabetical,
+            SortOrder::Count | SortOrder::TextCount => {
+                other.texts.cmp(&self.texts).then(alphabetical)
+            }
+        }
+    }
+}
+
+impl Sortable for svl_core::text::Text {
+    fn compare(&self, other: &Self, _order: SortOrder) -> Ordering {
+        self.url.cmp(&other.url)
+    }
+}
+
+/// A word found by a search with its total count and the number of texts it occurs in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WordResult {
+    pub word: svl_core::text::Word,
+    pub count: usize,
+    pub texts: usize,
+}
+
+impl Sortable for WordResult {
+    fn compare(&self, other: &Self, order: SortOrder) -> Ordering {
+        let alphabetical = self.word.as_str().cmp(other.word.as_str());
+        match order {
+            SortOrder::Alphabetical => alphabetical,
+            SortOrder::Count => other.count.cmp(&self.count).then(alphabetical),
+            SortOrder::TextCount => other.texts.cmp(&self.texts).then(alphabetical),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchState<Result> {
     active_searches: Vec<Search>,
     is_case_sensitive: bool,
     search_term: String,
     search_results: Vec<Result>,
+    // the search the current results were found by
+    results_search: Option<Search>,
+    // index of the page of search results shown, starting at 0
+    page: usize,
+    page_size: usize,
 }
 
 impl<Result> SearchState<Result> {
+    pub const DEFAULT_PAGE_SIZE: usize = 100;
+
     pub fn search_term(&self) -> String {
         self.search_term.clone()
     }
@@ -140,10 +330,42 @@ impl<Result> SearchState<Result> {
 
     pub fn ended_search(&mut self, search: &Search) {
         self.active_searches.retain(|s| s != search);
+        self.results_search = Some(search.clone());
+    }
+
+    pub fn results_search(&self) -> Option<&Search> {
+        self.results_search.as_ref()
     }
 
-    pub fn search_results_iter(&self) -> impl Iterator<Item = &Result> {
-        self.search_results.iter()
+    pub fn clear_active_searches(&mut self) {
+        self.active_searches.clear();
+    }
+
+    /// The search results on the current page.
+    pub fn page_results_iter(&self) -> impl Iterator<Item = &Result> {
+        self.search_results
+            .iter()
+            .skip(self.page * self.page_size)
+            .take(self.page_size)
+    }
+
+    pub const fn page(&self) -> usize {
+        self.page
+    }
+
+    /// Number of pages, at least one even without any results.
+    pub fn page_count(&self) -> usize {
+        self.search_results.len().div_ceil(self.page_size).max(1)
+    }
+
+    pub fn next_page(&mut self) {
+        if self.page + 1 < self.page_count() {
+            self.page += 1;
+        }
+    }
+
+    pub fn prev_page(&mut self) {
+        self.page = self.page.saturating_sub(1);
     }
 
     pub fn search_results_count(&self) -> usize {
@@ -160,6 +382,15 @@ impl<Result> SearchState<Result> {
 
     pub fn update_search_results(&mut self, rows: Vec<Result>) {
         self.search_results = rows;
+        self.page = 0;
+    }
+
+    /// Forgets the search term and its results, e.g. when pressing Escape.
+    pub fn clear(&mut self) {
+        self.search_term.clear();
+        self.search_results.clear();
+        self.results_search = None;
+        self.page = 0;
     }
 
     pub fn update_case_sensitive(&mut self, is_case_sensitive: bool) {
@@ -169,6 +400,30 @@ impl<Result> SearchState<Result> {
     pub fn is_searching(&self) -> bool {
         !self.active_searches.is_empty()
     }
+
+    /// Whether the same search is still running, so it doesn't need to be started again.
+    pub fn is_searching_for(&self, search: &Search) -> bool {
+        self.active_searches.contains(search)
+    }
+}
+
+impl<Result: Sortable> SearchState<Result> {
+    /// Sorts all search results and goes back to the first page.
+    pub fn sort_results(&mut self, order: SortOrder) {
+        self.search_results.sort_by(|a, b| a.compare(b, order));
+        self.page = 0;
+    }
+}
+
+impl<Result: serde::Serialize> SearchState<Result> {
+    /// Writes all search results as a JSON array, in their current order.
+    pub fn export_json(&self, path: &Path) -> std::result::Result<(), ExportError> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, &self.search_results)?;
+        // dropping the writer would ignore errors from writing the buffered rest
+        writer.flush()?;
+        Ok(())
+    }
 }
 
 impl<Result> Default for SearchState<Result> {
@@ -178,6 +433,9 @@ impl<Result> Default for SearchState<Result> {
             is_case_sensitive: true,
             search_term: String::new(),
             search_results: Vec::new(),
+            results_search: None,
+            page: 0,
+            page_size: Self::DEFAULT_PAGE_SIZE,
         }
     }
 }
@@ -186,7 +444,14 @@ pub type SearchResult = Result<SearchRows, SearchError>;
 
 impl From<DBError> for SearchError {
     fn from(err: DBError) -> Self {
-        SearchError::Db(err.to_string())
+        match err {
+            DBError::Timeout(after) => SearchError::timeout(after),
+            DBError::MissingColumn(idx) => SearchError::missing_column(format!("#{}", idx)),
+            DBError::InvalidType { column, expected } => {
+                SearchError::invalid_type(format!("column #{}", column), expected)
+            }
+            err => SearchError::Db(err.to_string()),
+        }
     }
 }
 
@@ -228,38 +493,28 @@ type Row = Vec<svl_core::db::DataValue>;
 struct AuthorRowPositions {
     name: usize,
     url: usize,
+    texts: usize,
 }
 
 fn decode_author(
     row: &Row,
     pos: AuthorRowPositions,
     author_id: usize,
-) -> Result<svl_core::text::Author, SearchError> {
-    let name = row
-        .get(pos.name)
-        .ok_or(SearchError::missing_column("name"))?;
-    let name = name
-        .get_str()
-        .map(|s| s.to_string())
-        .ok_or(SearchError::invalid_type("name", ExpectedType::String))?;
-
-    let url = row.get(pos.url).ok_or(SearchError::missing_column("url"))?;
-    let url = url
-        .get_str()
-        .map(|s| s.to_string())
-        .ok_or(SearchError::invalid_type("url", ExpectedType::String))?;
-
+) -> Result<AuthorResult, SearchError> {
     let author = svl_core::text::Author {
         author_id,
-        name,
-        url,
+        name: get_str(row, pos.name)?,
+        url: get_str(row, pos.url)?,
     };
 
-    Ok(author)
+    Ok(AuthorResult {
+        author,
+        texts: get_usize(row, pos.texts)?,
+    })
 }
 
 fn add_authors(
-    authors: &mut Vec<svl_core::text::Author>,
+    authors: &mut Vec<AuthorResult>,
     rows: &[Row],
     pos: AuthorRowPositions,
 ) -> Result<(), SearchError> {
@@ -269,13 +524,14 @@ fn add_authors(
     Ok(())
 }
 
-impl TryFrom<SearchRows> for Vec<svl_core::text::Author> {
+impl TryFrom<SearchRows> for Vec<AuthorResult> {
     type Error = SearchError;
 
     fn try_from(sr: SearchRows) -> Result<Self, Self::Error> {
         let name = sr.position("name")?;
         let url = sr.position("url")?;
-        let pos = AuthorRowPositions { name, url };
+        let texts = sr.position("texts")?;
+        let pos = AuthorRowPositions { name, url, texts };
         let mut rows = sr.rows;
 
         let mut authors = Vec::with_capacity(rows.rows.len());
@@ -300,38 +556,11 @@ struct TextRowPositions {
 }
 
 fn decode_text(row: &Row, pos: TextRowPositions) -> Result<svl_core::text::Text, SearchError> {
-    let id = row
-        .get(pos.text_id)
-        .and_then(|x| x.get_int())
-        .map(|i| i.into());
-
-    let text = row
-        .get(pos.text)
-        .ok_or(SearchError::missing_column("text"))?;
-    let text = text
-        .get_str()
-        .map(|x| x.to_string())
-        .ok_or(SearchError::invalid_type("text", ExpectedType::String))?;
-
-    let author_id = row
-        .get(pos.author_id)
-        .ok_or(SearchError::missing_column("author_id"))?;
-    let author_id = author_id
-        .get_int()
-        .map(|i| i as usize)
-        .ok_or(SearchError::invalid_type("author_id", ExpectedType::Usize))?;
-
-    let url = row.get(pos.url).ok_or(SearchError::missing_column("url"))?;
-    let url = url
-        .get_str()
-        .map(|x| x.to_string())
-        .ok_or(SearchError::invalid_type("url", ExpectedType::String))?;
-
     let text = svl_core::text::Text {
-        id,
-        text,
-        author_id: Some(author_id),
-        url,
+        id: get_usize(row, pos.text_id).ok().map(|i| i.into()),
+        text: get_str(row, pos.text)?,
+        author_id: Some(get_usize(row, pos.author_id)?),
+        url: get_str(row, pos.url)?,
     };
 
     Ok(text)
@@ -380,22 +609,22 @@ impl TryFrom<SearchRows> for Vec<svl_core::text::Text> {
 #[derive(Debug, Clone, Copy)]
 struct WordRowPositions {
     word: usize,
+    count: usize,
+    texts: usize,
 }
 
-fn decode_word(row: &Row, pos: WordRowPositions) -> Result<svl_core::text::Word, SearchError> {
-    let word = row
-        .get(pos.word)
-        .ok_or(SearchError::missing_column("word"))?;
-    let word = word
-        .get_str()
-        .map(|s| s.to_string())
-        .ok_or(SearchError::invalid_type("word", ExpectedType::String))?;
+fn decode_word(row: &Row, pos: WordRowPositions) -> Result<WordResult, SearchError> {
+    let word = WordResult {
+        word: get_str(row, pos.word)?.into(),
+        count: get_usize(row, pos.count)?,
+        texts: get_usize(row, pos.texts)?,
+    };
 
-    Ok(word.into())
+    Ok(word)
 }
 
 fn add_words(
-    words: &mut Vec<svl_core::text::Word>,
+    words: &mut Vec<WordResult>,
     rows: &[Row],
     pos: WordRowPositions,
 ) -> Result<(), SearchError> {
@@ -405,12 +634,14 @@ fn add_words(
     Ok(())
 }
 
-impl TryFrom<SearchRows> for Vec<svl_core::text::Word> {
+impl TryFrom<SearchRows> for Vec<WordResult> {
     type Error = SearchError;
 
     fn try_from(sr: SearchRows) -> Result<Self, Self::Error> {
         let word = sr.position("word")?;
-        let pos = WordRowPositions { word };
+        let count = sr.position("count")?;
+        let texts = sr.position("texts")?;
+        let pos = WordRowPositions { word, count, texts };
         let mut rows = sr.rows;
 
         let mut words = Vec::with_capacity(rows.rows.len());
@@ -425,3 +656,200 @@ impl TryFrom<SearchRows> for Vec<svl_core::text::Word> {
         Ok(words)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(term: &str, mode: SearchMode) -> Search {
+        Search::new(SearchKind::Word, term.to_string(), mode, false)
+    }
+
+    #[test]
+    fn search_state_tracks_pending_searches() {
+        let mut state = SearchState::<svl_core::text::Word>::default();
+        let amic = search("amic", SearchMode::StartsWith);
+        let amo = search("amo", SearchMode::StartsWith);
+
+        state.started_search(amic.clone());
+        assert!(state.is_searching());
+        assert!(state.is_searching_for(&amic));
+        assert!(!state.is_searching_for(&amo));
+
+        state.ended_search(&amic);
+        assert!(!state.is_searching());
+        assert!(!state.is_searching_for(&amic));
+    }
+
+    #[test]
+    fn search_state_pages_results() {
+        let mut state = SearchState::<usize> {
+            page_size: 2,
+            ..SearchState::default()
+        };
+        assert_eq!(state.page_count(), 1);
+
+        state.update_search_results(vec![1, 2, 3, 4, 5]);
+        assert_eq!(state.page_count(), 3);
+        assert_eq!(state.page_results_iter().collect::<Vec<_>>(), vec![&1, &2]);
+
+        state.prev_page();
+        assert_eq!(state.page(), 0);
+        state.next_page();
+        state.next_page();
+        state.next_page();
+        assert_eq!(state.page(), 2);
+        assert_eq!(state.page_results_iter().collect::<Vec<_>>(), vec![&5]);
+
+        state.update_search_results(vec![6]);
+        assert_eq!(state.page(), 0);
+
+        state.update_search("amo");
+        state.next_page();
+        state.clear();
+        assert_eq!(state.search_term(), "");
+        assert_eq!(state.search_results_count(), 0);
+        assert_eq!(state.page(), 0);
+    }
+
+    #[test]
+    fn search_state_sorts_word_results() {
+        let word = |word: &str, count, texts| WordResult {
+            word: word.into(),
+            count,
+            texts,
+        };
+        let mut state = SearchState::<WordResult>::default();
+        state.update_search_results(vec![
+            word("amo", 3, 1),
+            word("amicus", 5, 2),
+            word("amat", 3, 3),
+        ]);
+        state.next_page();
+
+        let words = |state: &SearchState<WordResult>| -> Vec<String> {
+            state
+                .page_results_iter()
+                .map(|w| w.word.as_str().to_string())
+                .collect()
+        };
+
+        state.sort_results(SortOrder::Alphabetical);
+        assert_eq!(words(&state), vec!["amat", "amicus", "amo"]);
+        state.sort_results(SortOrder::Count);
+        assert_eq!(words(&state), vec!["amicus", "amat", "amo"]);
+        state.sort_results(SortOrder::TextCount);
+        assert_eq!(words(&state), vec!["amat", "amicus", "amo"]);
+        assert_eq!(state.page(), 0);
+    }
+
+    #[test]
+    fn author_results_sort_by_text_count() {
+        let author = |name: &str, texts| AuthorResult {
+            author: svl_core::text::Author {
+                author_id: 0,
+                name: name.to_string(),
+                url: String::new(),
+            },
+            texts,
+        };
+        let mut authors = [author("Cicero", 2), author("Caesar", 2), author("Ovid", 5)];
+
+        authors.sort_by(|a, b| a.compare(b, SortOrder::TextCount));
+        let names: Vec<&str> = authors.iter().map(|a| a.author.name.as_str()).collect();
+        assert_eq!(names, vec!["Ovid", "Caesar", "Cicero"]);
+    }
+
+    #[test]
+    fn search_state_exports_results_as_json() {
+        let mut state = SearchState::<WordResult>::default();
+        state.update_search_results(vec![WordResult {
+            word: "amo".into(),
+            count: 3,
+            texts: 1,
+        }]);
+        let path = std::env::temp_dir().join("svl_ui_export_test.json");
+
+        state.export_json(&path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([{ "word": "amo", "count": 3, "texts": 1 }])
+        );
+    }
+
+    #[test]
+    fn match_range_finds_the_matched_part() {
+        assert_eq!(
+            search("mic", SearchMode::Contains).match_range("amicus"),
+            Some(1..4)
+        );
+        assert_eq!(
+            search("AM", SearchMode::StartsWith).match_range("amicus"),
+            Some(0..2)
+        );
+        assert_eq!(
+            search("us", SearchMode::EndsWith).match_range("amīcus"),
+            Some(5..7)
+        );
+        assert_eq!(
+            search("amo", SearchMode::IsEqual).match_range("amo"),
+            Some(0..3)
+        );
+        assert_eq!(search("xyz", SearchMode::Contains).match_range("amo"), None);
+        assert_eq!(search("^a", SearchMode::Regex).match_range("amo"), None);
+        assert_eq!(
+            Search::new(SearchKind::Word, "AM".into(), SearchMode::StartsWith, true)
+                .match_range("amo"),
+            None
+        );
+    }
+
+    #[test]
+    fn validate_accepts_valid_searches() {
+        assert!(search("amic", SearchMode::StartsWith).validate().is_ok());
+        assert!(search("", SearchMode::IsEqual).validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_empty_terms_for_substring_modes() {
+        for mode in [
+            SearchMode::Contains,
+            SearchMode::EndsWith,
+            SearchMode::StartsWith,
+        ] {
+            assert!(matches!(
+                search("  ", mode).validate(),
+                Err(SearchError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_invalid_regexes() {
+        assert!(search("^am(o|as)$", SearchMode::Regex).validate().is_ok());
+        assert!(matches!(
+            search("am(o|as", SearchMode::Regex).validate(),
+            Err(SearchError::Invalid(msg)) if msg.starts_with("invalid regular expression")
+        ));
+    }
+
+    #[test]
+    fn case_insensitive_regex_keeps_pattern() {
+        let query = search(r"^\w+ō$", SearchMode::Regex).query("word");
+        assert_eq!(query.code, "regex_matches(word, $term)");
+        assert_eq!(query.params["term"], r"(?i)^\w+ō$".into());
+    }
+
+    #[test]
+    fn validate_rejects_overly_long_terms() {
+        let term = "a".repeat(Search::MAX_TERM_LENGTH + 1);
+        assert!(matches!(
+            search(&term, SearchMode::Contains).validate(),
+            Err(SearchError::Invalid(_))
+        ));
+    }
+}